@@ -9,7 +9,7 @@ use image::RgbaImage;
 use nalgebra::Vector2;
 use web_sys::console;
 
-use crate::{camera::Camera, menus::MenusState, world::{World, WorldObject}};
+use crate::{camera::Camera, cross_section::CrossSection, field::surface_distance, menus::MenusState, render_mode::RenderSettings, spatial::MAP_RESOLUTION, world::{World, WorldObject}};
 
 pub struct MainApp {
     /// Behind an `Arc<Mutex<…>>` so we can pass it to [`egui::PaintCallback`] and paint later.
@@ -102,6 +102,8 @@ impl eframe::App for MainApp {
                         ui.add(egui::Slider::new(&mut self.glow_program.lock().background_light_min, 0.01..=1.0).text("Minimum background light"))
                             .on_hover_text("Ranges from 0.01 to 1, defines how much background light is artificially added to the lab");
 
+                        self.menus.render_mode_menu(ui, &mut self.glow_program.lock().render_settings);
+
                         let selected_object_text: String;
 
                         if self.glow_program.lock().currently_selected_object == 0 {
@@ -146,10 +148,43 @@ impl eframe::App for MainApp {
 
                     if self.glow_program.lock().currently_selected_object != 0 {
                         egui::Window::new("Object inspector").show(ctx, |ui| {
-                            self.menus.inspect_object_menu(ui, &mut self.world, self.time, &mut self.glow_program.lock().currently_selected_object);
+                            let cube_scaling_factor = self.glow_program.lock().cube_scaling_factor;
+                            self.menus.inspect_object_menu(ui, &mut self.world, self.time, cube_scaling_factor, &mut self.glow_program.lock().currently_selected_object);
                         });
                     }
 
+                    let cube_scaling_factor = self.glow_program.lock().cube_scaling_factor;
+                    self.menus.detector_windows(ctx, &self.world, cube_scaling_factor);
+
+                    egui::Window::new("Parameter sweep").default_open(false).show(ctx, |ui| {
+                        self.menus.sweep_menu(ui, &self.world, cube_scaling_factor);
+                    });
+
+                    egui::Window::new("Optimizer").default_open(false).show(ctx, |ui| {
+                        self.menus.optimizer_menu(ui, &mut self.world, &mut self.glow_program.lock().cube_scaling_factor);
+                    });
+
+                    egui::Window::new("Line profile").default_open(false).show(ctx, |ui| {
+                        self.menus.line_profile_menu(ui, &self.world, cube_scaling_factor);
+                    });
+
+                    egui::Window::new("Cross-section").default_open(false).show(ctx, |ui| {
+                        let selected_object = self.glow_program.lock().currently_selected_object;
+                        let selected_center = (selected_object != 0).then(|| self.world.objects[selected_object].center);
+
+                        let cube_scaling_factor = self.glow_program.lock().cube_scaling_factor;
+
+                        self.menus.cross_section_menu(ui, &self.world, &mut self.glow_program.lock().cross_section, selected_center, self.camera.position, cube_scaling_factor);
+                    });
+
+                    egui::Window::new("Beam paths").default_open(false).show(ctx, |ui| {
+                        self.menus.beam_paths_menu(ui, &self.world);
+                    });
+
+                    egui::Window::new("Materials").default_open(false).show(ctx, |ui| {
+                        self.menus.materials_menu(ui, &mut self.world);
+                    });
+
                     egui::Window::new("Info").show(ctx, |ui| {
                         self.menus.info_menu(ui);
                     });
@@ -238,7 +273,23 @@ impl MainApp {
 
             let object_found_index = objects_found[((((current_texture_resolution[1] - texture_coordinates_hover_pos[1]) * current_texture_resolution[0]) + texture_coordinates_hover_pos[0]) * 4) as usize];
 
-            if !self.menus.trying_to_align_to_object {
+            if self.menus.picking_profile_point {
+                let hover_pos = curr_response.hover_pos().unwrap();
+                let screen_position = [
+                    2.0 * hover_pos.x / rect.right_bottom().x - 1.0,
+                    2.0 * (current_texture_resolution[1] as f32 / current_texture_resolution[0] as f32) * (1.0 - hover_pos.y / rect.right_bottom().y) - 1.0
+                ];
+
+                let direction = self.camera.ray_direction(screen_position);
+                let object_index = object_found_index as usize;
+
+                if object_index != 0 {
+                    if let Some(distance) = surface_distance(&self.world.objects[object_index], self.camera.position, direction) {
+                        self.menus.add_profile_point(object_index, self.camera.position + direction * distance);
+                    }
+                }
+
+            } else if !self.menus.trying_to_align_to_object {
                 self.glow_program.lock().currently_selected_object = object_found_index as usize;
 
             } else if object_found_index == 0 || object_found_index as usize == self.glow_program.lock().currently_selected_object || object_found_index as usize == self.world.objects[self.glow_program.lock().currently_selected_object].object_aligned_to_self {
@@ -292,6 +343,12 @@ impl MainApp {
         }
 
         self.time += 0.01;
+        self.glow_program.lock().render_settings.advance(0.01);
+
+        // electro-optic elements follow their drive, keep repainting while it changes
+        if self.world.drive_electro_optic_elements(self.time) {
+            ui.ctx().request_repaint();
+        }
 
         self.camera.look_direction += response.drag_motion() * 0.01;
         self.camera.look_direction.y = self.camera.look_direction.y.clamp(-1.4, 1.4);
@@ -311,6 +368,23 @@ impl MainApp {
             callback: Arc::new(cb),
         };
         ui.painter().add(callback);
+
+        // the segment of the line profile on top of the lab
+        let to_screen = |point| self.camera.screen_position(point).map(|[x, y]| egui::Pos2::new(
+            (x + 1.0) / 2.0 * rect.right_bottom().x,
+            rect.right_bottom().y * (1.0 - (y + 1.0) * current_texture_resolution[0] as f32 / (2.0 * current_texture_resolution[1] as f32))
+        ));
+
+        let profile_points: Vec<egui::Pos2> = self.menus.profile_points().iter().filter_map(|point| to_screen(*point)).collect();
+        for point in &profile_points {
+            ui.painter().circle_filled(*point, 4.0, Color32::YELLOW);
+        }
+        if let [start, end] = profile_points[..] {
+            ui.painter().line_segment([start, end], egui::Stroke::new(2.0, Color32::YELLOW));
+        }
+
+        let cube_scaling_factor = self.glow_program.lock().cube_scaling_factor;
+        self.menus.paint_beam_paths(ui.painter(), &self.world, to_screen, self.camera.position, cube_scaling_factor, self.time);
     }
 }
 
@@ -324,7 +398,9 @@ pub struct MainGlowProgram {
     pub desired_scaling_factor: f32,
     pub cube_scaling_factor: f32,
     pub currently_selected_object: usize,
-    pub background_light_min: f32
+    pub background_light_min: f32,
+    pub cross_section: CrossSection,
+    pub render_settings: RenderSettings
 }
 
 #[allow(unsafe_code)] // we need unsafe code to use glow
@@ -471,6 +547,8 @@ impl MainGlowProgram {
                 cube_scaling_factor: 2.0,
                 currently_selected_object: 0,
                 background_light_min: 0.5,
+                cross_section: CrossSection::new(),
+                render_settings: RenderSettings::new(),
             })
         }
     }
@@ -547,6 +625,59 @@ impl MainGlowProgram {
                 self.background_light_min
             );
 
+            gl.uniform_1_u32(
+                gl.get_uniform_location(self.main_image_program, "render_mode").as_ref(),
+                self.render_settings.mode as u32
+            );
+
+            gl.uniform_1_u32(
+                gl.get_uniform_location(self.main_image_program, "render_colormap").as_ref(),
+                self.render_settings.colormap as u32
+            );
+
+            gl.uniform_1_f32(
+                gl.get_uniform_location(self.main_image_program, "render_exposure").as_ref(),
+                self.render_settings.exposure
+            );
+
+            gl.uniform_1_f32(
+                gl.get_uniform_location(self.main_image_program, "field_time").as_ref(),
+                self.render_settings.field_time
+            );
+
+            gl.uniform_1_u32(
+                gl.get_uniform_location(self.main_image_program, "slice_mode").as_ref(),
+                self.cross_section.mode as u32
+            );
+
+            gl.uniform_3_f32(
+                gl.get_uniform_location(self.main_image_program, "slice_center").as_ref(),
+                self.cross_section.center[0],
+                self.cross_section.center[1],
+                self.cross_section.center[2]
+            );
+
+            gl.uniform_2_f32(
+                gl.get_uniform_location(self.main_image_program, "slice_rotation").as_ref(),
+                self.cross_section.rotation[0],
+                self.cross_section.rotation[1]
+            );
+
+            gl.uniform_1_f32(
+                gl.get_uniform_location(self.main_image_program, "slice_size").as_ref(),
+                self.cross_section.size
+            );
+
+            gl.uniform_1_f32(
+                gl.get_uniform_location(self.main_image_program, "slice_opacity").as_ref(),
+                self.cross_section.opacity
+            );
+
+            gl.uniform_1_f32(
+                gl.get_uniform_location(self.main_image_program, "slice_exposure").as_ref(),
+                self.cross_section.exposure
+            );
+
             gl.uniform_1_f32(
                 gl.get_uniform_location(self.main_image_program, "cube_scaling_factor").as_ref(),
                 self.cube_scaling_factor
@@ -580,9 +711,42 @@ impl MainGlowProgram {
                 texture_resolution[0] as f32
             );
 
-            gl.uniform_1_u32_slice(
+            // main.frag reads the definitions as uvec4s, so the list has to be
+            // padded to a multiple of 4
+            let mut objects_definitions = world.get_gpu_compatible_world_objects_list();
+            objects_definitions.resize(objects_definitions.len().div_ceil(4) * 4, 0);
+
+            gl.uniform_4_u32_slice(
                 gl.get_uniform_location(self.main_image_program, "objects_definitions").as_ref(),
-                world.get_gpu_compatible_world_objects_list().as_slice()
+                objects_definitions.as_slice()
+            );
+
+            gl.uniform_1_u32_slice(
+                gl.get_uniform_location(self.main_image_program, "medium_definition").as_ref(),
+                world.get_gpu_compatible_medium().as_slice()
+            );
+
+            gl.uniform_4_u32_slice(
+                gl.get_uniform_location(self.main_image_program, "coating_materials_definitions").as_ref(),
+                world.get_gpu_compatible_coating_materials().as_slice()
+            );
+
+            // maps of the image elements, one layer each, texture unit 2
+            let (element_maps, element_map_layers) = world.get_gpu_compatible_element_maps();
+            let element_maps_texture = gl.create_texture().unwrap();
+
+            gl.active_texture(glow::TEXTURE2);
+            gl.bind_texture(glow::TEXTURE_2D_ARRAY, Some(element_maps_texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_image_3d(glow::TEXTURE_2D_ARRAY, 0, glow::RGBA8 as i32, MAP_RESOLUTION as i32, MAP_RESOLUTION as i32, element_map_layers as i32, 0, glow::RGBA, glow::UNSIGNED_BYTE, Some(element_maps.as_slice()));
+            gl.tex_parameter_i32(glow::TEXTURE_2D_ARRAY, glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D_ARRAY, glow::TEXTURE_MAG_FILTER, glow::NEAREST as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D_ARRAY, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D_ARRAY, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
+
+            gl.uniform_1_i32(
+                gl.get_uniform_location(self.main_image_program, "element_maps").as_ref(),
+                2
             );
 
             gl.clear_color(0.1, 0.1, 0.1, 1.0);
@@ -667,6 +831,7 @@ impl MainGlowProgram {
             // probably not the most efficient but oh well
             gl.delete_texture(color_buffer);
             gl.delete_texture(object_found);
+            gl.delete_texture(element_maps_texture);
             gl.delete_renderbuffer(rbo);
             gl.delete_framebuffer(framebuffer)
         }
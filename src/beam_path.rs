@@ -0,0 +1,117 @@
+// The path of the beam of every light drawn on top of the lab, its axis goes straight
+// from the light through every optical object it crosses until something solid stops it
+// or it leaves the world, with the 1/e² radius of the gaussian beam around it and the
+// polarization between the elements, the beams out of gratings, prisms and displacers
+// aren't followed
+use std::f32::consts::PI;
+
+use nalgebra::{Complex, Vector2, Vector3};
+
+use crate::{coatings::coating_jones_matrix, field::{blocking_distance, disk_crossing, jones_matrix_for_light, light_direction, light_transverse_axes, BEAM_WAIST}, polarimetry::{instantaneous_field, stokes_parameters}, spatial::element_at, world::{ObjectType, World, WorldObject}};
+
+// same as WORLD_SIZE in main.frag
+const WORLD_SIZE: f32 = 200.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeamSegment {
+    pub start: Vector3<f32>,
+    pub end: Vector3<f32>,
+    // distance from the light to the start of the segment, in cubes
+    pub start_distance: f32,
+    // jones vector of the light along the segment, in the basis of the light
+    pub polarization: Vector2<Complex<f32>>,
+    // the object the segment ends at, None if it leaves the world
+    pub end_index: Option<usize>
+}
+
+// distance along `direction` from a point inside the world to its walls
+fn world_exit_distance(origin: Vector3<f32>, direction: Vector3<f32>) -> f32 {
+    (0..3).filter(|axis| direction[*axis].abs() > 1e-6)
+        .map(|axis| {
+            let wall = if direction[axis] > 0.0 { WORLD_SIZE } else { 0.0 };
+            (wall - origin[axis]) / direction[axis]
+        })
+        .fold(f32::INFINITY, f32::min)
+        .max(0.0)
+}
+
+// segments of the beam of the light at `light_index`, one between every pair of objects
+// it crosses, it stops at the first solid object or once nothing gets through
+pub fn beam_path(world: &World, light_index: usize) -> Vec<BeamSegment> {
+    let light = world.objects[light_index];
+    let direction = light_direction(&light);
+    let origin = Vector3::from(light.center);
+    let medium_index = world.medium_refractive_index(light.material_wavelength);
+
+    let mut end = (world_exit_distance(origin, direction), None);
+    let mut crossed: Vec<(f32, usize)> = vec![];
+
+    for index in world.objects_associations.keys().filter(|index| **index != light_index) {
+        let object = &world.objects[*index];
+
+        if object.object_type == ObjectType::OpticalObjectRoundWall || object.object_type == ObjectType::Aperture {
+            if let Some(distance) = disk_crossing(object, origin, direction) {
+                crossed.push((distance, *index));
+            }
+
+        } else if let Some(distance) = blocking_distance(object, origin, direction).filter(|distance| *distance < end.0) {
+            end = (distance, Some(*index));
+        }
+    }
+
+    crossed.retain(|(distance, _)| *distance < end.0);
+    crossed.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let initial_intensity = stokes_parameters(light.polarization)[0];
+    let mut polarization = light.polarization;
+    let mut start_distance = 0.0;
+    let mut segments = vec![];
+
+    for (distance, index) in crossed {
+        segments.push(BeamSegment { start: origin + direction * start_distance, end: origin + direction * distance, start_distance, polarization, end_index: Some(index) });
+
+        let object = world.objects[index];
+        if object.object_type == ObjectType::OpticalObjectRoundWall {
+            let element = element_at(&object, origin + direction * distance, world.element_maps.get(&index));
+            polarization = jones_matrix_for_light(&element, &light, direction) * coating_jones_matrix(&object, &light, direction, &world.materials, medium_index) * polarization;
+        }
+
+        start_distance = distance;
+
+        // blocked by crossed polarizers and such
+        if stokes_parameters(polarization)[0] <= 1e-6 * initial_intensity {
+            return segments;
+        }
+    }
+
+    segments.push(BeamSegment { start: origin + direction * start_distance, end: origin + direction * end.0, start_distance, polarization, end_index: end.1 });
+
+    segments
+}
+
+// 1/e² radius of the intensity of the beam of `light` a distance (in cubes) away from its
+// waist, in cubes, w(z) of gaussian_beam_amplitude_along in field.rs
+pub fn beam_radius(light: &WorldObject, distance: f32, refractive_index: f32, cube_scaling_factor: f32) -> f32 {
+    let z = distance * cube_scaling_factor;
+    let z_r = (PI * BEAM_WAIST * BEAM_WAIST * refractive_index) / light.wavelength;
+
+    BEAM_WAIST * (1.0 + (z / z_r).powi(2)).sqrt() / cube_scaling_factor
+}
+
+// the polarization ellipse of a segment drawn around `center` in the plane perpendicular
+// to the beam, `size` is its semi-major axis in cubes
+pub fn polarization_glyph(light: &WorldObject, polarization: Vector2<Complex<f32>>, center: Vector3<f32>, size: f32, samples: usize) -> Vec<Vector3<f32>> {
+    // S0 = a² + b² and √(S1² + S2²) = a² - b² for the semi-axes a and b of the ellipse
+    let [s0, s1, s2, _] = stokes_parameters(polarization);
+    let amplitude = ((s0 + (s1 * s1 + s2 * s2).sqrt()) / 2.0).sqrt();
+    if amplitude <= 0.0 {
+        return vec![];
+    }
+
+    let [x_axis, y_axis] = light_transverse_axes(light);
+
+    (0..=samples).map(|i| {
+        let field = instantaneous_field(polarization, 2.0 * PI * i as f32 / samples as f32) * (size / amplitude);
+        center + x_axis * field.x + y_axis * field.y
+    }).collect()
+}
@@ -39,6 +39,26 @@ impl Camera {
         }
     }
 
+    // direction of the ray main.frag casts through `screen_position`, which goes from -1
+    // to 1 along the width of the viewport and as far as its aspect ratio lets it along
+    // its height (from the bottom)
+    pub fn ray_direction(&self, screen_position: [f32; 2]) -> Vector3<f32> {
+        let ray_dir = Vector3::new(screen_position[0], screen_position[1], 1.0);
+
+        rotate3d_y(rotate3d_x(ray_dir, self.look_direction.y), self.look_direction.x).normalize()
+    }
+
+    // where `point` ends up on the screen, same coordinates as above, None if it's behind
+    pub fn screen_position(&self, point: Vector3<f32>) -> Option<[f32; 2]> {
+        let local = rotate3d_x(rotate3d_y(point - self.position, -self.look_direction.x), -self.look_direction.y);
+
+        if local.z <= 1e-4 {
+            return None;
+        }
+
+        Some([local.x / local.z, local.y / local.z])
+    }
+
     pub fn update(&mut self, key: egui::Key) {
         let mut movement: Vector3<f32> = Vector3::new(0.0, 0.0, 0.0);
         let mut horizontal_movement = 0.0;
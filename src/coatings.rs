@@ -0,0 +1,248 @@
+// Thin-film coatings, reflectance and transmittance of a stack of layers with the transfer matrix method
+use std::f32::consts::PI;
+
+use nalgebra::{Complex, Matrix2, Vector3};
+
+use crate::{field::light_transverse_axes, materials::{MaterialLibrary, MaterialName}, world::WorldObject};
+
+// must match MAX_COATING_LAYERS and MAX_COATING_MATERIALS in main.frag, only
+// the first MAX_COATING_MATERIALS different materials used by coatings make it
+// to the gpu, the rest act like vacuum there
+pub const MAX_COATING_LAYERS: usize = 10;
+pub const MAX_COATING_MATERIALS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoatingLayer {
+    pub material: MaterialName,
+    // in micrometers, same as the wavelengths the materials read
+    pub thickness: f32
+}
+
+// the layers go from the outside (where the light comes from) to the surface,
+// WorldObject has to be Copy so they are stored in a fixed buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coating {
+    layers: [CoatingLayer; MAX_COATING_LAYERS],
+    layer_count: usize
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CoatingResponse {
+    // for s and p light
+    pub reflectance: [f32; 2],
+    pub transmittance: [f32; 2],
+    // amplitudes scaled so that their squares are the reflectance and transmittance
+    pub reflection: [Complex<f32>; 2],
+    pub transmission: [Complex<f32>; 2]
+}
+
+impl Coating {
+    pub fn new() -> Coating {
+        Coating {
+            layers: [CoatingLayer { material: MaterialName::new("MgF2"), thickness: 0.1 }; MAX_COATING_LAYERS],
+            layer_count: 0
+        }
+    }
+
+    pub fn layers(&self) -> &[CoatingLayer] {
+        &self.layers[..self.layer_count]
+    }
+
+    pub fn layers_mut(&mut self) -> &mut [CoatingLayer] {
+        &mut self.layers[..self.layer_count]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layer_count == 0
+    }
+
+    // returns false if there's no room left for it
+    pub fn push(&mut self, layer: CoatingLayer) -> bool {
+        if self.layer_count == MAX_COATING_LAYERS {
+            return false;
+        }
+
+        self.layers[self.layer_count] = layer;
+        self.layer_count += 1;
+        true
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.layer_count {
+            self.layers.copy_within((index + 1)..self.layer_count, index);
+            self.layer_count -= 1;
+        }
+    }
+
+    // refractive index and thickness of every layer at the wavelength (in micrometers)
+    pub fn indices(&self, materials: &MaterialLibrary, wavelength: f32) -> Vec<(f32, f32)> {
+        self.layers().iter()
+            .map(|layer| (materials.refractive_index(&layer.material, wavelength), layer.thickness))
+            .collect()
+    }
+}
+
+// a layer with an optical thickness of a quarter of the design wavelength
+pub fn quarter_wave_layer(material: &str, materials: &MaterialLibrary, design_wavelength: f32) -> CoatingLayer {
+    let material = MaterialName::new(material);
+
+    CoatingLayer {
+        material,
+        thickness: design_wavelength / (4.0 * materials.refractive_index(&material, design_wavelength))
+    }
+}
+
+// single layer of magnesium fluoride, the classic anti-reflection coating of lenses
+pub fn anti_reflection_coating(materials: &MaterialLibrary, design_wavelength: f32) -> Coating {
+    let mut coating = Coating::new();
+    coating.push(quarter_wave_layer("MgF2", materials, design_wavelength));
+    coating
+}
+
+// alternating quarter-wave layers of a high and a low index material, the reflections
+// of every interface add up in phase around the design wavelength
+pub fn dielectric_mirror(materials: &MaterialLibrary, design_wavelength: f32) -> Coating {
+    let mut coating = Coating::new();
+
+    for _ in 0..(MAX_COATING_LAYERS / 2) {
+        coating.push(quarter_wave_layer("TiO2 (film)", materials, design_wavelength));
+        coating.push(quarter_wave_layer("MgF2", materials, design_wavelength));
+    }
+
+    coating
+}
+
+// a film of water, the object it's on should be made of air so that there's
+// air on both sides of it
+pub fn soap_film(thickness: f32) -> Coating {
+    let mut coating = Coating::new();
+    coating.push(CoatingLayer { material: MaterialName::new("Water"), thickness });
+    coating
+}
+
+// response of a stack of layers (refractive index, thickness) between two media, the
+// light comes from the first one at an angle whose cosine is `cos_incidence`, every
+// layer gets a characteristic matrix [cos δ, i sin δ / η; i η sin δ, cos δ] with
+// δ = 2π n d cos θ / λ and η = n cos θ for s light and n / cos θ for p light
+pub fn stack_response(layers: &[(f32, f32)], incident_index: f32, exit_index: f32, cos_incidence: f32, wavelength: f32) -> CoatingResponse {
+    let invariant = incident_index * (1.0 - cos_incidence.powi(2)).max(0.0).sqrt();
+    // n cos θ, imaginary when the light can't get into the layer
+    let n_cos = |n: f32| Complex::new(n.powi(2) - invariant.powi(2), 0.0).sqrt();
+
+    let [s, p] = [false, true].map(|p_light| {
+        let admittance = |n: f32| if p_light { Complex::new(n.powi(2), 0.0) / n_cos(n) } else { n_cos(n) };
+
+        let characteristic_matrix = layers.iter().fold(Matrix2::<Complex<f32>>::identity(), |matrix, &(n, thickness)| {
+            let phase = n_cos(n) * (2.0 * PI * thickness / wavelength);
+            let eta = admittance(n);
+            let i = Complex::<f32>::i();
+
+            matrix * Matrix2::new(
+                phase.cos(), i * phase.sin() / eta,
+                i * eta * phase.sin(), phase.cos()
+            )
+        });
+
+        let incident_admittance = admittance(incident_index);
+        let exit_admittance = admittance(exit_index);
+
+        let b = characteristic_matrix[(0, 0)] + characteristic_matrix[(0, 1)] * exit_admittance;
+        let c = characteristic_matrix[(1, 0)] + characteristic_matrix[(1, 1)] * exit_admittance;
+        let denominator = incident_admittance * b + c;
+
+        let reflection = (incident_admittance * b - c) / denominator;
+        let transmittance = 4.0 * incident_admittance.re * exit_admittance.re / denominator.norm_sqr();
+        let transmission = incident_admittance * 2.0 / denominator;
+
+        (reflection, transmittance, Complex::from_polar(transmittance.max(0.0).sqrt(), transmission.arg()))
+    });
+
+    CoatingResponse {
+        reflectance: [s.0.norm_sqr(), p.0.norm_sqr()],
+        transmittance: [s.1, p.1],
+        reflection: [s.0, p.0],
+        transmission: [s.2, p.2]
+    }
+}
+
+// same stack seen from the other side
+pub fn reversed_layers(layers: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    layers.iter().rev().copied().collect()
+}
+
+// transmission of the coating of a thin optical object written in the basis of the
+// light going through it along `propagation`, the light goes from the medium into
+// the material of the object, identity if it has no coating
+pub fn coating_jones_matrix(object: &WorldObject, light: &WorldObject, propagation: Vector3<f32>, materials: &MaterialLibrary, medium_index: f32) -> Matrix2<Complex<f32>> {
+    if object.coating.is_empty() {
+        return Matrix2::identity();
+    }
+
+    let [horizontal, normal, _] = object.local_axes();
+    let response = stack_response(
+        &object.coating.indices(materials, light.material_wavelength),
+        medium_index,
+        materials.refractive_index(&object.material, light.material_wavelength),
+        propagation.dot(&normal).abs(),
+        light.material_wavelength
+    );
+
+    // s light is polarized perpendicular to the plane of incidence
+    let mut s_axis = normal.cross(&propagation);
+    if s_axis.norm() < 1e-4 {
+        s_axis = horizontal;
+    }
+
+    let [light_x, light_y] = light_transverse_axes(light);
+    let angle = s_axis.dot(&light_y).atan2(s_axis.dot(&light_x));
+    let (sin, cos) = angle.sin_cos();
+
+    let rotation = Matrix2::new(cos, -sin, sin, cos).map(|x| Complex::new(x, 0.0));
+    let transmission = Matrix2::new(response.transmission[0], Complex::new(0.0, 0.0), Complex::new(0.0, 0.0), response.transmission[1]);
+
+    rotation * transmission * rotation.transpose()
+}
+
+// rough rgb of a wavelength (in micrometers) of the visible spectrum
+pub fn wavelength_color(wavelength: f32) -> [f32; 3] {
+    let nm = wavelength * 1000.0;
+
+    let color = match nm {
+        nm if (380.0..440.0).contains(&nm) => [(440.0 - nm) / 60.0, 0.0, 1.0],
+        nm if (440.0..490.0).contains(&nm) => [0.0, (nm - 440.0) / 50.0, 1.0],
+        nm if (490.0..510.0).contains(&nm) => [0.0, 1.0, (510.0 - nm) / 20.0],
+        nm if (510.0..580.0).contains(&nm) => [(nm - 510.0) / 70.0, 1.0, 0.0],
+        nm if (580.0..645.0).contains(&nm) => [1.0, (645.0 - nm) / 65.0, 0.0],
+        nm if (645.0..=780.0).contains(&nm) => [1.0, 0.0, 0.0],
+        _ => [0.0, 0.0, 0.0]
+    };
+
+    // the eye is less sensitive at the edges of the spectrum
+    let sensitivity = match nm {
+        nm if nm < 420.0 => 0.3 + 0.7 * (nm - 380.0) / 40.0,
+        nm if nm > 700.0 => 0.3 + 0.7 * (780.0 - nm) / 80.0,
+        _ => 1.0
+    };
+
+    color.map(|channel| channel * sensitivity.max(0.0))
+}
+
+// color of white light after being weighted by `response` (reflectance, transmittance...)
+// across the visible spectrum, what thin-film interference colors look like
+pub fn spectrum_color(response: impl Fn(f32) -> f32) -> [f32; 3] {
+    let samples = 100;
+    let mut color = [0.0; 3];
+    let mut white = [0.0; 3];
+
+    for i in 0..=samples {
+        let wavelength = 0.38 + 0.4 * i as f32 / samples as f32;
+        let weight = response(wavelength);
+
+        for (channel, value) in wavelength_color(wavelength).into_iter().enumerate() {
+            color[channel] += value * weight;
+            white[channel] += value;
+        }
+    }
+
+    [0, 1, 2].map(|channel| (color[channel] / white[channel]).clamp(0.0, 1.0))
+}
@@ -0,0 +1,104 @@
+// Cross-section plane, a square slice of the lab where main.frag shows the summed field
+// of the lights in free space (the intensity, the phase or the polarization state) on top
+// of whatever is behind it, the rendering happens in main.frag, these are the cpu side
+// versions of its geometry and colors
+use std::f32::consts::PI;
+use std::fmt::{self, Display, Formatter};
+
+use nalgebra::Vector3;
+
+use crate::{camera::{rotate3d_x, rotate3d_y}, polarimetry::{fields_by_wavelength, total_stokes}, world::World};
+
+// must match the constants in main.frag
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum CrossSectionMode {
+    Off = 0,
+    Intensity = 1,
+    Phase = 2,
+    Polarization = 3
+}
+
+// Needed for the drop down list
+impl Display for CrossSectionMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "Off"),
+            Self::Intensity => write!(f, "Intensity"),
+            Self::Phase => write!(f, "Phase"),
+            Self::Polarization => write!(f, "Polarization state"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct CrossSection {
+    pub mode: CrossSectionMode,
+    pub center: [f32; 3],
+    // same as the rotation of the objects, the plane faces its normal
+    pub rotation: [f32; 2],
+    // side of the square in cubes
+    pub size: f32,
+    pub opacity: f32,
+    // intensity that shows up as full brightness is 1 / exposure
+    pub exposure: f32
+}
+
+impl CrossSection {
+    pub fn new() -> CrossSection {
+        CrossSection { mode: CrossSectionMode::Off, center: [12.5, 12.5, 12.5], rotation: [0.0, 0.0], size: 6.0, opacity: 0.7, exposure: 0.25 }
+    }
+
+    // [horizontal, normal, vertical] like WorldObject::local_axes, slice_crossing in main.frag
+    pub fn local_axes(&self) -> [Vector3<f32>; 3] {
+        [Vector3::x(), Vector3::y(), Vector3::z()].map(|axis| rotate3d_y(rotate3d_x(axis, self.rotation[1]), self.rotation[0]))
+    }
+
+    // point of the square at (x, y) from its center along its horizontal and vertical axes
+    pub fn point_at(&self, x: f32, y: f32) -> Vector3<f32> {
+        let [horizontal, _, vertical] = self.local_axes();
+
+        Vector3::from(self.center) + horizontal * x + vertical * y
+    }
+}
+
+// stokes parameters of everything that gets to `point` and the phase of the strongest
+// component of the field of the first wavelength, slice_fields in main.frag
+pub fn section_fields(world: &World, point: Vector3<f32>, cube_scaling_factor: f32) -> ([f32; 4], f32) {
+    let fields = fields_by_wavelength(world, point, cube_scaling_factor, None);
+
+    let phase = fields.first().map_or(0.0, |(_, field)| {
+        if field[0].norm() >= field[1].norm() { field[0].arg() } else { field[1].arg() }
+    });
+
+    (total_stokes(&fields), phase)
+}
+
+// hue from 0 to 1 with full saturation, hsv_to_rgb in main.frag
+pub fn hue_color(hue: f32, value: f32) -> [f32; 3] {
+    [1.0, 2.0 / 3.0, 1.0 / 3.0].map(|offset: f32| {
+        let channel = (((hue + offset).fract() * 6.0 - 3.0).abs() - 1.0).clamp(0.0, 1.0);
+        value * channel
+    })
+}
+
+// slice_color in main.frag
+// - intensity goes from black through red and yellow to white
+// - phase is the hue, dark where there's no light
+// - polarization maps (s1, s2, s3) to (r, g, b), horizontal is red, diagonal is green and
+//   right circular is blue, with the brightness of the intensity
+pub fn section_color(mode: CrossSectionMode, stokes: [f32; 4], phase: f32, exposure: f32) -> [f32; 3] {
+    let brightness = (stokes[0] * exposure).clamp(0.0, 1.0);
+
+    match mode {
+        CrossSectionMode::Off => [0.0; 3],
+        CrossSectionMode::Intensity => [0.0, 1.0, 2.0].map(|threshold| (3.0 * brightness - threshold).clamp(0.0, 1.0)),
+        CrossSectionMode::Phase => hue_color((phase + PI) / (2.0 * PI), brightness),
+        CrossSectionMode::Polarization => {
+            if stokes[0] <= 0.0 {
+                return [0.0; 3];
+            }
+
+            [stokes[1], stokes[2], stokes[3]].map(|s| brightness * (1.0 + s / stokes[0]) / 2.0)
+        }
+    }
+}
@@ -1,8 +1,8 @@
-use std::fmt::{self, Display, Formatter};
+use std::{f32::consts::PI, fmt::{self, Display, Formatter}};
 use egui::Color32;
 use nalgebra::{Complex, Matrix2, Vector2, Vector3};
 
-use crate::world::{self, LightPolarizationType, ObjectType, World, WorldObject};
+use crate::{materials::{MaterialLibrary, MaterialName}, prism::minimum_deviation, world::{self, ApertureType, GratingType, LightPolarizationType, ObjectType, PolarizerType, StressField, World, WorldObject}};
 
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Demo {
@@ -12,7 +12,12 @@ pub enum Demo {
     DoubleSlit,
     TripleSlit,
     UncoordinatedInterference,
-    CoordinatedInterference
+    CoordinatedInterference,
+    DiffractionGrating,
+    OpticalIsolator,
+    Prism,
+    DoubleRefraction,
+    Photoelasticity
 }
 
 // Needed for the drop down list
@@ -26,6 +31,11 @@ impl Display for Demo {
             Self::TripleSlit => write!(f, "Triple slit demo"),
             Self::UncoordinatedInterference => write!(f, "Uncoordinated interference demo"),
             Self::CoordinatedInterference => write!(f, "Coordinated interference demo"),
+            Self::DiffractionGrating => write!(f, "Diffraction grating demo"),
+            Self::OpticalIsolator => write!(f, "Optical isolator demo"),
+            Self::Prism => write!(f, "Dispersive prism demo"),
+            Self::DoubleRefraction => write!(f, "Double refraction demo"),
+            Self::Photoelasticity => write!(f, "Photoelasticity demo"),
         }
     }
 }
@@ -54,8 +64,8 @@ pub fn light_profile() -> World {
 pub fn simple_interference_demo() -> World {
     let mut demo_world = World::new();
 
-    let mut demo_red_light = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [13.749462, 13.868861, 16.94075], color: Color32::from_rgb(35, 1, 1), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::FRONT, aligned_distance: 0.5, object_aligned_to_self: 0, wavelength: 0.001 };
-    let mut demo_blue_light = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [13.749462, 13.868861, 16.94075], color: Color32::from_rgb(1, 1, 35), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::FRONT, aligned_distance: 0.0, object_aligned_to_self: 9, wavelength: 0.001 };
+    let mut demo_red_light = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [13.749462, 13.868861, 16.94075], color: Color32::from_rgb(35, 1, 1), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::FRONT, aligned_distance: 0.5, object_aligned_to_self: 0, wavelength: 0.001, ..WorldObject::new() };
+    let mut demo_blue_light = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [13.749462, 13.868861, 16.94075], color: Color32::from_rgb(1, 1, 35), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::FRONT, aligned_distance: 0.0, object_aligned_to_self: 9, wavelength: 0.001, ..WorldObject::new() };
     demo_world.aligned_objects.insert(9);
 
     demo_red_light.set_light_polarization();
@@ -70,15 +80,15 @@ pub fn simple_interference_demo() -> World {
 pub fn double_slit_demo() -> World {
     let mut demo_world = World::new();
 
-    let mut demo_red_light = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [13.749462, 13.868861, 16.94075], color: Color32::from_rgb(35, 1, 1), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: 0.5, object_aligned_to_self: 0, wavelength: 0.05 };
-    let mut demo_blue_light = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [13.749462, 13.868861, 16.94075], color: Color32::from_rgb(1, 1, 35), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::FRONT, aligned_distance: 0.0, object_aligned_to_self: 9, wavelength: 0.05 };
-    demo_world.aligned_objects.insert(9);
+    // a single light going through an opaque screen with two slits,
+    // the fringes show up on the wall in front of the camera
+    let mut demo_light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [13.5, 13.5, 11.0], color: Color32::from_rgb(35, 1, 1), radius: 0.1, polarization_type: LightPolarizationType::LinearHorizontal, wavelength: 0.02, ..WorldObject::new() };
+    let demo_slits = WorldObject { object_type: ObjectType::Aperture, rotation: [0.0, PI / 2.0], center: [13.5, 13.5, 12.0], color: Color32::from_rgb(60, 60, 60), radius: 0.5, aperture_type: ApertureType::MultipleSlits, aperture_width: 0.15, aperture_height: 0.8, slit_count: 2, slit_pitch: 0.45, ..WorldObject::new() };
 
-    demo_red_light.set_light_polarization();
-    demo_blue_light.set_light_polarization();
+    demo_light.set_light_polarization();
 
-    demo_world.insert_object(Vector3::from_vec(demo_red_light.center.into_iter().map(|x| x as i32).collect()), demo_red_light).unwrap();
-    demo_world.insert_object(Vector3::from_vec(demo_blue_light.center.into_iter().map(|x| x as i32).collect()), demo_blue_light).unwrap();
+    demo_world.insert_object(Vector3::from_vec(demo_light.center.into_iter().map(|x| x as i32).collect()), demo_light).unwrap();
+    demo_world.insert_object(Vector3::from_vec(demo_slits.center.into_iter().map(|x| x as i32).collect()), demo_slits).unwrap();
 
     return demo_world
 }
@@ -86,19 +96,13 @@ pub fn double_slit_demo() -> World {
 pub fn triple_slit_demo() -> World {
     let mut demo_world = World::new();
 
-    let mut demo_red_light = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [13.749462, 13.868861, 16.94075], color: Color32::from_rgb(35, 1, 1), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: 0.5, object_aligned_to_self: 0, wavelength: 0.1 };
-    let mut demo_green_light = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [13.749462, 13.868861, 16.94075], color: Color32::from_rgb(1, 35, 1), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::FRONT, aligned_distance: 0.0, object_aligned_to_self: 7, wavelength: 0.1 };
-    let mut demo_blue_light = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [13.749462, 13.868861, 16.94075], color: Color32::from_rgb(1, 1, 35), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.5, object_aligned_to_self: 0, wavelength: 0.1 };
-    demo_world.aligned_objects.insert(7);
-    demo_world.aligned_objects.insert(9);
+    let mut demo_light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [13.5, 13.5, 11.0], color: Color32::from_rgb(1, 35, 1), radius: 0.1, polarization_type: LightPolarizationType::LinearHorizontal, wavelength: 0.02, ..WorldObject::new() };
+    let demo_slits = WorldObject { object_type: ObjectType::Aperture, rotation: [0.0, PI / 2.0], center: [13.5, 13.5, 12.0], color: Color32::from_rgb(60, 60, 60), radius: 0.5, aperture_type: ApertureType::MultipleSlits, aperture_width: 0.15, aperture_height: 0.8, slit_count: 3, slit_pitch: 0.45, ..WorldObject::new() };
 
-    demo_red_light.set_light_polarization();
-    demo_green_light.set_light_polarization();
-    demo_blue_light.set_light_polarization();
+    demo_light.set_light_polarization();
 
-    demo_world.insert_object(Vector3::from_vec(demo_red_light.center.into_iter().map(|x| x as i32).collect()), demo_red_light).unwrap();
-    demo_world.insert_object(Vector3::from_vec(demo_green_light.center.into_iter().map(|x| x as i32).collect()), demo_green_light).unwrap();
-    demo_world.insert_object(Vector3::from_vec(demo_blue_light.center.into_iter().map(|x| x as i32).collect()), demo_blue_light).unwrap();
+    demo_world.insert_object(Vector3::from_vec(demo_light.center.into_iter().map(|x| x as i32).collect()), demo_light).unwrap();
+    demo_world.insert_object(Vector3::from_vec(demo_slits.center.into_iter().map(|x| x as i32).collect()), demo_slits).unwrap();
 
     return demo_world
 }
@@ -106,12 +110,12 @@ pub fn triple_slit_demo() -> World {
 pub fn uncoordinated_interference_demo() -> World {
     let mut demo_world = World::new();
 
-    let mut l1 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [15.375362, 15.805714, 12.920403], color: Color32::from_rgb(164, 30, 150), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: 0.5, object_aligned_to_self: 0, wavelength: 0.1 };
-    let mut l2 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.55704, 15.805714, 12.89948], color: Color32::from_rgb(164, 30, 150), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::FRONT, aligned_distance: 0.0, object_aligned_to_self: 7, wavelength: 0.1 };
-    let mut l3 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.62066, 15.051637, 12.97835], color: Color32::from_rgb(9, 62, 36), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.5, object_aligned_to_self: 0, wavelength: 0.1 };
-    let mut l4 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.778408, 16.207035, 12.74316], color: Color32::from_rgb(200, 40, 15), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.5, object_aligned_to_self: 0, wavelength: 0.1 };
-    let mut l5 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.81051, 15.668215, 12.135378], color: Color32::from_rgb(52, 112, 17), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.5, object_aligned_to_self: 0, wavelength: 0.1 };
-    let mut l6 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [15.516808, 15.95551, 13.701332], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.5, object_aligned_to_self: 0, wavelength: 0.1 };
+    let mut l1 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [15.375362, 15.805714, 12.920403], color: Color32::from_rgb(164, 30, 150), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: 0.5, object_aligned_to_self: 0, wavelength: 0.1, ..WorldObject::new() };
+    let mut l2 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.55704, 15.805714, 12.89948], color: Color32::from_rgb(164, 30, 150), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::FRONT, aligned_distance: 0.0, object_aligned_to_self: 7, wavelength: 0.1, ..WorldObject::new() };
+    let mut l3 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.62066, 15.051637, 12.97835], color: Color32::from_rgb(9, 62, 36), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.5, object_aligned_to_self: 0, wavelength: 0.1, ..WorldObject::new() };
+    let mut l4 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.778408, 16.207035, 12.74316], color: Color32::from_rgb(200, 40, 15), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.5, object_aligned_to_self: 0, wavelength: 0.1, ..WorldObject::new() };
+    let mut l5 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.81051, 15.668215, 12.135378], color: Color32::from_rgb(52, 112, 17), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.5, object_aligned_to_self: 0, wavelength: 0.1, ..WorldObject::new() };
+    let mut l6 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [15.516808, 15.95551, 13.701332], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.5, object_aligned_to_self: 0, wavelength: 0.1, ..WorldObject::new() };
 
     l1.set_light_polarization();
     l2.set_light_polarization();
@@ -139,19 +143,19 @@ pub fn coordinated_interference_demo() -> World {
     demo_world.aligned_objects.insert(7);
     demo_world.aligned_objects.insert(1);
 
-    let mut l1 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [12.466017, 13.034395, 15.146756], color: Color32::from_rgb(52, 112, 17), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::RIGHT, aligned_distance: 0.0, object_aligned_to_self: 8, wavelength: 0.1 };
+    let mut l1 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [12.466017, 13.034395, 15.146756], color: Color32::from_rgb(52, 112, 17), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::RIGHT, aligned_distance: 0.0, object_aligned_to_self: 8, wavelength: 0.1, ..WorldObject::new() };
 
-    let mut l2 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.55704, 15.805714, 12.89948], color: Color32::from_rgb(164, 30, 150), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 9, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 7, wavelength: 0.1 };
-    let mut l3 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.62066, 15.051637, 12.97835], color: Color32::from_rgb(9, 62, 36), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 0, wavelength: 0.1 };
-    let mut l4 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.778408, 16.207035, 12.74316], color: Color32::from_rgb(200, 40, 15), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 5, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 0, wavelength: 0.1 };
-    let mut l5 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.81051, 15.668215, 12.135378], color: Color32::from_rgb(52, 112, 17), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 4, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 6, wavelength: 0.1 };
+    let mut l2 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.55704, 15.805714, 12.89948], color: Color32::from_rgb(164, 30, 150), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 9, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 7, wavelength: 0.1, ..WorldObject::new() };
+    let mut l3 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.62066, 15.051637, 12.97835], color: Color32::from_rgb(9, 62, 36), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 8, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 0, wavelength: 0.1, ..WorldObject::new() };
+    let mut l4 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.778408, 16.207035, 12.74316], color: Color32::from_rgb(200, 40, 15), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 5, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 0, wavelength: 0.1, ..WorldObject::new() };
+    let mut l5 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [14.81051, 15.668215, 12.135378], color: Color32::from_rgb(52, 112, 17), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 4, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 6, wavelength: 0.1, ..WorldObject::new() };
 
-    let mut l6 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [12.466017, 13.334396, 15.146756], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::RIGHT, aligned_distance: 0.0, object_aligned_to_self: 5, wavelength: 0.1 };
+    let mut l6 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [12.466017, 13.334396, 15.146756], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::RIGHT, aligned_distance: 0.0, object_aligned_to_self: 5, wavelength: 0.1, ..WorldObject::new() };
 
-    let mut l7 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [12.466017, 13.6343975, 15.146756], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::RIGHT, aligned_distance: 0.0, object_aligned_to_self: 2, wavelength: 0.1 };
+    let mut l7 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [12.466017, 13.6343975, 15.146756], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 0, alignment: world::Alignment::RIGHT, aligned_distance: 0.0, object_aligned_to_self: 2, wavelength: 0.1, ..WorldObject::new() };
 
-    let mut l8 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [15.516808, 15.95551, 13.701332], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 3, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 1, wavelength: 0.1 };
-    let mut l9 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [15.516808, 15.95551, 13.701332], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 2, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 0, wavelength: 0.1 };
+    let mut l8 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [15.516808, 15.95551, 13.701332], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 3, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 1, wavelength: 0.1, ..WorldObject::new() };
+    let mut l9 = WorldObject { object_type: ObjectType::LightSource, rotation: [0.0, 0.0], center: [15.516808, 15.95551, 13.701332], color: Color32::from_rgb(78, 175, 51), width: 0.5, height: 0.5, radius: 0.1, polarization: Vector2::new(Complex { re: 1.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), jones_matrix: Matrix2::new(Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }, Complex { re: 0.0, im: 0.0 }), polarization_type: LightPolarizationType::LinearHorizontal, aligned_to_object: 2, alignment: world::Alignment::RIGHT, aligned_distance: -0.4, object_aligned_to_self: 0, wavelength: 0.1, ..WorldObject::new() };
 
     l1.set_light_polarization();
     l2.set_light_polarization();
@@ -175,3 +179,128 @@ pub fn coordinated_interference_demo() -> World {
 
     return demo_world
 }
+
+pub fn diffraction_grating_demo() -> World {
+    let mut demo_world = World::new();
+
+    // three lights with different wavelengths in the same spot going through a
+    // transmission grating, every color leaves the grating at its own angle
+    let mut red_light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [13.5, 13.5, 4.0], color: Color32::from_rgb(60, 1, 1), radius: 0.1, polarization_type: LightPolarizationType::LinearVertical, wavelength: 0.032, material_wavelength: 0.65, ..WorldObject::new() };
+    let mut green_light = WorldObject { color: Color32::from_rgb(1, 60, 1), wavelength: 0.026, material_wavelength: 0.53, ..red_light };
+    let mut blue_light = WorldObject { color: Color32::from_rgb(1, 1, 60), wavelength: 0.02, material_wavelength: 0.41, ..red_light };
+    let demo_grating = WorldObject { object_type: ObjectType::Grating, rotation: [0.0, PI / 2.0], center: [13.5, 13.5, 6.0], color: Color32::from_rgb(60, 60, 60), radius: 0.5, grating_type: GratingType::Transmission, groove_density: 20.0, blaze_angle: 0.0, ..WorldObject::new() };
+
+    red_light.set_light_polarization();
+    green_light.set_light_polarization();
+    blue_light.set_light_polarization();
+
+    demo_world.insert_object(Vector3::from_vec(red_light.center.into_iter().map(|x| x as i32).collect()), red_light).unwrap();
+    demo_world.insert_object(Vector3::from_vec(green_light.center.into_iter().map(|x| x as i32).collect()), green_light).unwrap();
+    demo_world.insert_object(Vector3::from_vec(blue_light.center.into_iter().map(|x| x as i32).collect()), blue_light).unwrap();
+    demo_world.insert_object(Vector3::from_vec(demo_grating.center.into_iter().map(|x| x as i32).collect()), demo_grating).unwrap();
+
+    demo_world
+}
+
+pub fn optical_isolator_demo() -> World {
+    let mut demo_world = World::new();
+
+    // a horizontal polarizer, a faraday rotator that rotates 45 degrees and a polarizer
+    // at 45 degrees, the red light goes through all of them but the blue one (think of it
+    // as the red light coming back from a reflection) gets blocked by the first polarizer
+    let mut forward_light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [13.5, 13.5, 6.0], color: Color32::from_rgb(60, 1, 1), radius: 0.1, polarization_type: LightPolarizationType::LinearHorizontal, wavelength: 0.1, ..WorldObject::new() };
+    let mut backward_light = WorldObject { rotation: [0.0, 0.0], center: [13.5, 13.5, 19.0], color: Color32::from_rgb(1, 1, 60), polarization_type: LightPolarizationType::LinearAntiDiagonal, wavelength: 0.11, ..forward_light };
+
+    let element = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], color: Color32::from_rgb(60, 60, 60), radius: 0.5, ..WorldObject::new() };
+    let mut input_polarizer = WorldObject { center: [13.5, 13.5, 9.0], ..element };
+    let mut faraday_rotator = WorldObject { center: [13.5, 13.5, 12.0], verdet_constant: 40.0, magnetic_field: 1.0, faraday_length: PI / 4.0 / 40.0, ..element };
+    let mut output_polarizer = WorldObject { center: [13.5, 13.5, 15.0], ..element };
+
+    forward_light.set_light_polarization();
+    backward_light.set_light_polarization();
+    input_polarizer.set_jones_matrix(PolarizerType::LinearHorizontal, 0.0, 0.0, 0.0);
+    faraday_rotator.set_jones_matrix(PolarizerType::FaradayRotator, 0.0, 0.0, 0.0);
+    output_polarizer.set_jones_matrix(PolarizerType::LinearTheta, PI / 4.0, 0.0, 0.0);
+
+    for object in [forward_light, backward_light, input_polarizer, faraday_rotator, output_polarizer] {
+        demo_world.insert_object(Vector3::from_vec(object.center.into_iter().map(|x| x as i32).collect()), object).unwrap();
+    }
+
+    demo_world
+}
+
+pub fn prism_demo() -> World {
+    let mut demo_world = World::new();
+
+    // five lights from blue to red in the same spot acting as white light, the prism
+    // is turned so that the green one goes through it at minimum deviation and every
+    // color leaves it at its own angle
+    let mut prism = WorldObject { object_type: ObjectType::Prism, center: [9.5, 13.5, 8.0], color: Color32::from_rgb(60, 60, 60), radius: 1.0, apex_angle: PI / 3.0, material: MaterialName::new("SF11"), ..WorldObject::new() };
+    let prism_index = MaterialLibrary::new().refractive_index(&prism.material, 0.55);
+    prism.rotation = [minimum_deviation(&prism, prism_index, 1.0).unwrap_or(0.0) / 2.0 - PI / 2.0, PI / 2.0];
+
+    let light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [9.5, 13.5, 3.0], radius: 0.1, polarization_type: LightPolarizationType::LinearVertical, ..WorldObject::new() };
+    let colors = [(0.45, Color32::from_rgb(1, 1, 60)), (0.5, Color32::from_rgb(1, 40, 40)), (0.55, Color32::from_rgb(1, 60, 1)), (0.6, Color32::from_rgb(50, 50, 1)), (0.65, Color32::from_rgb(60, 1, 1))];
+
+    // micrometers for the prism, the same numbers in cubes keep the colors from interfering
+    for (wavelength, color) in colors {
+        let mut colored_light = WorldObject { wavelength, material_wavelength: wavelength, color, ..light };
+        colored_light.set_light_polarization();
+
+        demo_world.insert_object(Vector3::from_vec(colored_light.center.into_iter().map(|x| x as i32).collect()), colored_light).unwrap();
+    }
+
+    demo_world.insert_object(Vector3::from_vec(prism.center.into_iter().map(|x| x as i32).collect()), prism).unwrap();
+
+    demo_world
+}
+
+pub fn double_refraction_demo() -> World {
+    let mut demo_world = World::new();
+
+    // diagonally polarized light going through a calcite block cut with its optic axis
+    // at 45 degrees, half of it leaves as the ordinary ray and half as the extraordinary
+    // one, displaced sideways by the walk-off
+    let mut light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [13.5, 13.5, 3.0], color: Color32::from_rgb(1, 60, 1), radius: 0.1, polarization_type: LightPolarizationType::LinearDiagonal, wavelength: 0.55, material_wavelength: 0.55, ..WorldObject::new() };
+    let displacer = WorldObject { object_type: ObjectType::BeamDisplacer, rotation: [0.0, PI / 2.0], center: [13.5, 13.5, 9.0], color: Color32::from_rgb(60, 60, 60), radius: 1.0, material: MaterialName::new("Calcite (ordinary)"), optic_axis: [PI / 4.0, 0.0], displacer_length: 6.0, ..WorldObject::new() };
+
+    light.set_light_polarization();
+
+    for object in [light, displacer] {
+        demo_world.insert_object(Vector3::from_vec(object.center.into_iter().map(|x| x as i32).collect()), object).unwrap();
+    }
+
+    demo_world
+}
+
+pub fn photoelasticity_demo() -> World {
+    let mut demo_world = World::new();
+
+    // a plane polariscope, five horizontally polarized lights from blue to red acting as
+    // white light go through a polycarbonate disk squeezed along its vertical axis and then
+    // through a vertical analyzer, only where the stress turned their polarization gets
+    // through and every color gets a different retardance
+    let light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [13.5, 13.5, 3.0], radius: 0.1, polarization_type: LightPolarizationType::LinearHorizontal, ..WorldObject::new() };
+    let colors = [(0.45, Color32::from_rgb(1, 1, 60)), (0.5, Color32::from_rgb(1, 40, 40)), (0.55, Color32::from_rgb(1, 60, 1)), (0.6, Color32::from_rgb(50, 50, 1)), (0.65, Color32::from_rgb(60, 1, 1))];
+
+    for (wavelength, color) in colors {
+        let mut colored_light = WorldObject { wavelength, material_wavelength: wavelength, color, ..light };
+        colored_light.set_light_polarization();
+
+        demo_world.insert_object(Vector3::from_vec(colored_light.center.into_iter().map(|x| x as i32).collect()), colored_light).unwrap();
+    }
+
+    let element = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], color: Color32::from_rgb(60, 60, 60), radius: 0.5, ..WorldObject::new() };
+    let mut sample = WorldObject { center: [13.5, 13.5, 8.0], stress_field: StressField::DiskCompression, applied_load: 500.0, stress_optic_coefficient: 70.0, sample_size: 50.0, sample_thickness: 6.0, ..element };
+    let mut analyzer = WorldObject { center: [13.5, 13.5, 11.0], ..element };
+    let screen = WorldObject { object_type: ObjectType::RoundWall, center: [13.5, 13.5, 16.0], radius: 0.5, color: Color32::from_rgb(200, 200, 200), ..element };
+
+    sample.set_jones_matrix(PolarizerType::PhotoelasticSample, 0.0, 0.0, 0.0);
+    analyzer.set_jones_matrix(PolarizerType::LinearVertical, 0.0, 0.0, 0.0);
+
+    for object in [sample, analyzer, screen] {
+        demo_world.insert_object(Vector3::from_vec(object.center.into_iter().map(|x| x as i32).collect()), object).unwrap();
+    }
+
+    demo_world
+}
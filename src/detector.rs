@@ -0,0 +1,112 @@
+// Detector screens, a grid of pixels over the square inscribed in the drawn disk of the
+// object that records the intensity and the stokes parameters of the light that reaches
+// each of them, see polarimetry.rs
+use std::fmt::{self, Display, Formatter};
+
+use nalgebra::Vector3;
+
+use crate::{polarimetry::{degree_of_polarization, stokes_at}, world::{World, WorldObject}};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum DetectorQuantity {
+    Intensity,
+    S1,
+    S2,
+    S3,
+    DegreeOfPolarization
+}
+
+// Needed for the drop down list
+impl Display for DetectorQuantity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Intensity => write!(f, "Intensity (S0)"),
+            Self::S1 => write!(f, "S1 (horizontal - vertical)"),
+            Self::S2 => write!(f, "S2 (diagonal - anti-diagonal)"),
+            Self::S3 => write!(f, "S3 (right - left circular)"),
+            Self::DegreeOfPolarization => write!(f, "Degree of polarization"),
+        }
+    }
+}
+
+pub fn measured_quantity(stokes: [f32; 4], quantity: DetectorQuantity) -> f32 {
+    match quantity {
+        DetectorQuantity::Intensity => stokes[0],
+        DetectorQuantity::S1 => stokes[1],
+        DetectorQuantity::S2 => stokes[2],
+        DetectorQuantity::S3 => stokes[3],
+        DetectorQuantity::DegreeOfPolarization => degree_of_polarization(stokes),
+    }
+}
+
+// side of the square inscribed in the disk main.frag draws, in cubes
+pub fn sensor_side(detector: &WorldObject) -> f32 {
+    detector.drawn_radius() * 2.0f32.sqrt()
+}
+
+// center of the pixel (column, row) counted from the top left of the detector, the
+// square inscribed in its disk along its horizontal and vertical axes
+pub fn pixel_position(detector: &WorldObject, column: usize, row: usize) -> Vector3<f32> {
+    let [horizontal, _, vertical] = detector.local_axes();
+    let side = sensor_side(detector);
+    let [columns, rows] = detector.detector_pixels;
+
+    let x = side * ((column as f32 + 0.5) / columns as f32 - 0.5);
+    let y = side * (0.5 - (row as f32 + 0.5) / rows as f32);
+
+    Vector3::from(detector.center) + horizontal * x + vertical * y
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectorReadout {
+    pub pixels: [usize; 2],
+    // sum of the stokes parameters of every frame recorded, row by row from the top left
+    pub stokes: Vec<[f32; 4]>,
+    pub frames: u32
+}
+
+impl DetectorReadout {
+    pub fn new(detector: &WorldObject) -> DetectorReadout {
+        let pixels = detector.detector_pixels.map(|x| x as usize);
+
+        DetectorReadout { pixels, stokes: vec![[0.0; 4]; pixels[0] * pixels[1]], frames: 0 }
+    }
+
+    // adds what the detector sees right now, starts over if its grid changed
+    pub fn record(&mut self, world: &World, detector_index: usize, cube_scaling_factor: f32) {
+        let detector = world.objects[detector_index];
+
+        if self.pixels != detector.detector_pixels.map(|x| x as usize) {
+            *self = DetectorReadout::new(&detector);
+        }
+
+        for row in 0..self.pixels[1] {
+            for column in 0..self.pixels[0] {
+                let stokes = stokes_at(world, pixel_position(&detector, column, row), cube_scaling_factor, Some(detector_index));
+                let pixel = &mut self.stokes[row * self.pixels[0] + column];
+
+                *pixel = [0, 1, 2, 3].map(|i| pixel[i] + stokes[i]);
+            }
+        }
+
+        self.frames += 1;
+    }
+
+    // average over the frames recorded of every pixel
+    pub fn values(&self, quantity: DetectorQuantity) -> Vec<f32> {
+        let frames = self.frames.max(1) as f32;
+
+        self.stokes.iter().map(|stokes| measured_quantity(stokes.map(|x| x / frames), quantity)).collect()
+    }
+
+    // min, max and mean
+    pub fn statistics(&self, quantity: DetectorQuantity) -> (f32, f32, f32) {
+        let values = self.values(quantity);
+
+        (
+            values.iter().copied().fold(f32::INFINITY, f32::min),
+            values.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+            values.iter().sum::<f32>() / values.len().max(1) as f32
+        )
+    }
+}
@@ -0,0 +1,224 @@
+// Scalar diffraction through aperture screens with the Huygens-Fresnel integral over the openings
+use std::f32::consts::PI;
+
+use nalgebra::{Complex, Vector2, Vector3};
+
+use crate::{field::gaussian_beam_amplitude, world::{ApertureType, WorldObject}};
+
+// integration samples across the width of every slit
+pub const SAMPLES_PER_SLIT: usize = 24;
+// integration samples per side of the bounding box of 2d apertures
+pub const SAMPLES_PER_SIDE: usize = 16;
+// the shader has a fixed loop limit, so we do too
+pub const MAX_SLITS: u32 = 8;
+
+// position of `point` projected onto the plane of the aperture, in cubes
+pub fn aperture_local_coordinates(aperture: &WorldObject, point: Vector3<f32>) -> Vector2<f32> {
+    let [horizontal, _, vertical] = aperture.local_axes();
+    let offset = point - Vector3::from(aperture.center);
+
+    Vector2::new(offset.dot(&horizontal), offset.dot(&vertical))
+}
+
+// center of the slit with index `slit` along the horizontal axis of the screen
+fn slit_center(aperture: &WorldObject, slit: u32) -> f32 {
+    let slit_count = slit_count(aperture);
+    (slit as f32 - (slit_count as f32 - 1.0) / 2.0) * aperture.slit_pitch
+}
+
+fn slit_count(aperture: &WorldObject) -> u32 {
+    match aperture.aperture_type {
+        ApertureType::MultipleSlits => aperture.slit_count.clamp(1, MAX_SLITS),
+        _ => 1
+    }
+}
+
+// whether light can go through the screen at the given local coordinates
+pub fn aperture_transmits(aperture: &WorldObject, local: Vector2<f32>) -> bool {
+    let half_width = aperture.aperture_width / 2.0;
+    let half_height = aperture.aperture_height / 2.0;
+
+    match aperture.aperture_type {
+        ApertureType::SingleSlit |
+        ApertureType::Rectangular => {
+            local.x.abs() < half_width && local.y.abs() < half_height
+        }
+
+        ApertureType::MultipleSlits => {
+            if local.y.abs() >= half_height {
+                return false;
+            }
+
+            (0..slit_count(aperture)).any(|slit| (local.x - slit_center(aperture, slit)).abs() < half_width)
+        }
+
+        ApertureType::CircularPinhole => {
+            local.norm() < half_width
+        }
+    }
+}
+
+// complex amplitude (without the jones vector of the light) that reaches `point`
+// after `light` goes through the openings of `aperture`, in a medium with the
+// given refractive index
+pub fn diffracted_amplitude(light: &WorldObject, aperture: &WorldObject, point: Vector3<f32>, refractive_index: f32, cube_scaling_factor: f32) -> Complex<f32> {
+    let [horizontal, normal, vertical] = aperture.local_axes();
+    let center = Vector3::from(aperture.center);
+    let wavelength = light.wavelength / refractive_index;
+    let k = 2.0 * PI / wavelength;
+
+    let mut amplitude = Complex::new(0.0, 0.0);
+
+    match aperture.aperture_type {
+        // long slits, the integral along the slit is solved with the stationary phase
+        // approximation so that we only have to integrate across each slit
+        ApertureType::SingleSlit |
+        ApertureType::MultipleSlits => {
+            let observed_height = aperture_local_coordinates(aperture, point).y.clamp(-aperture.aperture_height / 2.0, aperture.aperture_height / 2.0);
+            let du = aperture.aperture_width / SAMPLES_PER_SLIT as f32;
+
+            for slit in 0..slit_count(aperture) {
+                for sample in 0..SAMPLES_PER_SLIT {
+                    let u = slit_center(aperture, slit) - aperture.aperture_width / 2.0 + (sample as f32 + 0.5) * du;
+                    let source_point = center + horizontal * u + vertical * observed_height;
+
+                    let r = (point - source_point).norm() * cube_scaling_factor;
+                    let obliquity = normal.dot(&(point - source_point)).abs() * cube_scaling_factor / r;
+
+                    // cylindrical huygens wavelet e^(ikr) / sqrt(iλr)
+                    let wavelet = Complex::new(0.0, k * r - PI / 4.0).exp() / (wavelength * r).sqrt();
+                    amplitude += gaussian_beam_amplitude(light, source_point, refractive_index, cube_scaling_factor) * wavelet * obliquity * du * cube_scaling_factor;
+                }
+            }
+        }
+
+        ApertureType::CircularPinhole |
+        ApertureType::Rectangular => {
+            let (side_u, side_v) = match aperture.aperture_type {
+                ApertureType::CircularPinhole => (aperture.aperture_width, aperture.aperture_width),
+                _ => (aperture.aperture_width, aperture.aperture_height)
+            };
+
+            let du = side_u / SAMPLES_PER_SIDE as f32;
+            let dv = side_v / SAMPLES_PER_SIDE as f32;
+
+            for i in 0..SAMPLES_PER_SIDE {
+                for j in 0..SAMPLES_PER_SIDE {
+                    let local = Vector2::new(
+                        -side_u / 2.0 + (i as f32 + 0.5) * du,
+                        -side_v / 2.0 + (j as f32 + 0.5) * dv
+                    );
+
+                    if !aperture_transmits(aperture, local) {
+                        continue;
+                    }
+
+                    let source_point = center + horizontal * local.x + vertical * local.y;
+
+                    let r = (point - source_point).norm() * cube_scaling_factor;
+                    let obliquity = normal.dot(&(point - source_point)).abs() * cube_scaling_factor / r;
+
+                    // spherical huygens wavelet e^(ikr) / (iλr)
+                    let wavelet = Complex::new(0.0, k * r - PI / 2.0).exp() / (wavelength * r);
+                    amplitude += gaussian_beam_amplitude(light, source_point, refractive_index, cube_scaling_factor) * wavelet * obliquity * du * dv * cube_scaling_factor.powi(2);
+                }
+            }
+        }
+    }
+
+    amplitude
+}
+
+pub(crate) fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        return 1.0;
+    }
+
+    x.sin() / x
+}
+
+// bessel function of the first kind of order one,
+// polynomial approximation from Abramowitz and Stegun 9.4.4 and 9.4.6
+#[allow(clippy::excessive_precision)]
+fn bessel_j1(x: f32) -> f32 {
+    let ax = x.abs();
+
+    if ax < 3.0 {
+        let y = (x / 3.0).powi(2);
+        return x * (0.5 - 0.56249985 * y + 0.21093573 * y.powi(2) - 0.03954289 * y.powi(3)
+            + 0.00443319 * y.powi(4) - 0.00031761 * y.powi(5) + 0.00001109 * y.powi(6));
+    }
+
+    let y = 3.0 / ax;
+    let f1 = 0.79788456 + 0.00000156 * y + 0.01659667 * y.powi(2) + 0.00017105 * y.powi(3)
+        - 0.00249511 * y.powi(4) + 0.00113653 * y.powi(5) - 0.00020033 * y.powi(6);
+    let theta1 = ax - 2.35619449 + 0.12499612 * y + 0.00005650 * y.powi(2) - 0.00637879 * y.powi(3)
+        + 0.00074348 * y.powi(4) + 0.00079824 * y.powi(5) - 0.00029166 * y.powi(6);
+
+    x.signum() * f1 * theta1.cos() / ax.sqrt()
+}
+
+// far field (fraunhofer) intensity normalized to 1 at the center of the pattern,
+// the sines are taken w.r.t the horizontal and vertical axes of the screen and
+// the wavelength is the one inside of the medium
+pub fn fraunhofer_intensity(aperture: &WorldObject, sin_theta_u: f32, sin_theta_v: f32, wavelength: f32, cube_scaling_factor: f32) -> f32 {
+    let width = aperture.aperture_width * cube_scaling_factor;
+    let height = aperture.aperture_height * cube_scaling_factor;
+
+    let beta_u = PI * width * sin_theta_u / wavelength;
+    let beta_v = PI * height * sin_theta_v / wavelength;
+
+    match aperture.aperture_type {
+        ApertureType::SingleSlit => {
+            sinc(beta_u).powi(2)
+        }
+
+        ApertureType::MultipleSlits => {
+            let slit_count = slit_count(aperture) as f32;
+            let gamma = PI * aperture.slit_pitch * cube_scaling_factor * sin_theta_u / wavelength;
+
+            let array_factor = if gamma.sin().abs() < 1e-4 {
+                1.0
+            } else {
+                (slit_count * gamma).sin() / (slit_count * gamma.sin())
+            };
+
+            sinc(beta_u).powi(2) * array_factor.powi(2)
+        }
+
+        ApertureType::Rectangular => {
+            sinc(beta_u).powi(2) * sinc(beta_v).powi(2)
+        }
+
+        ApertureType::CircularPinhole => {
+            let x = PI * width * (sin_theta_u.powi(2) + sin_theta_v.powi(2)).sqrt() / wavelength;
+
+            if x.abs() < 1e-6 {
+                return 1.0;
+            }
+
+            (2.0 * bessel_j1(x) / x).powi(2)
+        }
+    }
+}
+
+// expected distance between fringes on a screen `distance` meters behind the
+// aperture, for slit arrays this is the separation between principal maxima,
+// for single apertures the separation between the first zeros and the central
+// maximum (the radius of the airy disk for pinholes), sizes are scaled to meters
+// and the wavelength is the one inside of the medium
+pub fn expected_fringe_period(aperture: &WorldObject, wavelength: f32, distance: f32, cube_scaling_factor: f32) -> f32 {
+    match aperture.aperture_type {
+        ApertureType::MultipleSlits if slit_count(aperture) > 1 => {
+            wavelength * distance / (aperture.slit_pitch * cube_scaling_factor)
+        }
+
+        ApertureType::CircularPinhole => {
+            1.22 * wavelength * distance / (aperture.aperture_width * cube_scaling_factor)
+        }
+
+        _ => {
+            wavelength * distance / (aperture.aperture_width * cube_scaling_factor)
+        }
+    }
+}
@@ -0,0 +1,193 @@
+// Birefringent beam displacers, the ordinary and extraordinary rays leave as new gaussian beams
+use std::fmt::{self, Display, Formatter};
+
+use nalgebra::{Complex, Matrix2, Vector2, Vector3};
+
+use crate::{field::{light_direction, light_transverse_axes}, prism::{planes_intersection, refract}, world::WorldObject};
+
+// planes that bound the block, the first ones are the faces the light goes through
+pub const DISPLACER_PLANES: usize = 6;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum CrystalRay {
+    Ordinary,
+    Extraordinary
+}
+
+// Shown in the inspector
+impl Display for CrystalRay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ordinary => write!(f, "ordinary"),
+            Self::Extraordinary => write!(f, "extraordinary"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DisplacedBeam {
+    pub ray: CrystalRay,
+    // where the ray goes in and out of the block
+    pub entry: Vector3<f32>,
+    pub exit: Vector3<f32>,
+    // direction of the energy (poynting vector) and of the wave vector inside
+    pub ray_direction: Vector3<f32>,
+    pub wave_direction: Vector3<f32>,
+    pub direction: Vector3<f32>,
+    // index seen by the wave, depends on the angle with the optic axis for the e ray
+    pub refractive_index: f32,
+    // angle between the ray and the wave vector inside, in radians
+    pub walk_off: f32,
+    // where the waist of the outgoing beam would be if it had always travelled
+    // in the medium, keeps the phase continuous across the block
+    pub waist: Vector3<f32>,
+    pub exit_normal: Vector3<f32>,
+    // projection onto the polarization of the ray in the basis of the light
+    pub jones_matrix: Matrix2<Complex<f32>>
+}
+
+// outward normals and offsets of the planes that bound the block, the faces
+// perpendicular to its normal are displacer_length apart and the sides are
+// 2 * radius long
+pub fn displacer_planes(displacer: &WorldObject) -> [(Vector3<f32>, f32); DISPLACER_PLANES] {
+    let [horizontal, normal, vertical] = displacer.local_axes();
+
+    [
+        (normal, displacer.displacer_length / 2.0),
+        (-normal, displacer.displacer_length / 2.0),
+        (horizontal, displacer.radius),
+        (-horizontal, displacer.radius),
+        (vertical, displacer.radius),
+        (-vertical, displacer.radius)
+    ]
+}
+
+// distance to the corners of the block from its center
+pub fn displacer_bounding_radius(displacer: &WorldObject) -> f32 {
+    (displacer.radius * 2f32.sqrt()).hypot(displacer.displacer_length / 2.0)
+}
+
+// index seen by an extraordinary wave going at `angle` from the optic axis
+pub fn extraordinary_index(ordinary_index: f32, extraordinary_index: f32, angle: f32) -> f32 {
+    let (sin, cos) = angle.sin_cos();
+    1.0 / ((cos / ordinary_index).powi(2) + (sin / extraordinary_index).powi(2)).sqrt()
+}
+
+// textbook walk-off angle of the extraordinary ray for a wave going at `angle` from
+// the optic axis, positive means away from the axis (positive crystals, ne > no)
+pub fn walk_off_angle(ordinary_index: f32, extraordinary_index: f32, angle: f32) -> f32 {
+    let (sin, cos) = angle.sin_cos();
+    let (no2, ne2) = (ordinary_index.powi(2), extraordinary_index.powi(2));
+
+    ((ne2 - no2) * sin * cos / (ne2 * cos.powi(2) + no2 * sin.powi(2))).atan()
+}
+
+// direction of the energy of a wave going along `wave_direction`, E = ε⁻¹ D and the
+// poynting vector is perpendicular to E and H = k x E
+fn poynting_direction(wave_direction: Vector3<f32>, polarization: Vector3<f32>, optic_axis: Vector3<f32>, ordinary_index: f32, extraordinary_index: f32) -> Vector3<f32> {
+    let electric_field = polarization / ordinary_index.powi(2)
+        + optic_axis * optic_axis.dot(&polarization) * (1.0 / extraordinary_index.powi(2) - 1.0 / ordinary_index.powi(2));
+
+    (wave_direction * electric_field.norm_squared() - electric_field * electric_field.dot(&wave_direction)).normalize()
+}
+
+// polarization (D) of the ordinary and the extraordinary waves going along `wave_direction`,
+// when it goes along the optic axis both see the same index so any pair works
+fn crystal_polarizations(wave_direction: Vector3<f32>, optic_axis: Vector3<f32>, fallback: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let mut ordinary = wave_direction.cross(&optic_axis);
+
+    if ordinary.norm() < 1e-4 {
+        ordinary = wave_direction.cross(&fallback);
+    }
+
+    let ordinary = ordinary.normalize();
+    (ordinary, wave_direction.cross(&ordinary).normalize())
+}
+
+// follows the axis of `light` through the block for one of the rays, None if it
+// misses the faces or the ray leaves through one of the sides, the faces are taken
+// as anti-reflection coated so the only loss is the projection onto the polarization
+// of the ray, the indices are the principal ones of the crystal and the one of the
+// medium at the wavelength of the light
+pub fn displaced_beam(displacer: &WorldObject, light: &WorldObject, ray: CrystalRay, indices: [f32; 2], medium_index: f32) -> Option<DisplacedBeam> {
+    let [ordinary_index, principal_extraordinary_index] = indices;
+    let planes = displacer_planes(displacer);
+    let center = Vector3::from(displacer.center);
+    let incident = light_direction(light);
+    let optic_axis = displacer.optic_axis_direction();
+    let [horizontal, _, _] = displacer.local_axes();
+
+    let (t_in, _, entry_plane, _) = planes_intersection(&planes, center, Vector3::from(light.center), incident)?;
+    if t_in <= 0.0 || entry_plane > 1 {
+        return None;
+    }
+
+    let entry = Vector3::from(light.center) + incident * t_in;
+    let entry_normal = planes[entry_plane].0;
+
+    // the index of the extraordinary wave depends on the direction it refracts
+    // into, which depends on the index, a few rounds are enough for it to settle
+    let mut refractive_index = ordinary_index;
+    let mut wave_direction = refract(incident, entry_normal, medium_index / refractive_index)?;
+
+    if ray == CrystalRay::Extraordinary {
+        for _ in 0..8 {
+            let angle = wave_direction.dot(&optic_axis).clamp(-1.0, 1.0).acos();
+            refractive_index = extraordinary_index(ordinary_index, principal_extraordinary_index, angle);
+            wave_direction = refract(incident, entry_normal, medium_index / refractive_index)?;
+        }
+    }
+
+    let (_, extraordinary_polarization) = crystal_polarizations(wave_direction, optic_axis, horizontal);
+    let ray_direction = match ray {
+        CrystalRay::Ordinary => wave_direction,
+        CrystalRay::Extraordinary => poynting_direction(wave_direction, extraordinary_polarization, optic_axis, ordinary_index, principal_extraordinary_index)
+    };
+
+    // the energy goes along the ray, it has to leave through the opposite face
+    let (inside_distance, exit_plane) = planes.iter().enumerate()
+        .filter(|(_, (normal, _))| normal.dot(&ray_direction) > 1e-6)
+        .map(|(plane, (normal, offset))| ((offset - normal.dot(&(entry - center))) / normal.dot(&ray_direction), plane))
+        .fold((f32::INFINITY, entry_plane), |closest, current| if current.0 < closest.0 { current } else { closest });
+
+    if exit_plane != (entry_plane ^ 1) {
+        return None;
+    }
+
+    let exit = entry + ray_direction * inside_distance;
+    let exit_normal = planes[exit_plane].0;
+    let direction = refract(wave_direction, -exit_normal, refractive_index / medium_index)?;
+
+    // the phase follows the wave vector, not the ray
+    let optical_distance = t_in + inside_distance * wave_direction.dot(&ray_direction) * refractive_index / medium_index;
+
+    // polarization of the ray once it's out, written in the basis of the light
+    let (ordinary_out, extraordinary_out) = crystal_polarizations(direction, optic_axis, horizontal);
+    let polarization = match ray {
+        CrystalRay::Ordinary => ordinary_out,
+        CrystalRay::Extraordinary => extraordinary_out
+    };
+    let [light_x, light_y] = light_transverse_axes(light);
+    let projection = Vector2::new(polarization.dot(&light_x), polarization.dot(&light_y));
+
+    Some(DisplacedBeam {
+        ray,
+        entry,
+        exit,
+        ray_direction,
+        wave_direction,
+        direction,
+        refractive_index,
+        walk_off: ray_direction.dot(&wave_direction).clamp(-1.0, 1.0).acos(),
+        waist: exit - direction * optical_distance,
+        exit_normal,
+        jones_matrix: (projection * projection.transpose()).map(|x| Complex::new(x, 0.0))
+    })
+}
+
+// both rays of the light, the ones that make it through the block
+pub fn displaced_beams(displacer: &WorldObject, light: &WorldObject, indices: [f32; 2], medium_index: f32) -> Vec<DisplacedBeam> {
+    [CrystalRay::Ordinary, CrystalRay::Extraordinary].into_iter()
+        .filter_map(|ray| displaced_beam(displacer, light, ray, indices, medium_index))
+        .collect()
+}
@@ -0,0 +1,61 @@
+// Electro-optic elements, retarders whose retardance follows a time-varying voltage
+use std::f32::consts::PI;
+
+use nalgebra::{Complex, Matrix2};
+
+use crate::world::{retarder_jones_matrix, DriveWaveform, PolarizerType, WorldObject};
+
+pub fn is_electro_optic(element: &WorldObject) -> bool {
+    matches!(element.polarizer_type, PolarizerType::LiquidCrystalCell | PolarizerType::PockelsCell)
+}
+
+// voltage applied to the element at `time` (in seconds), offset + amplitude w(2π f t)
+pub fn drive_voltage(element: &WorldObject, time: f64) -> f32 {
+    let phase = (2.0 * std::f64::consts::PI * element.drive_frequency as f64 * time) as f32;
+
+    let waveform = match element.drive_waveform {
+        DriveWaveform::Constant => 0.0,
+        DriveWaveform::Sine => phase.sin(),
+        DriveWaveform::Square => if phase.sin() >= 0.0 { 1.0 } else { -1.0 },
+    };
+
+    element.drive_offset + element.drive_amplitude * waveform
+}
+
+// tilt of the molecules in the middle of a nematic cell, nothing happens under the
+// threshold (the fréedericksz transition) and then they turn towards the field,
+// θ = π/2 - 2 atan(e^(-(V - Vth) / V0)), the cell only feels the magnitude of the
+// field so the sign of the voltage doesn't matter
+pub fn liquid_crystal_tilt(cell: &WorldObject, voltage: f32) -> f32 {
+    if voltage.abs() <= cell.lc_threshold_voltage {
+        return 0.0;
+    }
+
+    PI / 2.0 - 2.0 * (-(voltage.abs() - cell.lc_threshold_voltage) / cell.lc_voltage_scale).exp().atan()
+}
+
+// nematic liquid crystal cell, the light sees about Δn cos²θ of the birefringence of
+// the molecules as they tilt towards the field
+pub fn liquid_crystal_retardance(cell: &WorldObject, voltage: f32) -> f32 {
+    cell.lc_max_retardance * liquid_crystal_tilt(cell, voltage).cos().powi(2)
+}
+
+// pockels cell, the field makes the crystal birefringent linearly in the voltage, half
+// a wave at the half-wave voltage
+pub fn pockels_retardance(cell: &WorldObject, voltage: f32) -> f32 {
+    PI * voltage / cell.half_wave_voltage
+}
+
+pub fn electro_optic_retardance(element: &WorldObject, voltage: f32) -> f32 {
+    match element.polarizer_type {
+        PolarizerType::LiquidCrystalCell => liquid_crystal_retardance(element, voltage),
+        PolarizerType::PockelsCell => pockels_retardance(element, voltage),
+        _ => 0.0,
+    }
+}
+
+// a retarder with its fast axis at polarizer_angle and the retardance of the voltage at
+// `time`, computed here every frame so main.frag just sees a retarder
+pub fn electro_optic_jones_matrix(element: &WorldObject, time: f64) -> Matrix2<Complex<f32>> {
+    retarder_jones_matrix(electro_optic_retardance(element, drive_voltage(element, time)), element.polarizer_angle)
+}
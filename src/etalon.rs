@@ -0,0 +1,68 @@
+// Fabry-Pérot etalons, the coherent sum of every round trip between two partial mirrors
+use std::f32::consts::PI;
+
+use nalgebra::Complex;
+
+use crate::world::WorldObject;
+
+// must match MAX_ROUND_TRIPS in main.frag, round trips after these are dropped,
+// with a reflectance of 0.98 (the highest the menu allows) they are under 1%
+pub const MAX_ROUND_TRIPS: usize = 256;
+
+// cosine of the angle of the light inside the gap, `cos_incidence` is the one outside,
+// the index of the gap is relative to the medium around the etalon
+pub fn cos_inside(etalon: &WorldObject, cos_incidence: f32) -> f32 {
+    (1.0 - (1.0 - cos_incidence.powi(2)) / etalon.etalon_index.powi(2)).max(0.0).sqrt()
+}
+
+// phase picked up in a round trip through the gap, δ = 4π n d cos θ / λ, the
+// wavelength in micrometers just like the gap
+pub fn round_trip_phase(etalon: &WorldObject, wavelength: f32, cos_incidence: f32) -> f32 {
+    4.0 * PI * etalon.etalon_index * etalon.etalon_gap * cos_inside(etalon, cos_incidence) / wavelength
+}
+
+// amplitude that gets through, t² (1 + R e^(iδ) + R² e^(2iδ) + ...) e^(iδ/2) with
+// t² = 1 - R for lossless mirrors, the light goes through the gap once to get out
+pub fn etalon_transmission(etalon: &WorldObject, wavelength: f32, cos_incidence: f32) -> Complex<f32> {
+    let phase = round_trip_phase(etalon, wavelength, cos_incidence);
+    let round_trip = Complex::from_polar(etalon.etalon_reflectance, phase);
+
+    let mut pass = Complex::from_polar(1.0 - etalon.etalon_reflectance, phase / 2.0);
+    let mut transmission = Complex::new(0.0, 0.0);
+
+    for _ in 0..MAX_ROUND_TRIPS {
+        transmission += pass;
+        pass *= round_trip;
+    }
+
+    transmission
+}
+
+// the closed form of |etalon_transmission|² for infinite round trips,
+// 1 / (1 + F sin²(δ/2))
+pub fn airy_transmittance(reflectance: f32, phase: f32) -> f32 {
+    1.0 / (1.0 + coefficient_of_finesse(reflectance) * (phase / 2.0).sin().powi(2))
+}
+
+// F = 4R / (1 - R)²
+pub fn coefficient_of_finesse(reflectance: f32) -> f32 {
+    4.0 * reflectance / (1.0 - reflectance).powi(2)
+}
+
+// free spectral range over the width of a peak, 𝓕 = π √R / (1 - R)
+pub fn finesse(reflectance: f32) -> f32 {
+    PI * reflectance.sqrt() / (1.0 - reflectance)
+}
+
+// distance between two peaks around `wavelength`, λ² / (2 n d cos θ), in micrometers
+pub fn free_spectral_range(etalon: &WorldObject, wavelength: f32, cos_incidence: f32) -> f32 {
+    wavelength.powi(2) / (2.0 * etalon.etalon_index * etalon.etalon_gap * cos_inside(etalon, cos_incidence))
+}
+
+// order and wavelength of the peak closest to `wavelength`, λm = 2 n d cos θ / m
+pub fn closest_resonance(etalon: &WorldObject, wavelength: f32, cos_incidence: f32) -> (u32, f32) {
+    let optical_round_trip = 2.0 * etalon.etalon_index * etalon.etalon_gap * cos_inside(etalon, cos_incidence);
+    let order = (optical_round_trip / wavelength).round().max(1.0);
+
+    (order as u32, optical_round_trip / order)
+}
@@ -0,0 +1,278 @@
+// cpu side mirror of the electric field model used in main.frag, along with the elements
+// in the other modules, anything changed here should also be changed in the shader and vice versa
+use std::f32::consts::PI;
+
+use nalgebra::{Complex, Matrix2, Vector2, Vector3};
+
+use crate::{camera::{rotate3d_x, rotate3d_y}, coatings::coating_jones_matrix, diffraction::diffracted_amplitude, displacer::{displaced_beams, displacer_planes}, etalon::etalon_transmission, grating::{diffraction_orders, grating_incidence_point}, prism::{planes_intersection, prism_beam, prism_intersection}, spatial::element_at, world::{GratingType, ObjectType, PolarizerType, World, WorldObject}};
+
+// beam waist of every gaussian beam, in meters
+pub const BEAM_WAIST: f32 = 5.0;
+
+pub fn light_direction(light: &WorldObject) -> Vector3<f32> {
+    let mut light_dir = Vector3::new(0.0, 0.0, -1.0);
+    light_dir = rotate3d_x(light_dir, light.rotation[1]);
+    light_dir = rotate3d_y(light_dir, light.rotation[0]);
+    light_dir.normalize()
+}
+
+// the axes in which the jones vector of the light is written, Ex goes along
+// the first one and Ey along the second one
+pub fn light_transverse_axes(light: &WorldObject) -> [Vector3<f32>; 2] {
+    [
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0)
+    ].map(|axis| rotate3d_y(rotate3d_x(axis, light.rotation[1]), light.rotation[0]))
+}
+
+// scalar part of the gaussian beam emitted by `light` evaluated at `point`, the
+// jones vector of the light still has to be multiplied by this, `refractive_index`
+// is the one of the medium at the wavelength of the light
+pub fn gaussian_beam_amplitude(light: &WorldObject, point: Vector3<f32>, refractive_index: f32, cube_scaling_factor: f32) -> Complex<f32> {
+    gaussian_beam_amplitude_along(Vector3::from(light.center), light_direction(light), light.wavelength, refractive_index, point, cube_scaling_factor)
+}
+
+// same as above for a beam with its waist at `center` going towards `light_dir`
+pub fn gaussian_beam_amplitude_along(center: Vector3<f32>, light_dir: Vector3<f32>, wavelength: f32, refractive_index: f32, point: Vector3<f32>, cube_scaling_factor: f32) -> Complex<f32> {
+    // gaussian beams only go forward
+    if light_dir.dot(&(point - center)) <= 0.0 {
+        return Complex::new(0.0, 0.0);
+    }
+
+    let radius = (point - center).cross(&light_dir).norm() * cube_scaling_factor;
+    // distance along the axis of the beam, the curvature of the wavefront is
+    // already taken into account by the radius term of the phase
+    let z = (point - center).dot(&light_dir) * cube_scaling_factor;
+    let n = refractive_index;
+
+    let w0 = BEAM_WAIST;
+    let z_r = (PI * w0 * w0 * n) / wavelength;
+    let w_z = w0 * (1.0 + (z / z_r).powi(2)).sqrt();
+    let r_z = z * (1.0 + (z_r / z).powi(2));
+    let gouy_z = (z / z_r).atan();
+    let k = (2.0 * PI * n) / wavelength;
+
+    let envelope = (w0 / w_z) * (-radius.powi(2) / w_z.powi(2)).exp();
+    let phase = k * z + k * (radius.powi(2) / (2.0 * r_z)) - gouy_z;
+
+    Complex::new(0.0, phase).exp() * envelope * 2.0
+}
+
+// jones matrices of optical objects are written in the basis of the object (its
+// horizontal and vertical axes), this writes them in the basis of the light that
+// goes through them in the direction `propagation`, reciprocal elements act as
+// their transpose when the light goes against their normal
+pub fn jones_matrix_for_light(element: &WorldObject, light: &WorldObject, propagation: Vector3<f32>) -> Matrix2<Complex<f32>> {
+    let [horizontal, normal, vertical] = element.local_axes();
+    let [light_x, light_y] = light_transverse_axes(light);
+
+    // axes of the element projected onto the plane perpendicular to the light
+    let horizontal = (horizontal - propagation * horizontal.dot(&propagation)).normalize();
+    let vertical = vertical - propagation * vertical.dot(&propagation);
+    let vertical = (vertical - horizontal * vertical.dot(&horizontal)).normalize();
+
+    let basis_change = Matrix2::new(
+        light_x.dot(&horizontal), light_x.dot(&vertical),
+        light_y.dot(&horizontal), light_y.dot(&vertical)
+    ).map(|x| Complex::new(x, 0.0));
+
+    // the retardance of chromatic retarders depends on the wavelength of the light
+    let mut jones_matrix = match element.polarizer_type {
+        PolarizerType::ChromaticRetarder => element.chromatic_retarder_jones_matrix(light.material_wavelength),
+        // and so does the transmission of etalons, which also depends on the angle of the light
+        PolarizerType::FabryPerotEtalon => Matrix2::identity() * etalon_transmission(element, light.material_wavelength, propagation.dot(&normal).abs()),
+        _ => element.jones_matrix
+    };
+
+    if propagation.dot(&normal) < 0.0 && element.polarizer_type != PolarizerType::FaradayRotator {
+        jones_matrix = jones_matrix.transpose();
+    }
+
+    basis_change * jones_matrix * basis_change.transpose()
+}
+
+// distance along `direction` (a unit vector) from `origin` to where it crosses the
+// disk of a round object, if it does
+pub fn disk_crossing(object: &WorldObject, origin: Vector3<f32>, direction: Vector3<f32>) -> Option<f32> {
+    let normal = object.local_axes()[1];
+    let denominator = direction.dot(&normal);
+    if denominator.abs() < 1e-6 {
+        return None;
+    }
+
+    let distance = (Vector3::from(object.center) - origin).dot(&normal) / denominator;
+    let hit = origin + direction * distance;
+
+    if distance > 0.0 && (hit - Vector3::from(object.center)).norm() <= object.drawn_radius() {
+        Some(distance)
+    } else {
+        None
+    }
+}
+
+// distance along `direction` to the surface of an object the light can land on or
+// go through, for picking points on them
+pub fn surface_distance(object: &WorldObject, origin: Vector3<f32>, direction: Vector3<f32>) -> Option<f32> {
+    match object.object_type {
+        ObjectType::OpticalObjectRoundWall | ObjectType::Aperture => disk_crossing(object, origin, direction),
+        _ => blocking_distance(object, origin, direction)
+    }
+}
+
+// distance along `direction` to the first surface of a solid object that blocks the
+// light, square walls are left out just like main.frag leaves them out
+pub fn blocking_distance(object: &WorldObject, origin: Vector3<f32>, direction: Vector3<f32>) -> Option<f32> {
+    match object.object_type {
+        ObjectType::RoundWall | ObjectType::Grating | ObjectType::Detector => disk_crossing(object, origin, direction),
+
+        ObjectType::Prism => prism_intersection(object, origin, direction).map(|(near, _, _, _)| near).filter(|near| *near > 0.0),
+        ObjectType::BeamDisplacer => planes_intersection(&displacer_planes(object), Vector3::from(object.center), origin, direction).map(|(near, _, _, _)| near).filter(|near| *near > 0.0),
+
+        // cube walls fill the cube their center is in
+        ObjectType::CubeWall => {
+            let corner = Vector3::from(object.center).map(|x| x.floor());
+            let mut near = f32::NEG_INFINITY;
+            let mut far = f32::INFINITY;
+
+            for axis in 0..3 {
+                if direction[axis].abs() < 1e-6 {
+                    if origin[axis] < corner[axis] || origin[axis] > corner[axis] + 1.0 {
+                        return None;
+                    }
+                    continue;
+                }
+
+                let a = (corner[axis] - origin[axis]) / direction[axis];
+                let b = (corner[axis] + 1.0 - origin[axis]) / direction[axis];
+                near = near.max(a.min(b));
+                far = far.min(a.max(b));
+            }
+
+            if near <= far && near > 0.0 { Some(near) } else { None }
+        }
+
+        _ => None
+    }
+}
+
+// jones vector (in the basis of the light) of the field of every light source at `point`
+// following the same paths main.frag follows: straight from the light through the optical
+// objects in between (or diffracted by the aperture it crosses) unless something solid is
+// in the way, plus the orders of gratings and the beams out of prisms and beam displacers,
+// `exclude` is the object the point is on, it doesn't block its own light
+pub fn light_fields_at(world: &World, point: Vector3<f32>, cube_scaling_factor: f32, exclude: Option<usize>) -> Vec<(usize, Vector2<Complex<f32>>)> {
+    let objects: Vec<(usize, WorldObject)> = world.objects_associations.keys()
+        .filter(|index| Some(**index) != exclude)
+        .map(|index| (*index, world.objects[*index]))
+        .collect();
+
+    world.light_sources.iter().map(|light_index| {
+        let light = world.objects[*light_index as usize];
+        let medium_index = world.medium_refractive_index(light.material_wavelength);
+        let mut field = Vector2::new(Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+
+        // straight from the light, walking from the point back to it like main.frag
+        let to_light = Vector3::from(light.center) - point;
+        let distance_to_light = to_light.norm();
+        // a probe or a detector sitting on the light, there's no direction to walk back along
+        if distance_to_light < 1e-6 {
+            return (*light_index as usize, field);
+        }
+
+        let backwards = to_light / distance_to_light;
+        let propagation = -backwards;
+
+        let mut crossed: Vec<(f32, usize, WorldObject)> = vec![];
+        let mut blocked = false;
+
+        for (index, object) in &objects {
+            if object.object_type == ObjectType::OpticalObjectRoundWall || object.object_type == ObjectType::Aperture {
+                if let Some(distance) = disk_crossing(object, point, backwards).filter(|distance| *distance < distance_to_light) {
+                    crossed.push((distance, *index, *object));
+                }
+
+            } else if blocking_distance(object, point, backwards).is_some_and(|distance| distance < distance_to_light) {
+                blocked = true;
+            }
+        }
+
+        if !blocked {
+            // from the light to the point
+            crossed.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+            let mut jones_vector = light.polarization;
+            // the light diffracts at the first aperture it meets, same as main.frag
+            let mut aperture = None;
+
+            for (distance, index, object) in crossed {
+                if object.object_type == ObjectType::Aperture {
+                    aperture = aperture.or(Some(object));
+                    continue;
+                }
+
+                let hit = point + backwards * distance;
+                let element = element_at(&object, hit, world.element_maps.get(&index));
+                let jones_matrix = jones_matrix_for_light(&element, &light, propagation) * coating_jones_matrix(&object, &light, propagation, &world.materials, medium_index);
+
+                jones_vector = jones_matrix * jones_vector;
+            }
+
+            let amplitude = match aperture {
+                Some(aperture) => diffracted_amplitude(&light, &aperture, point, medium_index, cube_scaling_factor),
+                None => gaussian_beam_amplitude(&light, point, medium_index, cube_scaling_factor)
+            };
+
+            field += jones_vector * amplitude;
+        }
+
+        for (_, object) in &objects {
+            match object.object_type {
+                // every order leaves the grating as a gaussian beam with its waist where the
+                // waist of the light was
+                ObjectType::Grating => {
+                    let Some(hit) = grating_incidence_point(object, &light) else { continue };
+
+                    let normal = object.local_axes()[1];
+                    let incident = light_direction(&light);
+                    let behind = (point - Vector3::from(object.center)).dot(&normal).signum() == incident.dot(&normal).signum();
+                    if behind != (object.grating_type == GratingType::Transmission) {
+                        continue;
+                    }
+
+                    let distance_to_grating = (hit - Vector3::from(light.center)).norm();
+
+                    for order in diffraction_orders(object, &light, medium_index) {
+                        let amplitude = gaussian_beam_amplitude_along(hit - order.direction * distance_to_grating, order.direction, light.wavelength, medium_index, point, cube_scaling_factor);
+                        field += order.jones_matrix * light.polarization * amplitude;
+                    }
+                }
+
+                ObjectType::Prism => {
+                    let prism_index = world.materials.refractive_index(&object.material, light.material_wavelength);
+                    let Some(beam) = prism_beam(object, &light, prism_index, medium_index, &object.coating.indices(&world.materials, light.material_wavelength)) else { continue };
+
+                    if (point - beam.exit).dot(&beam.exit_normal) > 0.0 {
+                        field += beam.jones_matrix * light.polarization * gaussian_beam_amplitude_along(beam.waist, beam.direction, light.wavelength, medium_index, point, cube_scaling_factor);
+                    }
+                }
+
+                ObjectType::BeamDisplacer => {
+                    let indices = [
+                        world.materials.refractive_index(&object.material, light.material_wavelength),
+                        world.materials.refractive_index(&object.extraordinary_material, light.material_wavelength)
+                    ];
+
+                    for beam in displaced_beams(object, &light, indices, medium_index) {
+                        if (point - beam.exit).dot(&beam.exit_normal) > 0.0 {
+                            field += beam.jones_matrix * light.polarization * gaussian_beam_amplitude_along(beam.waist, beam.direction, light.wavelength, medium_index, point, cube_scaling_factor);
+                        }
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        (*light_index as usize, field)
+    }).collect()
+}
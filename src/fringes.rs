@@ -0,0 +1,204 @@
+// Fringe analysis of the intensity along a line, the visibility (Imax - Imin) / (Imax + Imin),
+// the dominant spatial frequency from an fft of the profile and what the geometry says
+// they should be, two lights of the same wavelength or a light behind slits
+use std::f32::consts::PI;
+use std::fmt::{self, Display, Formatter};
+
+use nalgebra::{Complex, Vector3};
+
+use crate::{detector::{DetectorQuantity, DetectorReadout}, diffraction::expected_fringe_period, field::{disk_crossing, light_direction, light_fields_at, BEAM_WAIST}, world::{ApertureType, ObjectType, World, WorldObject}};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum FringeAxis {
+    Horizontal,
+    Vertical
+}
+
+// Needed for the drop down list
+impl Display for FringeAxis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Horizontal => write!(f, "Along the horizontal axis of the detector"),
+            Self::Vertical => write!(f, "Along the vertical axis of the detector"),
+        }
+    }
+}
+
+// radix 2 cooley-tukey, the length has to be a power of two
+pub fn fft(values: &[Complex<f32>]) -> Vec<Complex<f32>> {
+    let n = values.len();
+    if n <= 1 {
+        return values.to_vec();
+    }
+
+    let even = fft(&values.iter().step_by(2).copied().collect::<Vec<_>>());
+    let odd = fft(&values.iter().skip(1).step_by(2).copied().collect::<Vec<_>>());
+
+    let mut transformed = vec![Complex::new(0.0, 0.0); n];
+    for k in 0..n / 2 {
+        let twiddle = Complex::from_polar(1.0, -2.0 * PI * k as f32 / n as f32) * odd[k];
+        transformed[k] = even[k] + twiddle;
+        transformed[k + n / 2] = even[k] - twiddle;
+    }
+
+    transformed
+}
+
+pub fn fringe_visibility(intensities: &[f32]) -> f32 {
+    let max = intensities.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let min = intensities.iter().copied().fold(f32::INFINITY, f32::min);
+
+    if max + min <= 0.0 {
+        return 0.0;
+    }
+
+    (max - min) / (max + min)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FringeAnalysis {
+    pub visibility: f32,
+    // cycles per meter and meters, None if the profile is flat
+    pub frequency: Option<f32>,
+    pub period: Option<f32>,
+    // [cycles per meter, magnitude] up to the nyquist frequency
+    pub spectrum: Vec<[f32; 2]>
+}
+
+// `spacing` is the distance in meters between the samples, the profile loses its mean and
+// gets padded with zeros up to four times the next power of two so the peak can be found
+// between the bins, the peak itself is refined with a parabola through the bins around it
+pub fn analyze_fringes(intensities: &[f32], spacing: f32) -> FringeAnalysis {
+    let mean = intensities.iter().sum::<f32>() / intensities.len().max(1) as f32;
+    let size = intensities.len().next_power_of_two() * 4;
+
+    let mut padded: Vec<Complex<f32>> = intensities.iter().map(|intensity| Complex::new(intensity - mean, 0.0)).collect();
+    padded.resize(size, Complex::new(0.0, 0.0));
+
+    let magnitudes: Vec<f32> = fft(&padded).iter().take(size / 2 + 1).map(|x| x.norm()).collect();
+    let frequency_of = |bin: f32| bin / (size as f32 * spacing);
+
+    let spectrum = magnitudes.iter().enumerate().map(|(bin, magnitude)| [frequency_of(bin as f32), *magnitude]).collect();
+
+    // the first bins are what is left of the mean and the envelope, peaks have to rise
+    // over their neighbours
+    let peak = (2..magnitudes.len() - 1)
+        .filter(|bin| magnitudes[*bin] >= magnitudes[bin - 1] && magnitudes[*bin] >= magnitudes[bin + 1])
+        .max_by(|a, b| magnitudes[*a].total_cmp(&magnitudes[*b]))
+        .filter(|bin| magnitudes[*bin] > 1e-6 * mean.abs().max(f32::MIN_POSITIVE) * intensities.len() as f32);
+
+    let frequency = peak.map(|bin| {
+        let (left, center, right) = (magnitudes[bin - 1], magnitudes[bin], magnitudes[bin + 1]);
+        let curvature = left - 2.0 * center + right;
+        let offset = if curvature.abs() > 0.0 { 0.5 * (left - right) / curvature } else { 0.0 };
+
+        frequency_of(bin as f32 + offset.clamp(-0.5, 0.5))
+    });
+
+    FringeAnalysis { visibility: fringe_visibility(intensities), frequency, period: frequency.map(|frequency| 1.0 / frequency), spectrum }
+}
+
+// intensity along the row or the column through the middle of a detector readout and the
+// distance between its pixels in meters
+pub fn detector_profile(readout: &DetectorReadout, side: f32, axis: FringeAxis) -> (Vec<f32>, f32) {
+    let intensities = readout.values(DetectorQuantity::Intensity);
+    let [columns, rows] = readout.pixels;
+
+    match axis {
+        FringeAxis::Horizontal => (intensities[(rows / 2) * columns..(rows / 2 + 1) * columns].to_vec(), side / columns as f32),
+        FringeAxis::Vertical => ((0..rows).map(|row| intensities[row * columns + columns / 2]).collect(), side / rows as f32),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpectedFringes {
+    pub description: String,
+    // meters along the axis of the analysis
+    pub period: f32,
+    pub visibility: f32
+}
+
+// local wave vector (radians per meter) of the gaussian beam of `light` at `point`, along
+// the axis of the beam plus the tilt of the curvature of the wavefront, k (ẑ + ρ / R(z))
+pub fn local_wave_vector(light: &WorldObject, point: Vector3<f32>, refractive_index: f32, cube_scaling_factor: f32) -> Vector3<f32> {
+    let direction = light_direction(light);
+    let offset = (point - Vector3::from(light.center)) * cube_scaling_factor;
+    let z = offset.dot(&direction);
+    let radial = offset - direction * z;
+
+    let z_r = PI * BEAM_WAIST.powi(2) * refractive_index / light.wavelength;
+    let k = 2.0 * PI * refractive_index / light.wavelength;
+
+    if z.abs() < 1e-9 {
+        return direction * k;
+    }
+
+    (direction + radial / (z * (1.0 + (z_r / z).powi(2)))) * k
+}
+
+// what the fringes at `point` along `axis` should look like
+// - two lights of the same wavelength, the phase difference changes as (k1 - k2) along the
+//   screen so the period is 2π / |(k1 - k2) · axis|, λ / (2 sin(α / 2)) for beams crossing
+//   at α on a screen facing them, the visibility is 2 |E1† E2| / (|E1|² + |E2|²) so
+//   orthogonal polarizations don't interfere
+// - a light behind slits, the period of diffraction.rs
+pub fn expected_fringes(world: &World, point: Vector3<f32>, axis: Vector3<f32>, cube_scaling_factor: f32, exclude: Option<usize>) -> Option<ExpectedFringes> {
+    let fields = light_fields_at(world, point, cube_scaling_factor, exclude);
+
+    for (i, (first, first_field)) in fields.iter().enumerate() {
+        for (second, second_field) in fields.iter().skip(i + 1) {
+            let (a, b) = (world.objects[*first], world.objects[*second]);
+            if a.wavelength != b.wavelength {
+                continue;
+            }
+
+            let refractive_index = world.medium_refractive_index(a.material_wavelength);
+            let difference = local_wave_vector(&a, point, refractive_index, cube_scaling_factor) - local_wave_vector(&b, point, refractive_index, cube_scaling_factor);
+            let projected = difference.dot(&axis).abs();
+            let intensities = first_field.norm_squared() + second_field.norm_squared();
+
+            if projected < 1e-6 || intensities <= 0.0 {
+                continue;
+            }
+
+            let angle = light_direction(&a).dot(&light_direction(&b)).clamp(-1.0, 1.0).acos();
+            let separation = (Vector3::from(a.center) - Vector3::from(b.center)).norm() * cube_scaling_factor;
+
+            return Some(ExpectedFringes {
+                description: format!("Lights {} and {}, {:.4} m apart with beams crossing at {:.3} degrees", first, second, separation, angle.to_degrees()),
+                period: 2.0 * PI / projected,
+                visibility: 2.0 * first_field.dotc(second_field).norm() / intensities
+            });
+        }
+    }
+
+    for light_index in &world.light_sources {
+        let light = world.objects[*light_index as usize];
+        let to_light = Vector3::from(light.center) - point;
+
+        let slits = world.objects_associations.keys()
+            .map(|index| world.objects[*index])
+            .filter(|object| object.object_type == ObjectType::Aperture && object.aperture_type == ApertureType::MultipleSlits && object.slit_count > 1)
+            .find(|aperture| disk_crossing(aperture, point, to_light.normalize()).is_some_and(|distance| distance < to_light.norm()));
+
+        if let Some(aperture) = slits {
+            let wavelength = light.wavelength / world.medium_refractive_index(light.material_wavelength);
+            let distance = (point - Vector3::from(aperture.center)).norm() * cube_scaling_factor;
+
+            // the slits are along the horizontal axis of the aperture
+            let [horizontal, _, _] = aperture.local_axes();
+            let along = horizontal.dot(&axis).abs();
+            if along < 1e-3 {
+                continue;
+            }
+
+            return Some(ExpectedFringes {
+                description: format!("{} slits {:.4} mm apart, {:.4} m away", aperture.slit_count, aperture.slit_pitch * cube_scaling_factor * 1000.0, distance),
+                period: expected_fringe_period(&aperture, wavelength, distance, cube_scaling_factor) / along,
+                visibility: 1.0
+            });
+        }
+    }
+
+    None
+}
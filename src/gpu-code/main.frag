@@ -10,16 +10,43 @@ uniform float cube_scaling_factor;
 uniform uint light_sources_count;
 uniform float background_light_min;
 
-#define OBJECT_SIZE uint(25)
+// the cross-section plane, see cross_section.rs
+uniform uint slice_mode;
+uniform vec3 slice_center;
+uniform vec2 slice_rotation;
+uniform float slice_size;
+uniform float slice_opacity;
+uniform float slice_exposure;
+
+// false color views, see render_mode.rs
+uniform uint render_mode;
+uniform uint render_colormap;
+uniform float render_exposure;
+// 2π field_time is ωt of the first light
+uniform float field_time;
+
+#define OBJECT_SIZE uint(96)
 #define NUM_OBJECTS uint(10)
 #define SIZE_BUCKETS uint(25)
+#define MATERIAL_SIZE uint(10)
+// must match coatings.rs
+#define MAX_COATING_LAYERS 10
+#define MAX_COATING_MATERIALS uint(8)
 
 uniform uint lights_definitions_indices[NUM_OBJECTS];
 uniform uint objects[699];
 uniform uint buckets[SIZE_BUCKETS];
-// to be able to use WorldObject objects_definitions[] i'd have to have
-// sent it in a compatible alignment, not doin that tho
-uniform uint objects_definitions[OBJECT_SIZE * NUM_OBJECTS];
+// to be able to use WorldObject object_definition() i'd have to have
+// sent it in a compatible alignment, not doin that tho, it's packed in uvec4s
+// because some implementations use a whole vec4 slot for every uint in an array
+// and we would run out of uniforms, read it with object_definition()
+uniform uvec4 objects_definitions[(OBJECT_SIZE * NUM_OBJECTS + uint(3)) / uint(4)];
+// material the lab is filled with, packed like in materials.rs
+uniform uint medium_definition[MATERIAL_SIZE];
+// every different material used by the coatings, the layers of the objects point here
+uniform uvec4 coating_materials_definitions[MAX_COATING_MATERIALS * MATERIAL_SIZE / uint(4)];
+// maps of the image elements, one layer per element, see spatial.rs
+uniform mediump sampler2DArray element_maps;
 
 layout(location = 0) out vec4 out_color;
 layout(location = 1) out vec4 object_found;
@@ -38,6 +65,85 @@ const uint LIGHT_SOURCE = uint(3);                // Sphere that represents a li
 const uint OPTICAL_OBJECT_CUBE = uint(4);         // An object represented using a jones matrix
 const uint OPTICAL_OBJECT_SQUARE_WALL = uint(5);  // An object represented using a jones matrix
 const uint OPTICAL_OBJECT_ROUND_WALL = uint(6);   // An object represented using a jones matrix
+const uint APERTURE = uint(7);                    // Opaque round screen with openings that diffract light
+const uint GRATING = uint(8);                     // Round grating that splits light into diffraction orders
+const uint PRISM = uint(9);                       // Triangular prism that refracts light through two of its faces
+const uint BEAM_DISPLACER = uint(10);             // Block of a birefringent crystal that splits light into two rays
+const uint DETECTOR = uint(11);                   // Round screen that records the light, looks like a round wall
+const uint PROBE = uint(12);                      // Small sphere that measures the light, doesn't block it
+
+// WorldObject.aperture_type possible values
+const uint SINGLE_SLIT = uint(0);
+const uint MULTIPLE_SLITS = uint(1);
+const uint CIRCULAR_PINHOLE = uint(2);
+const uint RECTANGULAR_APERTURE = uint(3);
+
+// WorldObject.grating_type possible values
+const uint TRANSMISSION_GRATING = uint(0);
+const uint REFLECTION_GRATING = uint(1);
+
+// Material.model possible values
+const uint CONSTANT_DISPERSION = uint(0);
+const uint CAUCHY_DISPERSION = uint(1);
+const uint SELLMEIER_DISPERSION = uint(2);
+
+// WorldObject.polarizer_type values we care about
+const uint FARADAY_ROTATOR = uint(17);
+const uint CHROMATIC_RETARDER = uint(18);
+const uint FABRY_PEROT_ETALON = uint(19);
+const uint Q_PLATE = uint(20);
+const uint VORTEX_RETARDER = uint(21);
+const uint IMAGE_ELEMENT = uint(22);
+const uint PHOTOELASTIC_SAMPLE = uint(23);
+
+// must match the constants in spatial.rs
+const int MAP_RESOLUTION = 128;
+const uint RETARDANCE_MAP = uint(0);
+const uint FAST_AXIS_MAP = uint(1);
+const uint AMPLITUDE_MAP = uint(2);
+
+// must match the constants in etalon.rs
+const int MAX_ROUND_TRIPS = 256;
+
+// must match the constants in grating.rs
+const int MAX_GRATING_ORDER = 3;
+const float GRATING_REFRACTIVE_INDEX = 1.5;
+
+// must match the constants in prism.rs
+const int PRISM_PLANES = 5;
+const int PRISM_REFRACTING_FACES = 2;
+
+// must match the constants in displacer.rs
+const int DISPLACER_PLANES = 6;
+
+// must match CrossSectionMode in cross_section.rs
+const uint SLICE_OFF = uint(0);
+const uint SLICE_INTENSITY = uint(1);
+const uint SLICE_PHASE = uint(2);
+const uint SLICE_POLARIZATION = uint(3);
+
+// must match RenderMode and Colormap in render_mode.rs
+const uint RENDER_REALISTIC = uint(0);
+const uint RENDER_INTENSITY = uint(1);
+const uint RENDER_PHASE_EX = uint(2);
+const uint RENDER_PHASE_EY = uint(3);
+const uint RENDER_S1 = uint(4);
+const uint RENDER_S2 = uint(5);
+const uint RENDER_S3 = uint(6);
+const uint RENDER_DEGREE_OF_POLARIZATION = uint(7);
+const uint RENDER_AZIMUTH_ELLIPTICITY = uint(8);
+const uint RENDER_FIELD_EX = uint(9);
+const uint RENDER_FIELD_EY = uint(10);
+
+const uint COLORMAP_GRAYSCALE = uint(0);
+const uint COLORMAP_INFERNO = uint(1);
+const uint COLORMAP_VIRIDIS = uint(2);
+const uint COLORMAP_JET = uint(3);
+
+// must match the constants in diffraction.rs
+const int SAMPLES_PER_SLIT = 24;
+const int SAMPLES_PER_SIDE = 16;
+const uint MAX_SLITS = uint(8);
 
 // Complex matrix =
 // |a b|
@@ -55,6 +161,14 @@ struct Polarization { // 128 bits
 };
 
 // Struct definitions ====================================
+struct Material {
+  uint model;
+  // see assets/materials.txt for their meaning
+  float coefficients[7];
+  // in micrometers
+  vec2 wavelength_range;
+};
+
 struct WorldObject {
   uint type;
   vec2 rotation;
@@ -71,7 +185,55 @@ struct WorldObject {
   Polarization polarization;
   // Will only be relevant if it's an optical object
   Complex2x2Matrix jones_matrix;
+  // in cubes for the propagation and in micrometers for the materials, see world.rs
   float wavelength;
+  float material_wavelength;
+  // Will only be relevant if it's an aperture screen
+  uint aperture_type;
+  float aperture_width;
+  float aperture_height;
+  uint slit_count;
+  float slit_pitch;
+  // Will only be relevant if it's a diffraction grating
+  uint grating_type;
+  float groove_density;
+  float blaze_angle;
+  // Will only be relevant if it's an optical object
+  uint polarizer_type;
+  float polarizer_angle;
+  // Will only be relevant if it's a chromatic retarder
+  float birefringence;
+  float birefringence_dispersion;
+  float retarder_thickness;
+  // Will only be relevant if it's a prism
+  float apex_angle;
+  // Will only be relevant if it's a beam displacer
+  vec2 optic_axis;
+  float displacer_length;
+  // Will only be relevant if it's a fabry-pérot etalon
+  float etalon_reflectance;
+  float etalon_gap;
+  float etalon_index;
+  // Will only be relevant if it's a spatially varying element
+  float topological_charge;
+  float spatial_retardance;
+  // layer of element_maps (U32_MAX if it has none) and a bit for every map it uses
+  uint element_map_layer;
+  uint element_map_channels;
+  // Will only be relevant if it's a photoelastic sample
+  uint stress_field;
+  float applied_load;
+  float stress_optic_coefficient;
+  float sample_size;
+  float sample_thickness;
+  // thin-film coating, index into coating_materials_definitions (U32_MAX if it
+  // didn't fit) and thickness in micrometers of every layer
+  uint coating_layer_count;
+  uint coating_materials[MAX_COATING_LAYERS];
+  float coating_thickness[MAX_COATING_LAYERS];
+  // the one of prisms and the ordinary one of beam displacers
+  Material material;
+  Material extraordinary_material;
 };
 
 struct RayObject {
@@ -99,6 +261,15 @@ struct RayObject {
   uint object_hit;
   Complex2x2Matrix optical_objects_found_product;
   int optical_objects_through_which_it_passed;
+  // one bit per object index, an object spans many cells but its
+  // jones matrix must only be applied once
+  uint optical_objects_applied;
+
+  // the first aperture screen the light meets on its way here, the one farthest
+  // back along the ray, must match light_fields_at in field.rs
+  bool crossed_aperture;
+  uint aperture_index;
+  float aperture_distance;
 };
 
 struct ObjectGoal {
@@ -223,10 +394,10 @@ vec2 cx_pow(vec2 a, float n) {
 //  c d]
 Complex2x2Matrix cx_2x2_mat_mul(Complex2x2Matrix A, Complex2x2Matrix B) {
   Complex2x2Matrix resultant_mat;
-  resultant_mat.a = A.a * B.a + A.b * B.c;
-  resultant_mat.b = A.a * B.b + A.b * B.d;
-  resultant_mat.c = A.c * B.a + A.d * B.c;
-  resultant_mat.d = A.c * B.b + A.d * B.d;
+  resultant_mat.a = cx_mul(A.a, B.a) + cx_mul(A.b, B.c);
+  resultant_mat.b = cx_mul(A.a, B.b) + cx_mul(A.b, B.d);
+  resultant_mat.c = cx_mul(A.c, B.a) + cx_mul(A.d, B.c);
+  resultant_mat.d = cx_mul(A.c, B.b) + cx_mul(A.d, B.d);
 
   return resultant_mat;
 }
@@ -244,61 +415,134 @@ Complex2x2Matrix cx_scalar_x_2x2_mat_mul(vec2 cx_scalar, Complex2x2Matrix cx_mat
 Polarization cx_2x2_mat_x_cx_pol_mul(Complex2x2Matrix mat, Polarization vec) {
   Polarization result = Polarization(vec2(0, 0), vec2(0, 0));
   result.Ex = cx_add(cx_mul(mat.a, vec.Ex), cx_mul(mat.b, vec.Ey));
-  result.Ey = cx_add(cx_mul(mat.c, vec.Ey), cx_mul(mat.d, vec.Ey));
+  result.Ey = cx_add(cx_mul(mat.c, vec.Ex), cx_mul(mat.d, vec.Ey));
 
   return result;
 }
 
 // Hash implementation
+uint object_definition(uint index) {
+  return objects_definitions[index / uint(4)][index % uint(4)];
+}
+
 uint hash(ivec3 val) {
   return uint(val.x + WORLD_SIZE.y * (val.y + WORLD_SIZE.z * val.z));
 }
 
+// material packed like in materials.rs starting at index
+Material object_material(uint index) {
+  Material material;
+    material.model = object_definition(index);
+
+    for (uint i = uint(0); i < uint(7); i++) {
+      material.coefficients[i] = uintBitsToFloat(object_definition(index + uint(1) + i));
+    }
+
+    material.wavelength_range = vec2(uintBitsToFloat(object_definition(index + uint(8))), uintBitsToFloat(object_definition(index + uint(9))));
+
+  return material;
+}
+
 WorldObject get_object_at_index(uint object_index) {
   WorldObject selected_object;
     // this whole section could break shit,
     // should add a check here or before sending
-    selected_object.type = objects_definitions[object_index * OBJECT_SIZE];
+    selected_object.type = object_definition(object_index * OBJECT_SIZE);
+
+    selected_object.rotation.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(1)));
+    selected_object.rotation.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(2)));
+
+    selected_object.center.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(3)));
+    selected_object.center.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(4)));
+    selected_object.center.z = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(5)));
+
+    selected_object.color.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(6)));
+    selected_object.color.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(7)));
+    selected_object.color.z = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(8)));
+
+    selected_object.width = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(9)));
+    selected_object.height = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(10)));
 
-    selected_object.rotation.x = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(1)]);
-    selected_object.rotation.y = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(2)]);
+    selected_object.radius = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(11)));
 
-    selected_object.center.x = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(3)]);
-    selected_object.center.y = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(4)]);
-    selected_object.center.z = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(5)]);
+    selected_object.polarization.Ex.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(12)));
+    selected_object.polarization.Ex.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(13)));
 
-    selected_object.color.x = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(6)]);
-    selected_object.color.y = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(7)]);
-    selected_object.color.z = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(8)]);
+    selected_object.polarization.Ey.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(14)));
+    selected_object.polarization.Ey.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(15)));
 
-    selected_object.width = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(9)]);
-    selected_object.height = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(10)]);
+    selected_object.jones_matrix.a.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(16)));
+    selected_object.jones_matrix.a.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(17)));
 
-    selected_object.radius = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(11)]);
+    selected_object.jones_matrix.b.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(18)));
+    selected_object.jones_matrix.b.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(19)));
 
-    selected_object.polarization.Ex.x = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(12)]);
-    selected_object.polarization.Ex.y = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(13)]);
+    selected_object.jones_matrix.c.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(20)));
+    selected_object.jones_matrix.c.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(21)));
 
-    selected_object.polarization.Ey.x = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(14)]);
-    selected_object.polarization.Ey.y = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(15)]);
+    selected_object.jones_matrix.d.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(22)));
+    selected_object.jones_matrix.d.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(23)));
 
-    selected_object.jones_matrix.a.x = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(16)]);
-    selected_object.jones_matrix.a.y = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(17)]);
+    selected_object.wavelength = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(24)));
 
-    selected_object.jones_matrix.b.x = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(18)]);
-    selected_object.jones_matrix.b.y = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(19)]);
+    selected_object.aperture_type = object_definition((object_index * OBJECT_SIZE) + uint(25));
+    selected_object.aperture_width = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(26)));
+    selected_object.aperture_height = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(27)));
+    selected_object.slit_count = object_definition((object_index * OBJECT_SIZE) + uint(28));
+    selected_object.slit_pitch = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(29)));
 
-    selected_object.jones_matrix.c.x = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(20)]);
-    selected_object.jones_matrix.c.y = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(21)]);
+    selected_object.grating_type = object_definition((object_index * OBJECT_SIZE) + uint(30));
+    selected_object.groove_density = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(31)));
+    selected_object.blaze_angle = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(32)));
 
-    selected_object.jones_matrix.d.x = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(22)]);
-    selected_object.jones_matrix.d.y = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(23)]);
+    selected_object.polarizer_type = object_definition((object_index * OBJECT_SIZE) + uint(33));
+    selected_object.polarizer_angle = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(34)));
 
-    selected_object.wavelength = uintBitsToFloat(objects_definitions[(object_index * OBJECT_SIZE) + uint(24)]);
+    selected_object.birefringence = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(35)));
+    selected_object.birefringence_dispersion = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(36)));
+    selected_object.retarder_thickness = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(37)));
+
+    selected_object.apex_angle = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(38)));
+
+    selected_object.optic_axis.x = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(39)));
+    selected_object.optic_axis.y = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(40)));
+    selected_object.displacer_length = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(41)));
+
+    selected_object.etalon_reflectance = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(42)));
+    selected_object.etalon_gap = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(43)));
+    selected_object.etalon_index = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(44)));
+
+    selected_object.topological_charge = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(45)));
+    selected_object.spatial_retardance = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(46)));
+    selected_object.element_map_layer = object_definition((object_index * OBJECT_SIZE) + uint(47));
+    selected_object.element_map_channels = object_definition((object_index * OBJECT_SIZE) + uint(48));
+
+    selected_object.stress_field = object_definition((object_index * OBJECT_SIZE) + uint(49));
+    selected_object.applied_load = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(50)));
+    selected_object.stress_optic_coefficient = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(51)));
+    selected_object.sample_size = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(52)));
+    selected_object.sample_thickness = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(53)));
+
+    selected_object.material_wavelength = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(54)));
+
+    selected_object.coating_layer_count = object_definition((object_index * OBJECT_SIZE) + uint(55));
+
+    for (int layer = 0; layer < MAX_COATING_LAYERS; layer++) {
+      selected_object.coating_materials[layer] = object_definition((object_index * OBJECT_SIZE) + uint(56 + 2 * layer));
+      selected_object.coating_thickness[layer] = uintBitsToFloat(object_definition((object_index * OBJECT_SIZE) + uint(57 + 2 * layer)));
+    }
+
+    selected_object.material = object_material((object_index * OBJECT_SIZE) + uint(76));
+    selected_object.extraordinary_material = object_material((object_index * OBJECT_SIZE) + uint(76) + MATERIAL_SIZE);
 
     return selected_object;
 }
 
+// round objects are drawn out to twice their radius, must match drawn_radius in world.rs
+float drawn_radius(WorldObject object) {
+  return object.radius * 2.0;
+}
+
 // Intersections code
 // Thanks to iq's https://www.shadertoy.com/view/XtlBDs
 // 0--b--3
@@ -356,6 +600,120 @@ vec3 raySphereIntersectPos(vec3 s, vec3 c, vec3 d, float r) {
   return c + i;
 }
 
+// horizontal axis, normal and vertical axis of an object
+vec3 object_horizontal_axis(WorldObject object) {
+  return rotate3dY(rotate3dX(vec3(1.0, 0.0, 0.0), object.rotation.y), object.rotation.x);
+}
+
+vec3 object_normal(WorldObject object) {
+  return rotate3dY(rotate3dX(vec3(0.0, 1.0, 0.0), object.rotation.y), object.rotation.x);
+}
+
+vec3 object_vertical_axis(WorldObject object) {
+  return rotate3dY(rotate3dX(vec3(0.0, 0.0, 1.0), object.rotation.y), object.rotation.x);
+}
+
+// Prism and beam displacer geometry, mirrors prism.rs and displacer.rs
+// outward normal (xyz) and offset (w) of one of the planes that bound the prism,
+// the first ones are the refracting faces
+vec4 prism_plane(WorldObject prism, int plane) {
+  vec3 horizontal = object_horizontal_axis(prism);
+  vec3 normal = object_normal(prism);
+  vec3 vertical = object_vertical_axis(prism);
+  float s = sin(prism.apex_angle / 2.0);
+  float c = cos(prism.apex_angle / 2.0);
+  float half_height = prism.radius * 2.0 * c / 2.0;
+
+  if (plane == 0) {
+    return vec4(-horizontal * c + normal * s, s * half_height);
+  }
+  if (plane == 1) {
+    return vec4(horizontal * c + normal * s, s * half_height);
+  }
+  if (plane == 2) {
+    return vec4(-normal, half_height);
+  }
+  if (plane == 3) {
+    return vec4(vertical, prism.radius);
+  }
+
+  return vec4(-vertical, prism.radius);
+}
+
+// same for the block of a beam displacer, the first ones are the faces
+// the light goes through
+vec4 displacer_plane(WorldObject displacer, int plane) {
+  vec3 horizontal = object_horizontal_axis(displacer);
+  vec3 normal = object_normal(displacer);
+  vec3 vertical = object_vertical_axis(displacer);
+
+  if (plane == 0) {
+    return vec4(normal, displacer.displacer_length / 2.0);
+  }
+  if (plane == 1) {
+    return vec4(-normal, displacer.displacer_length / 2.0);
+  }
+  if (plane == 2) {
+    return vec4(horizontal, displacer.radius);
+  }
+  if (plane == 3) {
+    return vec4(-horizontal, displacer.radius);
+  }
+  if (plane == 4) {
+    return vec4(vertical, displacer.radius);
+  }
+
+  return vec4(-vertical, displacer.radius);
+}
+
+vec4 bounding_plane(WorldObject object, int plane) {
+  if (object.type == PRISM) {
+    return prism_plane(object, plane);
+  }
+
+  return displacer_plane(object, plane);
+}
+
+int bounding_plane_count(WorldObject object) {
+  if (object.type == PRISM) {
+    return PRISM_PLANES;
+  }
+
+  return DISPLACER_PLANES;
+}
+
+// distances along the ray where it goes in and out of a prism or a beam
+// displacer and the planes it goes through there
+bool convex_intersection(WorldObject object, vec3 origin, vec3 direction, out vec2 distances, out ivec2 planes) {
+  distances = vec2(-10000.0, 10000.0);
+  planes = ivec2(0);
+
+  for (int plane = 0; plane < bounding_plane_count(object); plane++) {
+    vec4 bound = bounding_plane(object, plane);
+    float denominator = dot(bound.xyz, direction);
+    float distance = bound.w - dot(bound.xyz, origin - object.center);
+
+    if (abs(denominator) < 1e-6) {
+      if (distance < 0.0) {
+        return false;
+      }
+      continue;
+    }
+
+    float t = distance / denominator;
+
+    if (denominator < 0.0 && t > distances.x) {
+      distances.x = t;
+      planes.x = plane;
+    } else if (denominator > 0.0 && t < distances.y) {
+      distances.y = t;
+      planes.y = plane;
+    }
+  }
+
+  return distances.x <= distances.y && distances.y > 0.0;
+}
+
 // Ray marching code
 void step_ray(inout RayObject ray) {
   ray.mask = lessThanEqual(ray.side_dist.xyz, min(ray.side_dist.yzx, ray.side_dist.zxy));
@@ -374,7 +732,7 @@ vec3 object_hit_distance(WorldObject selected_object, RayObject ray) {
   }
 
   // if we hit a sphere type, we have to do additional checks
-  if (selected_object.type == LIGHT_SOURCE) {
+  if (selected_object.type == LIGHT_SOURCE || selected_object.type == PROBE) {
     return raySphereIntersectPos(ray.pos, selected_object.center, ray.dir, selected_object.radius);
   }
 
@@ -437,7 +795,18 @@ vec3 object_hit_distance(WorldObject selected_object, RayObject ray) {
     // return -1.0;
   }
 
-  if (selected_object.type == ROUND_WALL || selected_object.type == OPTICAL_OBJECT_ROUND_WALL) {
+  if (selected_object.type == PRISM || selected_object.type == BEAM_DISPLACER) {
+    vec2 distances;
+    ivec2 planes;
+
+    if (convex_intersection(selected_object, ray.pos, ray.dir, distances, planes) && distances.x > 0.0) {
+      return ray.pos + ray.dir * distances.x;
+    }
+
+    return vec3(-1.0);
+  }
+
+  if (selected_object.type == ROUND_WALL || selected_object.type == DETECTOR || selected_object.type == OPTICAL_OBJECT_ROUND_WALL || selected_object.type == APERTURE || selected_object.type == GRATING) {
     vec3 a = rotate3dY(
         rotate3dX(
             vec3(
@@ -489,7 +858,7 @@ vec3 object_hit_distance(WorldObject selected_object, RayObject ray) {
     vec3 hit_pos_object_space = quadIntersect(ray.pos, ray.dir, selected_object.center, selected_object.center + b, selected_object.center + c, selected_object.center + d);
     float past_plane_product_ray = dot(ray.dir, hit_pos_object_space - ray.pos);
 
-    if (length(hit_pos_object_space) < selected_object.radius * 2.0) {
+    if (length(hit_pos_object_space) < drawn_radius(selected_object)) {
       return hit_pos_object_space + selected_object.center;
     }
 
@@ -499,6 +868,854 @@ vec3 object_hit_distance(WorldObject selected_object, RayObject ray) {
   return vec3(-1.0);
 }
 
+// Aperture code, mirrors diffraction.rs
+vec2 aperture_local_coordinates(WorldObject aperture, vec3 point) {
+  vec3 offset = point - aperture.center;
+  return vec2(dot(offset, object_horizontal_axis(aperture)), dot(offset, object_vertical_axis(aperture)));
+}
+
+uint aperture_slit_count(WorldObject aperture) {
+  if (aperture.aperture_type == MULTIPLE_SLITS) {
+    return clamp(aperture.slit_count, uint(1), MAX_SLITS);
+  }
+
+  return uint(1);
+}
+
+float aperture_slit_center(WorldObject aperture, uint slit) {
+  return (float(slit) - (float(aperture_slit_count(aperture)) - 1.0) / 2.0) * aperture.slit_pitch;
+}
+
+bool aperture_transmits(WorldObject aperture, vec2 local) {
+  float half_width = aperture.aperture_width / 2.0;
+  float half_height = aperture.aperture_height / 2.0;
+
+  if (aperture.aperture_type == CIRCULAR_PINHOLE) {
+    return length(local) < half_width;
+  }
+
+  if (abs(local.y) >= half_height) {
+    return false;
+  }
+
+  for (uint slit = uint(0); slit < aperture_slit_count(aperture); slit++) {
+    if (abs(local.x - aperture_slit_center(aperture, slit)) < half_width) {
+      return true;
+    }
+  }
+
+  return false;
+}
+
+// Materials code, mirrors materials.rs
+Material medium_material() {
+  Material material;
+    material.model = medium_definition[0];
+
+    for (uint i = uint(0); i < uint(7); i++) {
+      material.coefficients[i] = uintBitsToFloat(medium_definition[i + uint(1)]);
+    }
+
+    material.wavelength_range = vec2(uintBitsToFloat(medium_definition[8]), uintBitsToFloat(medium_definition[9]));
+
+  return material;
+}
+
+// wavelength in micrometers
+float refractive_index(Material material, float wavelength) {
+  wavelength = clamp(wavelength, material.wavelength_range.x, material.wavelength_range.y);
+  float l2 = wavelength * wavelength;
+
+  if (material.model == CONSTANT_DISPERSION) {
+    return material.coefficients[0];
+  }
+
+  if (material.model == CAUCHY_DISPERSION) {
+    return material.coefficients[0] + material.coefficients[1] / l2 + material.coefficients[2] / (l2 * l2);
+  }
+
+  float n2 = material.coefficients[0];
+  for (int term = 0; term < 3; term++) {
+    n2 += material.coefficients[2 * term + 1] * l2 / (l2 - material.coefficients[2 * term + 2]);
+  }
+
+  return sqrt(max(n2, 1.0));
+}
+
+float medium_refractive_index(float wavelength) {
+  return refractive_index(medium_material(), wavelength);
+}
+
+Material coating_material(uint index) {
+  Material material;
+    uint start = index * MATERIAL_SIZE;
+    material.model = coating_materials_definitions[start / uint(4)][start % uint(4)];
+
+    for (uint i = uint(0); i < uint(9); i++) {
+      uint position = start + uint(1) + i;
+      float value = uintBitsToFloat(coating_materials_definitions[position / uint(4)][position % uint(4)]);
+
+      if (i < uint(7)) {
+        material.coefficients[i] = value;
+      } else {
+        material.wavelength_range[i - uint(7)] = value;
+      }
+    }
+
+  return material;
+}
+
+// Coatings code, mirrors coatings.rs
+float coating_layer_index(WorldObject object, int layer, float wavelength) {
+  if (object.coating_materials[layer] == U32_MAX) {
+    return 1.0;
+  }
+
+  return refractive_index(coating_material(object.coating_materials[layer]), wavelength);
+}
+
+// n cos θ of a layer, imaginary when the light can't get into it
+vec2 coating_n_cos(float n, float tangential_index) {
+  return cx_sqrt(vec2(n * n - tangential_index * tangential_index, 0.0));
+}
+
+vec2 coating_admittance(float n, float tangential_index, bool p_light) {
+  vec2 n_cos = coating_n_cos(n, tangential_index);
+  vec2 n2 = vec2(n * n, 0.0);
+
+  return p_light ? cx_div(n2, n_cos) : n_cos;
+}
+
+// transmission amplitudes of s (xy) and p (zw) light through the coating of object
+// with the characteristic matrix method, scaled so that their squares are the
+// transmitted power, the layers are crossed from the surface out when reversed
+vec4 coating_transmission(WorldObject object, float incident_index, float exit_index, float cos_incidence, float wavelength, bool reversed) {
+  float tangential_index = incident_index * sqrt(max(1.0 - cos_incidence * cos_incidence, 0.0));
+  vec4 transmission;
+
+  for (int polarization = 0; polarization < 2; polarization++) {
+    bool p_light = polarization == 1;
+    vec2 m00 = vec2(1.0, 0.0);
+    vec2 m01 = vec2(0.0);
+    vec2 m10 = vec2(0.0);
+    vec2 m11 = vec2(1.0, 0.0);
+
+    for (int i = 0; i < MAX_COATING_LAYERS; i++) {
+      if (uint(i) >= object.coating_layer_count) {
+        break;
+      }
+
+      int layer = reversed ? int(object.coating_layer_count) - 1 - i : i;
+      float n = coating_layer_index(object, layer, wavelength);
+      vec2 eta = coating_admittance(n, tangential_index, p_light);
+      vec2 phase = coating_n_cos(n, tangential_index) * (2.0 * PI * object.coating_thickness[layer] / wavelength);
+
+      vec2 c = cx_cos(phase);
+      vec2 s = cx_sin(phase);
+      vec2 s_over_eta = cx_div(s, eta);
+      vec2 s_times_eta = cx_mul(s, eta);
+      vec2 a01 = vec2(-s_over_eta.y, s_over_eta.x);
+      vec2 a10 = vec2(-s_times_eta.y, s_times_eta.x);
+
+      vec2 n00 = cx_add(cx_mul(m00, c), cx_mul(m01, a10));
+      vec2 n01 = cx_add(cx_mul(m00, a01), cx_mul(m01, c));
+      vec2 n10 = cx_add(cx_mul(m10, c), cx_mul(m11, a10));
+      vec2 n11 = cx_add(cx_mul(m10, a01), cx_mul(m11, c));
+
+      m00 = n00;
+      m01 = n01;
+      m10 = n10;
+      m11 = n11;
+    }
+
+    vec2 incident_admittance = coating_admittance(incident_index, tangential_index, p_light);
+    vec2 exit_admittance = coating_admittance(exit_index, tangential_index, p_light);
+
+    vec2 b = cx_add(m00, cx_mul(m01, exit_admittance));
+    vec2 c = cx_add(m10, cx_mul(m11, exit_admittance));
+    vec2 denominator = cx_add(cx_mul(incident_admittance, b), c);
+
+    float transmittance = 4.0 * incident_admittance.x * exit_admittance.x / dot(denominator, denominator);
+    vec2 numerator = incident_admittance * 2.0;
+    vec2 amplitude = cx_div(numerator, denominator);
+    vec2 t = sqrt(max(transmittance, 0.0)) * vec2(cos(cx_arg(amplitude)), sin(cx_arg(amplitude)));
+
+    if (p_light) {
+      transmission.zw = t;
+    } else {
+      transmission.xy = t;
+    }
+  }
+
+  return transmission;
+}
+
+// the transmission matrices of s and p light written in the basis of the light,
+// s is polarized along s_axis
+Complex2x2Matrix sp_jones_matrix(vec4 transmission, vec3 s_axis, WorldObject light_object) {
+  vec3 light_x = rotate3dY(rotate3dX(vec3(1.0, 0.0, 0.0), light_object.rotation.y), light_object.rotation.x);
+  vec3 light_y = rotate3dY(rotate3dX(vec3(0.0, 1.0, 0.0), light_object.rotation.y), light_object.rotation.x);
+  float angle = atan(dot(s_axis, light_y), dot(s_axis, light_x));
+  float s = sin(angle);
+  float c = cos(angle);
+
+  // rotation * diag(ts, tp) * transpose(rotation)
+  Complex2x2Matrix jones_matrix;
+    jones_matrix.a = transmission.xy * c * c + transmission.zw * s * s;
+    jones_matrix.b = (transmission.xy - transmission.zw) * s * c;
+    jones_matrix.c = (transmission.xy - transmission.zw) * s * c;
+    jones_matrix.d = transmission.xy * s * s + transmission.zw * c * c;
+
+  return jones_matrix;
+}
+
+vec3 light_direction(WorldObject light_object) {
+  vec3 light_dir = vec3(0.0, 0.0, -1.0);
+  light_dir = rotate3dX(light_dir, light_object.rotation.y);
+  light_dir = rotate3dY(light_dir, light_object.rotation.x);
+  return normalize(light_dir);
+}
+
+// scalar part of a gaussian beam with its waist at center going towards light_dir,
+// propagating through the medium of the lab, mirrors field.rs
+vec2 gaussian_beam_amplitude_along(vec3 center, vec3 light_dir, float wavelength, float refractive_index, vec3 point) {
+  // gaussian beams only go forward
+  if (dot(light_dir, point - center) <= 0.0) {
+    return vec2(0.0);
+  }
+
+  // virtual distance
+  float radius = computeDistance(center, center + light_dir, point) * cube_scaling_factor;
+  // distance along the axis of the beam, the curvature of the wavefront is
+  // already taken into account by the radius term of the phase
+  float z = dot(point - center, light_dir) * cube_scaling_factor;
+  float n = refractive_index;
+
+  // Gaussian beam definition
+  // TODO: this should also be part of some light definition
+  float w0 = 5.0;
+  float z_r = (PI * w0 * w0 * n) / wavelength;
+  float w_z = w0 * sqrt(1.0 + pow(z / z_r, 2.0));
+  float R_z = z * (1.0 + pow(z_r / z, 2.0));
+  float gouy_z = atan(z / z_r);
+  float k = (2.0 * PI * n) / wavelength;
+
+  vec2 envelope = vec2((w0 / w_z) * exp(-pow(radius, 2.0) / pow(w_z, 2.0)), 0);
+  vec2 phase = cx_exp(vec2(0.0, k * z + k * (pow(radius, 2.0) / (2.0 * R_z)) - gouy_z));
+
+  return cx_mul(envelope, phase) * 2.0;
+}
+
+// scalar part of the gaussian beam emitted by light_object at point
+vec2 gaussian_beam_amplitude(WorldObject light_object, vec3 point) {
+  return gaussian_beam_amplitude_along(light_object.center, light_direction(light_object), light_object.wavelength, medium_refractive_index(light_object.material_wavelength), point);
+}
+
+// Huygens-Fresnel integral of the light that goes through the openings of the
+// aperture and reaches point, slits are integrated only across their width
+vec2 diffracted_amplitude(WorldObject light_object, WorldObject aperture, vec3 point) {
+  vec3 horizontal = object_horizontal_axis(aperture);
+  vec3 normal = object_normal(aperture);
+  vec3 vertical = object_vertical_axis(aperture);
+  // wavelength inside of the medium
+  float wavelength = light_object.wavelength / medium_refractive_index(light_object.material_wavelength);
+  float k = 2.0 * PI / wavelength;
+
+  vec2 amplitude = vec2(0.0);
+
+  if (aperture.aperture_type == SINGLE_SLIT || aperture.aperture_type == MULTIPLE_SLITS) {
+    float observed_height = clamp(aperture_local_coordinates(aperture, point).y, -aperture.aperture_height / 2.0, aperture.aperture_height / 2.0);
+    float du = aperture.aperture_width / float(SAMPLES_PER_SLIT);
+
+    for (uint slit = uint(0); slit < aperture_slit_count(aperture); slit++) {
+      for (int i = 0; i < SAMPLES_PER_SLIT; i++) {
+        float u = aperture_slit_center(aperture, slit) - aperture.aperture_width / 2.0 + (float(i) + 0.5) * du;
+        vec3 source_point = aperture.center + horizontal * u + vertical * observed_height;
+
+        float r = length(point - source_point) * cube_scaling_factor;
+        float obliquity = abs(dot(normal, point - source_point)) * cube_scaling_factor / r;
+
+        // cylindrical huygens wavelet e^(ikr) / sqrt(iλr)
+        vec2 wavelet = cx_exp(vec2(0.0, k * r - PI / 4.0)) / sqrt(wavelength * r);
+        vec2 incident = gaussian_beam_amplitude(light_object, source_point);
+        amplitude += cx_mul(incident, wavelet) * obliquity * du * cube_scaling_factor;
+      }
+    }
+
+  } else {
+    vec2 side = vec2(aperture.aperture_width, aperture.aperture_height);
+    if (aperture.aperture_type == CIRCULAR_PINHOLE) {
+      side.y = aperture.aperture_width;
+    }
+
+    vec2 delta = side / float(SAMPLES_PER_SIDE);
+
+    for (int i = 0; i < SAMPLES_PER_SIDE; i++) {
+      for (int j = 0; j < SAMPLES_PER_SIDE; j++) {
+        vec2 local = -side / 2.0 + (vec2(float(i), float(j)) + 0.5) * delta;
+
+        if (!aperture_transmits(aperture, local)) {
+          continue;
+        }
+
+        vec3 source_point = aperture.center + horizontal * local.x + vertical * local.y;
+
+        float r = length(point - source_point) * cube_scaling_factor;
+        float obliquity = abs(dot(normal, point - source_point)) * cube_scaling_factor / r;
+
+        // spherical huygens wavelet e^(ikr) / (iλr)
+        vec2 wavelet = cx_exp(vec2(0.0, k * r - PI / 2.0)) / (wavelength * r);
+        vec2 incident = gaussian_beam_amplitude(light_object, source_point);
+        amplitude += cx_mul(incident, wavelet) * obliquity * delta.x * delta.y * cube_scaling_factor * cube_scaling_factor;
+      }
+    }
+  }
+
+  return amplitude;
+}
+
+// linear retarder with its fast axis at fast_axis radians from the horizontal axis, mirrors world.rs
+Complex2x2Matrix retarder_jones_matrix(float retardance, float fast_axis) {
+  float s = sin(fast_axis);
+  float c = cos(fast_axis);
+
+  vec2 fast = cx_exp(vec2(0.0, -retardance / 2.0));
+  vec2 slow = cx_exp(vec2(0.0, retardance / 2.0));
+
+  Complex2x2Matrix jones_matrix;
+    jones_matrix.a = fast * c * c + slow * s * s;
+    jones_matrix.b = (fast - slow) * s * c;
+    jones_matrix.c = (fast - slow) * s * c;
+    jones_matrix.d = fast * s * s + slow * c * c;
+
+  return jones_matrix;
+}
+
+// jones matrix of a chromatic retarder for light of the given wavelength (in micrometers),
+// fast axis at polarizer_angle, mirrors world.rs
+Complex2x2Matrix chromatic_retarder_jones_matrix(WorldObject element, float wavelength) {
+  float birefringence = element.birefringence + element.birefringence_dispersion / pow(wavelength, 2.0);
+  return retarder_jones_matrix(2.0 * PI * birefringence * element.retarder_thickness / wavelength, element.polarizer_angle);
+}
+
+// Spatially varying elements code, mirrors spatial.rs
+// value of a map of the element under the point (x, y) of its disk, from 0 to 1
+float element_map_value(WorldObject element, uint channel, vec2 disk) {
+  int column = min(int((disk.x + 1.0) / 2.0 * float(MAP_RESOLUTION)), MAP_RESOLUTION - 1);
+  int row = min(int((1.0 - disk.y) / 2.0 * float(MAP_RESOLUTION)), MAP_RESOLUTION - 1);
+
+  return texelFetch(element_maps, ivec3(column, row, int(element.element_map_layer)), 0)[channel];
+}
+
+bool uses_element_map(WorldObject element, uint channel) {
+  return element.element_map_layer != U32_MAX && (element.element_map_channels & (uint(1) << channel)) != uint(0);
+}
+
+// difference between the principal stresses (in pascals) at (x, y) of the disk of a
+// photoelastic sample and the angle of the first one from the horizontal axis
+vec2 principal_stresses(WorldObject element, vec2 disk) {
+  // in meters
+  float size = element.sample_size / 1000.0;
+  float thickness = element.sample_thickness / 1000.0;
+  vec3 stresses;
+
+  // disk under diametral compression along its vertical axis
+  if (element.stress_field == uint(0)) {
+    float radius = size / 2.0;
+    float x = disk.x * radius;
+    float y = disk.y * radius;
+    float r1 = max(pow(x * x + (radius - y) * (radius - y), 2.0), 1e-12);
+    float r2 = max(pow(x * x + (radius + y) * (radius + y), 2.0), 1e-12);
+    float scale = 2.0 * element.applied_load / (PI * thickness);
+
+    stresses = vec3(
+      -scale * (x * x * (radius - y) / r1 + x * x * (radius + y) / r2 - 1.0 / size),
+      -scale * (pow(radius - y, 3.0) / r1 + pow(radius + y, 3.0) / r2 - 1.0 / size),
+      scale * (x * (radius - y) * (radius - y) / r1 - x * (radius + y) * (radius + y) / r2)
+    );
+
+  // beam in pure bending
+  } else {
+    float y = disk.y * size / 2.0;
+    stresses = vec3(12.0 * element.applied_load * y / (thickness * pow(size, 3.0)), 0.0, 0.0);
+  }
+
+  float difference = stresses.x - stresses.y;
+  return vec2(sqrt(difference * difference + 4.0 * stresses.z * stresses.z), 0.5 * atan(2.0 * stresses.z, difference));
+}
+
+// copy of the element with the jones matrix it has at point, so that it can
+// go through jones_matrix_for_light like any other element
+WorldObject element_at(WorldObject element, vec3 point) {
+  if (element.polarizer_type != Q_PLATE && element.polarizer_type != VORTEX_RETARDER && element.polarizer_type != IMAGE_ELEMENT && element.polarizer_type != PHOTOELASTIC_SAMPLE) {
+    return element;
+  }
+
+  vec3 offset = point - element.center;
+  vec2 disk = vec2(dot(offset, object_horizontal_axis(element)), dot(offset, object_vertical_axis(element))) / drawn_radius(element);
+  float azimuth = atan(disk.y, disk.x);
+
+  // the fast axis turns q times as fast as the azimuth
+  if (element.polarizer_type == Q_PLATE) {
+    element.jones_matrix = retarder_jones_matrix(element.spatial_retardance, element.topological_charge * azimuth + element.polarizer_angle);
+
+  // a half-wave plate whose fast axis turns m / 2 times as fast as the azimuth
+  } else if (element.polarizer_type == VORTEX_RETARDER) {
+    element.jones_matrix = retarder_jones_matrix(PI, element.topological_charge * azimuth / 2.0 + element.polarizer_angle);
+
+  // a chromatic retarder following the stress-optic law, Δn = C (σ1 - σ2)
+  } else if (element.polarizer_type == PHOTOELASTIC_SAMPLE) {
+    vec2 stresses = principal_stresses(element, disk);
+
+    element.polarizer_type = CHROMATIC_RETARDER;
+    element.polarizer_angle = stresses.y;
+    element.birefringence = element.stress_optic_coefficient * 1e-12 * stresses.x;
+    element.birefringence_dispersion = 0.0;
+    element.retarder_thickness = element.sample_thickness * 1000.0;
+
+  // whatever the maps don't give is uniform
+  } else {
+    float retardance = uses_element_map(element, RETARDANCE_MAP) ? element_map_value(element, RETARDANCE_MAP, disk) * 2.0 * PI : element.spatial_retardance;
+    float fast_axis = uses_element_map(element, FAST_AXIS_MAP) ? element_map_value(element, FAST_AXIS_MAP, disk) * PI : element.polarizer_angle;
+    float amplitude = uses_element_map(element, AMPLITUDE_MAP) ? element_map_value(element, AMPLITUDE_MAP, disk) : 1.0;
+
+    element.jones_matrix = cx_scalar_x_2x2_mat_mul(vec2(amplitude, 0.0), retarder_jones_matrix(retardance, fast_axis));
+  }
+
+  return element;
+}
+
+// Etalon code, mirrors etalon.rs
+// amplitude that gets through a fabry-pérot etalon, the coherent sum of the light
+// that leaves after every round trip between its mirrors
+vec2 etalon_transmission(WorldObject etalon, float wavelength, float cos_incidence) {
+  float cos_inside = sqrt(max(1.0 - (1.0 - cos_incidence * cos_incidence) / (etalon.etalon_index * etalon.etalon_index), 0.0));
+  float phase = 4.0 * PI * etalon.etalon_index * etalon.etalon_gap * cos_inside / wavelength;
+  vec2 round_trip = etalon.etalon_reflectance * vec2(cos(phase), sin(phase));
+
+  vec2 pass = (1.0 - etalon.etalon_reflectance) * vec2(cos(phase / 2.0), sin(phase / 2.0));
+  vec2 transmission = vec2(0.0);
+
+  for (int i = 0; i < MAX_ROUND_TRIPS; i++) {
+    transmission += pass;
+    pass = cx_mul(pass, round_trip);
+  }
+
+  return transmission;
+}
+
+// jones matrix of an optical object written in the basis of the light going through
+// it in the direction propagation, mirrors field.rs
+Complex2x2Matrix jones_matrix_for_light(WorldObject element, WorldObject light_object, vec3 propagation) {
+  vec3 horizontal = object_horizontal_axis(element);
+  vec3 vertical = object_vertical_axis(element);
+  vec3 light_x = rotate3dY(rotate3dX(vec3(1.0, 0.0, 0.0), light_object.rotation.y), light_object.rotation.x);
+  vec3 light_y = rotate3dY(rotate3dX(vec3(0.0, 1.0, 0.0), light_object.rotation.y), light_object.rotation.x);
+
+  // axes of the element projected onto the plane perpendicular to the light
+  horizontal = normalize(horizontal - propagation * dot(horizontal, propagation));
+  vertical = vertical - propagation * dot(vertical, propagation);
+  vertical = normalize(vertical - horizontal * dot(vertical, horizontal));
+
+  Complex2x2Matrix basis_change;
+    basis_change.a = vec2(dot(light_x, horizontal), 0.0);
+    basis_change.b = vec2(dot(light_x, vertical), 0.0);
+    basis_change.c = vec2(dot(light_y, horizontal), 0.0);
+    basis_change.d = vec2(dot(light_y, vertical), 0.0);
+
+  Complex2x2Matrix basis_change_transposed = basis_change;
+    basis_change_transposed.b = basis_change.c;
+    basis_change_transposed.c = basis_change.b;
+
+  Complex2x2Matrix jones_matrix = element.jones_matrix;
+
+  // the retardance of chromatic retarders depends on the wavelength of the light
+  if (element.polarizer_type == CHROMATIC_RETARDER) {
+    jones_matrix = chromatic_retarder_jones_matrix(element, light_object.material_wavelength);
+  }
+
+  // and so does the transmission of etalons, which also depends on the angle of the light
+  if (element.polarizer_type == FABRY_PEROT_ETALON) {
+    vec2 transmission = etalon_transmission(element, light_object.material_wavelength, abs(dot(propagation, object_normal(element))));
+
+    jones_matrix.a = transmission;
+    jones_matrix.b = vec2(0.0);
+    jones_matrix.c = vec2(0.0);
+    jones_matrix.d = transmission;
+  }
+
+  // reciprocal elements act as their transpose when the light goes against
+  // their normal, faraday rotators don't and that's what makes them non-reciprocal
+  if (dot(propagation, object_normal(element)) < 0.0 && element.polarizer_type != FARADAY_ROTATOR) {
+    vec2 b = jones_matrix.b;
+    jones_matrix.b = jones_matrix.c;
+    jones_matrix.c = b;
+  }
+
+  return cx_2x2_mat_mul(basis_change, cx_2x2_mat_mul(jones_matrix, basis_change_transposed));
+}
+
+// transmission of the coating of a thin optical object in the basis of the light
+// going through it along propagation, from the medium into the material of the object
+Complex2x2Matrix coating_jones_matrix(WorldObject element, WorldObject light_object, vec3 propagation) {
+  vec3 normal = object_normal(element);
+  vec4 transmission = coating_transmission(
+    element,
+    medium_refractive_index(light_object.material_wavelength),
+    refractive_index(element.material, light_object.material_wavelength),
+    abs(dot(propagation, normal)),
+    light_object.material_wavelength,
+    false
+  );
+
+  // s light is polarized perpendicular to the plane of incidence
+  vec3 s_axis = cross(normal, propagation);
+  if (length(s_axis) < 1e-4) {
+    s_axis = object_horizontal_axis(element);
+  }
+
+  return sp_jones_matrix(transmission, s_axis, light_object);
+}
+
+// Grating code, mirrors grating.rs
+float sinc(float x) {
+  if (abs(x) < 1e-6) {
+    return 1.0;
+  }
+
+  return sin(x) / x;
+}
+
+bool grating_incidence_point(WorldObject grating, WorldObject light_object, out vec3 hit) {
+  vec3 normal = object_normal(grating);
+  vec3 light_dir = light_direction(light_object);
+
+  float cos_incidence = dot(light_dir, normal);
+  if (abs(cos_incidence) < 1e-4) {
+    return false;
+  }
+
+  float distance = dot(grating.center - light_object.center, normal) / cos_incidence;
+  hit = light_object.center + light_dir * distance;
+
+  return distance > 0.0 && length(hit - grating.center) <= drawn_radius(grating);
+}
+
+// grating equation in vector form, wavelength inside of the medium
+bool grating_order_direction(WorldObject grating, vec3 incident, int order, float wavelength, out vec3 direction) {
+  vec3 grating_vector = object_horizontal_axis(grating);
+  vec3 normal = object_normal(grating);
+  float period = 1.0 / grating.groove_density;
+
+  float cos_incidence = dot(incident, normal);
+  vec3 tangential = incident - normal * cos_incidence + grating_vector * (float(order) * wavelength / period);
+
+  float tangential_squared = dot(tangential, tangential);
+  if (tangential_squared >= 1.0) {
+    return false;
+  }
+
+  float normal_component = sqrt(1.0 - tangential_squared) * sign(cos_incidence);
+
+  if (grating.grating_type == TRANSMISSION_GRATING) {
+    direction = tangential + normal * normal_component;
+  } else {
+    direction = tangential - normal * normal_component;
+  }
+
+  return true;
+}
+
+float grating_order_efficiency(WorldObject grating, int order, float wavelength, float refractive_index) {
+  float period = 1.0 / grating.groove_density;
+
+  if (grating.blaze_angle == 0.0) {
+    return 0.25 * pow(sinc(PI * float(order) / 2.0), 2.0);
+  }
+
+  float optical_path_difference;
+  if (grating.grating_type == TRANSMISSION_GRATING) {
+    optical_path_difference = (GRATING_REFRACTIVE_INDEX - refractive_index) * period * tan(grating.blaze_angle);
+  } else {
+    optical_path_difference = 2.0 * refractive_index * period * sin(grating.blaze_angle);
+  }
+
+  return pow(sinc(PI * (float(order) - optical_path_difference / wavelength)), 2.0);
+}
+
+// field that reaches point through every order of the grating, the orders are
+// gaussian beams that start where the light hits the grating
+Polarization grating_orders_field(WorldObject light_object, WorldObject grating, vec3 point) {
+  Polarization field;
+    field.Ex = vec2(0.0);
+    field.Ey = vec2(0.0);
+
+  vec3 hit;
+  if (!grating_incidence_point(grating, light_object, hit)) {
+    return field;
+  }
+
+  vec3 normal = object_normal(grating);
+  vec3 incident = light_direction(light_object);
+
+  // transmitted orders only exist behind the grating, reflected ones in front of it
+  bool point_behind_grating = sign(dot(point - grating.center, normal)) == sign(dot(incident, normal));
+  if (point_behind_grating != (grating.grating_type == TRANSMISSION_GRATING)) {
+    return field;
+  }
+
+  vec3 undiffracted = incident;
+  if (grating.grating_type == REFLECTION_GRATING) {
+    undiffracted = reflect(incident, normal);
+  }
+
+  // angle of the grooves in the basis of the jones vector of the light
+  vec3 grooves = object_vertical_axis(grating);
+  vec3 light_x = rotate3dY(rotate3dX(vec3(1.0, 0.0, 0.0), light_object.rotation.y), light_object.rotation.x);
+  vec3 light_y = rotate3dY(rotate3dX(vec3(0.0, 1.0, 0.0), light_object.rotation.y), light_object.rotation.x);
+  float angle = atan(dot(grooves, light_y), dot(grooves, light_x));
+  mat2 rotation = mat2(cos(angle), sin(angle), -sin(angle), cos(angle));
+
+  float distance_to_grating = length(hit - light_object.center);
+  float medium_index = medium_refractive_index(light_object.material_wavelength);
+
+  for (int order = -MAX_GRATING_ORDER; order <= MAX_GRATING_ORDER; order++) {
+    vec3 direction;
+    if (!grating_order_direction(grating, incident, order, light_object.wavelength / medium_index, direction)) {
+      continue;
+    }
+
+    float te = sqrt(grating_order_efficiency(grating, order, light_object.wavelength, medium_index));
+    float tm = te * abs(dot(direction, undiffracted));
+    mat2 jones_matrix = rotation * mat2(te, 0.0, 0.0, tm) * transpose(rotation);
+
+    // the waist of the order stays where the waist of the light was so that
+    // the beam keeps spreading and the phase is continuous at the grating
+    vec2 amplitude = gaussian_beam_amplitude_along(hit - direction * distance_to_grating, direction, light_object.wavelength, medium_index, point);
+
+    // the jones matrix is real so it can be applied to both parts separately
+    vec2 re = jones_matrix * vec2(light_object.polarization.Ex.x, light_object.polarization.Ey.x);
+    vec2 im = jones_matrix * vec2(light_object.polarization.Ex.y, light_object.polarization.Ey.y);
+
+    field.Ex += cx_mul(vec2(re.x, im.x), amplitude);
+    field.Ey += cx_mul(vec2(re.y, im.y), amplitude);
+  }
+
+  return field;
+}
+
+// Prism code, mirrors prism.rs
+// light that leaves a prism or a beam displacer
+struct RefractedBeam {
+  // where the axis of the light goes in and out of the object
+  vec3 entry;
+  vec3 exit;
+  vec3 direction;
+  // where the waist of the outgoing beam would be if it had always
+  // travelled in the medium, keeps the phase continuous across the object
+  vec3 waist;
+  // outward normal of the face the light leaves through
+  vec3 exit_normal;
+  // what happens to the polarization in the basis of the light
+  Complex2x2Matrix jones_matrix;
+};
+
+// follows the axis of the light through both refracting faces of the prism, false
+// if it misses them or gets totally reflected inside
+bool prism_beam(WorldObject prism, WorldObject light_object, out RefractedBeam beam) {
+  vec3 incident = light_direction(light_object);
+  float prism_index = refractive_index(prism.material, light_object.material_wavelength);
+  float medium_index = medium_refractive_index(light_object.material_wavelength);
+
+  vec2 distances;
+  ivec2 planes;
+  if (!convex_intersection(prism, light_object.center, incident, distances, planes) || distances.x <= 0.0 || planes.x >= PRISM_REFRACTING_FACES) {
+    return false;
+  }
+
+  beam.entry = light_object.center + incident * distances.x;
+  vec3 entry_normal = prism_plane(prism, planes.x).xyz;
+  vec3 internal_direction = refract(incident, entry_normal, medium_index / prism_index);
+  if (internal_direction == vec3(0.0)) {
+    return false;
+  }
+
+  // first plane the light reaches from the inside
+  float inside_distance = 10000.0;
+  int exit_plane = planes.x;
+  for (int plane = 0; plane < PRISM_PLANES; plane++) {
+    vec4 bound = prism_plane(prism, plane);
+    float denominator = dot(bound.xyz, internal_direction);
+
+    if (denominator > 1e-6) {
+      float t = (bound.w - dot(bound.xyz, beam.entry - prism.center)) / denominator;
+      if (t < inside_distance) {
+        inside_distance = t;
+        exit_plane = plane;
+      }
+    }
+  }
+
+  if (exit_plane >= PRISM_REFRACTING_FACES || exit_plane == planes.x) {
+    return false;
+  }
+
+  beam.exit = beam.entry + internal_direction * inside_distance;
+  beam.exit_normal = prism_plane(prism, exit_plane).xyz;
+  beam.direction = refract(internal_direction, -beam.exit_normal, prism_index / medium_index);
+  if (beam.direction == vec3(0.0)) {
+    return false;
+  }
+
+  // both faces have the same coating, the light crosses it backwards on the way out,
+  // without one it's just the fresnel transmission of the bare faces
+  vec4 t_in = coating_transmission(prism, medium_index, prism_index, -dot(incident, entry_normal), light_object.material_wavelength, false);
+  vec4 t_out = coating_transmission(prism, prism_index, medium_index, dot(internal_direction, beam.exit_normal), light_object.material_wavelength, true);
+  vec4 transmission = vec4(cx_mul(t_in.xy, t_out.xy), cx_mul(t_in.zw, t_out.zw));
+
+  // s light is polarized along the apex edge
+  beam.jones_matrix = sp_jones_matrix(transmission, object_vertical_axis(prism), light_object);
+  beam.waist = beam.exit - beam.direction * (distances.x + inside_distance * prism_index / medium_index);
+
+  return true;
+}
+
+// Beam displacer code, mirrors displacer.rs
+vec3 optic_axis_direction(WorldObject displacer) {
+  vec3 azimuth = object_horizontal_axis(displacer) * cos(displacer.optic_axis.y) + object_vertical_axis(displacer) * sin(displacer.optic_axis.y);
+  return object_normal(displacer) * cos(displacer.optic_axis.x) + azimuth * sin(displacer.optic_axis.x);
+}
+
+// index seen by an extraordinary wave going at angle from the optic axis
+float extraordinary_index(float ordinary, float extraordinary, float angle) {
+  return 1.0 / sqrt(pow(cos(angle) / ordinary, 2.0) + pow(sin(angle) / extraordinary, 2.0));
+}
+
+// polarization of the extraordinary wave, in the plane of the wave vector and
+// the optic axis, when they are parallel any direction works
+vec3 extraordinary_polarization(vec3 wave_direction, vec3 optic_axis, vec3 fallback) {
+  vec3 ordinary = cross(wave_direction, optic_axis);
+
+  if (length(ordinary) < 1e-4) {
+    ordinary = cross(wave_direction, fallback);
+  }
+
+  return normalize(cross(wave_direction, normalize(ordinary)));
+}
+
+// follows the axis of the light through the block for the ordinary or the
+// extraordinary ray, false if it misses the faces or leaves through a side
+bool displacer_beam(WorldObject displacer, WorldObject light_object, bool extraordinary, out RefractedBeam beam) {
+  vec3 incident = light_direction(light_object);
+  vec3 optic_axis = optic_axis_direction(displacer);
+  vec3 horizontal = object_horizontal_axis(displacer);
+  float ordinary_index = refractive_index(displacer.material, light_object.material_wavelength);
+  float principal_extraordinary_index = refractive_index(displacer.extraordinary_material, light_object.material_wavelength);
+  float medium_index = medium_refractive_index(light_object.material_wavelength);
+
+  vec2 distances;
+  ivec2 planes;
+  if (!convex_intersection(displacer, light_object.center, incident, distances, planes) || distances.x <= 0.0 || planes.x > 1) {
+    return false;
+  }
+
+  beam.entry = light_object.center + incident * distances.x;
+  vec3 entry_normal = displacer_plane(displacer, planes.x).xyz;
+
+  // the index of the extraordinary wave depends on the direction it refracts into
+  float index = ordinary_index;
+  vec3 wave_direction = refract(incident, entry_normal, medium_index / index);
+
+  if (extraordinary) {
+    for (int i = 0; i < 8; i++) {
+      index = extraordinary_index(ordinary_index, principal_extraordinary_index, acos(clamp(dot(wave_direction, optic_axis), -1.0, 1.0)));
+      wave_direction = refract(incident, entry_normal, medium_index / index);
+    }
+  }
+
+  if (wave_direction == vec3(0.0)) {
+    return false;
+  }
+
+  // the energy of the extraordinary wave goes along the poynting vector
+  vec3 ray_direction = wave_direction;
+  if (extraordinary) {
+    vec3 polarization = extraordinary_polarization(wave_direction, optic_axis, horizontal);
+    vec3 electric_field = polarization / (ordinary_index * ordinary_index)
+      + optic_axis * dot(optic_axis, polarization) * (1.0 / (principal_extraordinary_index * principal_extraordinary_index) - 1.0 / (ordinary_index * ordinary_index));
+
+    ray_direction = normalize(wave_direction * dot(electric_field, electric_field) - electric_field * dot(electric_field, wave_direction));
+  }
+
+  // it has to leave through the opposite face
+  int exit_plane = planes.x == 0 ? 1 : 0;
+  float inside_distance = 10000.0;
+  int closest_plane = planes.x;
+  for (int plane = 0; plane < DISPLACER_PLANES; plane++) {
+    vec4 bound = displacer_plane(displacer, plane);
+    float denominator = dot(bound.xyz, ray_direction);
+
+    if (denominator > 1e-6) {
+      float t = (bound.w - dot(bound.xyz, beam.entry - displacer.center)) / denominator;
+      if (t < inside_distance) {
+        inside_distance = t;
+        closest_plane = plane;
+      }
+    }
+  }
+
+  if (closest_plane != exit_plane) {
+    return false;
+  }
+
+  beam.exit = beam.entry + ray_direction * inside_distance;
+  beam.exit_normal = displacer_plane(displacer, exit_plane).xyz;
+  beam.direction = refract(wave_direction, -beam.exit_normal, index / medium_index);
+  if (beam.direction == vec3(0.0)) {
+    return false;
+  }
+
+  // the phase follows the wave vector, not the ray
+  beam.waist = beam.exit - beam.direction * (distances.x + inside_distance * dot(wave_direction, ray_direction) * index / medium_index);
+
+  // projection onto the polarization of the ray once it's out
+  vec3 polarization = extraordinary_polarization(beam.direction, optic_axis, horizontal);
+  if (!extraordinary) {
+    polarization = cross(polarization, beam.direction);
+  }
+
+  vec3 light_x = rotate3dY(rotate3dX(vec3(1.0, 0.0, 0.0), light_object.rotation.y), light_object.rotation.x);
+  vec3 light_y = rotate3dY(rotate3dX(vec3(0.0, 1.0, 0.0), light_object.rotation.y), light_object.rotation.x);
+  vec2 projection = vec2(dot(polarization, light_x), dot(polarization, light_y));
+  beam.jones_matrix.a = vec2(projection.x * projection.x, 0.0);
+  beam.jones_matrix.b = vec2(projection.x * projection.y, 0.0);
+  beam.jones_matrix.c = vec2(projection.x * projection.y, 0.0);
+  beam.jones_matrix.d = vec2(projection.y * projection.y, 0.0);
+
+  return true;
+}
+
+// field that reaches point after going through a prism or a beam displacer, the
+// light leaves it as a new gaussian beam from its exit face
+Polarization refracted_field(WorldObject light_object, RefractedBeam beam, vec3 point) {
+  Polarization field;
+    field.Ex = vec2(0.0);
+    field.Ey = vec2(0.0);
+
+  if (dot(point - beam.exit, beam.exit_normal) <= 0.0) {
+    return field;
+  }
+
+  vec2 amplitude = gaussian_beam_amplitude_along(beam.waist, beam.direction, light_object.wavelength, medium_refractive_index(light_object.material_wavelength), point);
+
+  Polarization transmitted = cx_2x2_mat_x_cx_pol_mul(beam.jones_matrix, light_object.polarization);
+
+  field.Ex = cx_mul(transmitted.Ex, amplitude);
+  field.Ey = cx_mul(transmitted.Ey, amplitude);
+
+  return field;
+}
+
 bool iterateRayTowardsLightSource(inout RayObject ray, ObjectGoal goal) {
   bool is_first_object = true;
   bool found_first_optical_object = false;
@@ -522,21 +1739,45 @@ bool iterateRayTowardsLightSource(inout RayObject ray, ObjectGoal goal) {
         vec3 pos_hit = object_hit_distance(object, ray);
         float curr_distance_traveled = length(pos_hit - ray.pos);
 
-        bool is_valid_collision_target = (object.type != LIGHT_SOURCE) || (objects[(current_index * uint(3)) + uint(1)] == goal.goal_index);
+        bool is_valid_collision_target = ((object.type != LIGHT_SOURCE) || (objects[(current_index * uint(3)) + uint(1)] == goal.goal_index)) && object.type != PROBE;
 
         if (all(greaterThan(pos_hit, vec3(-0.5))) && curr_distance_traveled < min_distance && is_valid_collision_target) {
-          if (object.type == OPTICAL_OBJECT_ROUND_WALL) {
-            ray.optical_objects_through_which_it_passed += 1;
+          uint object_bit = uint(1) << objects[(current_index * uint(3)) + uint(1)];
 
-            if (!found_first_optical_object) {
-              ray.optical_objects_found_product = object.jones_matrix;
-              found_first_optical_object = true;
+          if (object.type == OPTICAL_OBJECT_ROUND_WALL) {
+            if ((ray.optical_objects_applied & object_bit) == uint(0)) {
+              ray.optical_objects_applied |= object_bit;
+              ray.optical_objects_through_which_it_passed += 1;
+
+              // the light travels against the direction of this ray
+              Complex2x2Matrix jones_matrix = jones_matrix_for_light(element_at(object, pos_hit), goal.goal, -ray.dir);
+
+              // the light goes through the coating before the element itself
+              if (object.coating_layer_count > uint(0)) {
+                jones_matrix = cx_2x2_mat_mul(jones_matrix, coating_jones_matrix(object, goal.goal, -ray.dir));
+              }
+
+              // we are walking from the surface back to the light, so every new object
+              // is one the light went through before the ones we already found
+              if (!found_first_optical_object) {
+                ray.optical_objects_found_product = jones_matrix;
+                found_first_optical_object = true;
+
+              } else {
+                ray.optical_objects_found_product = cx_2x2_mat_mul(
+                  ray.optical_objects_found_product,
+                  jones_matrix
+                );
+              }
+            }
 
-            } else {
-              ray.optical_objects_found_product = cx_2x2_mat_mul(
-                object.jones_matrix,
-                ray.optical_objects_found_product
-              );
+          } else if (object.type == APERTURE) {
+            // the light that reaches us through an aperture is computed
+            // later on with the diffraction integral
+            if (!ray.crossed_aperture || curr_distance_traveled > ray.aperture_distance) {
+              ray.crossed_aperture = true;
+              ray.aperture_index = objects[(current_index * uint(3)) + uint(1)];
+              ray.aperture_distance = curr_distance_traveled;
             }
 
           } else {
@@ -565,9 +1806,9 @@ bool iterateRayTowardsLightSource(inout RayObject ray, ObjectGoal goal) {
           return true;
       }
 
-      ray.color.x *= uintBitsToFloat(objects_definitions[(ray.object_hit * OBJECT_SIZE) + uint(6)]);
-      ray.color.y *= uintBitsToFloat(objects_definitions[(ray.object_hit * OBJECT_SIZE) + uint(7)]);
-      ray.color.z *= uintBitsToFloat(objects_definitions[(ray.object_hit * OBJECT_SIZE) + uint(8)]);
+      ray.color.x *= uintBitsToFloat(object_definition((ray.object_hit * OBJECT_SIZE) + uint(6)));
+      ray.color.y *= uintBitsToFloat(object_definition((ray.object_hit * OBJECT_SIZE) + uint(7)));
+      ray.color.z *= uintBitsToFloat(object_definition((ray.object_hit * OBJECT_SIZE) + uint(8)));
       ray.color.a = 1.0;
 
       ray.ended_in_hit = true;
@@ -629,7 +1870,22 @@ bool iterateRayInDirection(inout RayObject ray) {
         float curr_distance_traveled = length(pos_hit - ray.pos);
 
         if (all(greaterThan(pos_hit, vec3(-0.5))) && curr_distance_traveled < min_distance) {
-          if (object.type == OPTICAL_OBJECT_ROUND_WALL) {
+          if (object.type == APERTURE && aperture_transmits(object, aperture_local_coordinates(object, pos_hit))) {
+            // we can see through the openings of the screen
+
+          } else if (object.type == GRATING && object.grating_type == TRANSMISSION_GRATING) {
+            // transmission gratings are mostly see through
+            ray.color *= 0.5;
+            ray.object_hit = objects[(current_index * uint(3)) + uint(1)];
+            ray.ended_in_hit = true;
+
+          } else if (object.type == PRISM || object.type == BEAM_DISPLACER) {
+            // glass or crystal, tinted by the color of the object
+            ray.color.xyz *= mix(vec3(1.0), object.color, 0.5);
+            ray.object_hit = objects[(current_index * uint(3)) + uint(1)];
+            ray.ended_in_hit = true;
+
+          } else if (object.type == OPTICAL_OBJECT_ROUND_WALL) {
             ray.color *= 0.1;
             ray.object_hit = objects[(current_index * uint(3)) + uint(1)];
             ray.ended_in_hit = true;
@@ -652,9 +1908,9 @@ bool iterateRayInDirection(inout RayObject ray) {
 
       WorldObject object_hit = get_object_at_index(ray.object_hit);
 
-      ray.color.x *= uintBitsToFloat(objects_definitions[(ray.object_hit * OBJECT_SIZE) + uint(6)]);
-      ray.color.y *= uintBitsToFloat(objects_definitions[(ray.object_hit * OBJECT_SIZE) + uint(7)]);
-      ray.color.z *= uintBitsToFloat(objects_definitions[(ray.object_hit * OBJECT_SIZE) + uint(8)]);
+      ray.color.x *= uintBitsToFloat(object_definition((ray.object_hit * OBJECT_SIZE) + uint(6)));
+      ray.color.y *= uintBitsToFloat(object_definition((ray.object_hit * OBJECT_SIZE) + uint(7)));
+      ray.color.z *= uintBitsToFloat(object_definition((ray.object_hit * OBJECT_SIZE) + uint(8)));
       ray.color.a = 1.0;
 
       ray.ended_in_hit = true;
@@ -687,6 +1943,234 @@ bool iterateRayInDirection(inout RayObject ray) {
   return false;
 }
 
+// field of a light at a point in free space, the paths main() adds up for the walls
+// (straight from the light, the orders of gratings and the beams of prisms and beam
+// displacers) without checking on which side of a wall the point is
+Polarization light_field_at(uint light_source_index, vec3 point) {
+  WorldObject light_object = get_object_at_index(lights_definitions_indices[light_source_index]);
+
+  Polarization field;
+    field.Ex = vec2(0.0);
+    field.Ey = vec2(0.0);
+
+  ObjectGoal light_source_goal;
+    light_source_goal.goal = light_object;
+    light_source_goal.goal_index = lights_definitions_indices[light_source_index];
+    light_source_goal.has_goal = true;
+
+  RayObject bounced;
+    bounced.pos = point;
+    bounced.dir = normalize(light_object.center - point);
+    bounced.map_pos = ivec3(bounced.pos);
+    bounced.delta_dist = 1.0 / abs(bounced.dir);
+    bounced.step = ivec3(sign(bounced.dir));
+    bounced.side_dist = (sign(bounced.dir) * (vec3(bounced.map_pos) - bounced.pos) + (sign(bounced.dir) * 0.5) + 0.5) * bounced.delta_dist;
+    bounced.mask = lessThanEqual(bounced.side_dist.xyz, min(bounced.side_dist.yzx, bounced.side_dist.zxy));
+    bounced.color = vec4(1.0);
+    bounced.distance_traveled = 0.0;
+    bounced.current_real_position = point;
+    bounced.ended_in_hit = false;
+    bounced.object_hit = U32_MAX;
+    bounced.optical_objects_through_which_it_passed = 0;
+    bounced.optical_objects_applied = uint(0);
+    bounced.crossed_aperture = false;
+
+  if (iterateRayTowardsLightSource(bounced, light_source_goal)) {
+    Polarization polarization = light_object.polarization;
+
+    if (bounced.optical_objects_through_which_it_passed > 0) {
+      polarization = cx_2x2_mat_x_cx_pol_mul(bounced.optical_objects_found_product, polarization);
+    }
+
+    vec2 amplitude;
+
+    if (bounced.crossed_aperture) {
+      amplitude = diffracted_amplitude(light_object, get_object_at_index(bounced.aperture_index), point);
+    } else {
+      amplitude = gaussian_beam_amplitude(light_object, point);
+    }
+
+    field.Ex = cx_mul(polarization.Ex, amplitude);
+    field.Ey = cx_mul(polarization.Ey, amplitude);
+  }
+
+  for (uint object_index = uint(0); object_index < NUM_OBJECTS; object_index++) {
+    WorldObject object = get_object_at_index(object_index);
+
+    if (object.type == GRATING) {
+      vec3 hit;
+      if (grating_incidence_point(object, light_object, hit)) {
+        Polarization orders_field = grating_orders_field(light_object, object, point);
+        field.Ex = cx_add(field.Ex, orders_field.Ex);
+        field.Ey = cx_add(field.Ey, orders_field.Ey);
+      }
+
+    } else if (object.type == PRISM || object.type == BEAM_DISPLACER) {
+      for (int outgoing = 0; outgoing < 2; outgoing++) {
+        RefractedBeam beam;
+        bool goes_through;
+
+        if (object.type == PRISM) {
+          goes_through = outgoing == 0 && prism_beam(object, light_object, beam);
+        } else {
+          goes_through = displacer_beam(object, light_object, outgoing == 1, beam);
+        }
+
+        if (goes_through) {
+          Polarization refracted = refracted_field(light_object, beam, point);
+          field.Ex = cx_add(field.Ex, refracted.Ex);
+          field.Ey = cx_add(field.Ey, refracted.Ey);
+        }
+      }
+    }
+  }
+
+  return field;
+}
+
+// distance along the ray to the cross-section square, CrossSection::local_axes in cross_section.rs
+bool slice_crossing(vec3 origin, vec3 direction, out float distance) {
+  vec3 horizontal = rotate3dY(rotate3dX(vec3(1.0, 0.0, 0.0), slice_rotation.y), slice_rotation.x);
+  vec3 normal = rotate3dY(rotate3dX(vec3(0.0, 1.0, 0.0), slice_rotation.y), slice_rotation.x);
+  vec3 vertical = rotate3dY(rotate3dX(vec3(0.0, 0.0, 1.0), slice_rotation.y), slice_rotation.x);
+
+  float denominator = dot(direction, normal);
+  if (abs(denominator) < 1e-6) {
+    return false;
+  }
+
+  distance = dot(slice_center - origin, normal) / denominator;
+  vec3 offset = origin + direction * distance - slice_center;
+
+  return distance > 0.0 && abs(dot(offset, horizontal)) <= slice_size / 2.0 && abs(dot(offset, vertical)) <= slice_size / 2.0;
+}
+
+vec3 hsv_to_rgb(float hue, float value) {
+  return value * clamp(abs(fract(vec3(hue) + vec3(1.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0) - 1.0, 0.0, 1.0);
+}
+
+// the stokes parameters of the lights that share a wavelength added up like in main(),
+// section_fields and section_color in cross_section.rs
+vec3 slice_color(vec3 point) {
+  Polarization fields[NUM_OBJECTS];
+
+  for (uint i = uint(0); i < light_sources_count; i++) {
+    fields[i] = light_field_at(i, point);
+  }
+
+  vec4 stokes = vec4(0.0);
+  float phase = 0.0;
+
+  for (uint i = uint(0); i < light_sources_count; i++) {
+    float wavelength = get_object_at_index(lights_definitions_indices[i]).wavelength;
+    bool already_added = false;
+
+    for (uint j = uint(0); j < i; j++) {
+      already_added = already_added || get_object_at_index(lights_definitions_indices[j]).wavelength == wavelength;
+    }
+
+    if (already_added) {
+      continue;
+    }
+
+    vec2 Ex = vec2(0.0);
+    vec2 Ey = vec2(0.0);
+
+    for (uint j = i; j < light_sources_count; j++) {
+      if (get_object_at_index(lights_definitions_indices[j]).wavelength == wavelength) {
+        Ex = cx_add(Ex, fields[j].Ex);
+        Ey = cx_add(Ey, fields[j].Ey);
+      }
+    }
+
+    if (i == uint(0)) {
+      vec2 strongest = dot(Ex, Ex) >= dot(Ey, Ey) ? Ex : Ey;
+      phase = atan(strongest.y, strongest.x);
+    }
+
+    vec2 cross_product = cx_mul(Ex, cx_conj(Ey));
+    stokes += vec4(dot(Ex, Ex) + dot(Ey, Ey), dot(Ex, Ex) - dot(Ey, Ey), 2.0 * cross_product.x, 2.0 * cross_product.y);
+  }
+
+  float brightness = clamp(stokes.x * slice_exposure, 0.0, 1.0);
+
+  if (slice_mode == SLICE_INTENSITY) {
+    return clamp(3.0 * brightness - vec3(0.0, 1.0, 2.0), 0.0, 1.0);
+  } else if (slice_mode == SLICE_PHASE) {
+    return hsv_to_rgb((phase + PI) / (2.0 * PI), brightness);
+  } else if (stokes.x > 0.0) {
+    return brightness * (1.0 + stokes.yzw / stokes.x) / 2.0;
+  }
+
+  return vec3(0.0);
+}
+
+// colormap_color in render_mode.rs, polynomial fits of matplotlib's colormaps
+vec3 colormap_color(float t) {
+  t = clamp(t, 0.0, 1.0);
+
+  if (render_colormap == COLORMAP_INFERNO) {
+    return clamp(vec3(0.00021894, 0.001651, -0.0194809) + t * (vec3(0.10651342, 0.5639564, 3.9327124) + t * (vec3(11.602493, -3.972854, -15.942394) + t * (vec3(-41.703996, 17.4364, 44.354145) + t * (vec3(77.16294, -33.40236, -81.80731) + t * (vec3(-71.31943, 32.626064, 73.20952) + t * vec3(25.131126, -12.242669, -23.070325)))))), 0.0, 1.0);
+  } else if (render_colormap == COLORMAP_VIRIDIS) {
+    return clamp(vec3(0.27772733, 0.0054073445, 0.3340998) + t * (vec3(0.10509304, 1.4046135, 1.3845902) + t * (vec3(-0.33086183, 0.21484756, 0.09509516) + t * (vec3(-4.6342305, -5.799101, -19.332441) + t * (vec3(6.22827, 14.179933, 56.69055) + t * (vec3(4.776385, -13.745145, -65.35303) + t * vec3(-5.435456, 4.6458526, 26.312435)))))), 0.0, 1.0);
+  } else if (render_colormap == COLORMAP_JET) {
+    return clamp(1.5 - abs(4.0 * t - vec3(3.0, 2.0, 1.0)), 0.0, 1.0);
+  }
+
+  return vec3(t);
+}
+
+// diverging_color in render_mode.rs
+vec3 diverging_color(float value) {
+  value = clamp(value, -1.0, 1.0);
+  return value >= 0.0 ? vec3(1.0, 1.0 - value, 1.0 - value) : vec3(1.0 + value, 1.0 + value, 1.0);
+}
+
+// signed_color in render_mode.rs
+vec3 signed_color(float value) {
+  value = clamp(value, -1.0, 1.0);
+  return vec3(max(value, 0.0), 0.0, max(-value, 0.0));
+}
+
+// render_color in render_mode.rs, `field` is the field of the first wavelength, `stokes`
+// the stokes parameters of every wavelength added up and `instantaneous` the real field
+// of every wavelength added up at the current time
+vec3 false_color(vec4 stokes, Polarization field, vec2 instantaneous) {
+  float brightness = clamp(stokes.x * render_exposure, 0.0, 1.0);
+
+  if (render_mode == RENDER_FIELD_EX) {
+    return signed_color(instantaneous.x * sqrt(render_exposure));
+  } else if (render_mode == RENDER_FIELD_EY) {
+    return signed_color(instantaneous.y * sqrt(render_exposure));
+  } else if (render_mode == RENDER_INTENSITY) {
+    return colormap_color(brightness);
+  } else if (stokes.x <= 0.0) {
+    return vec3(0.0);
+  } else if (render_mode == RENDER_PHASE_EX || render_mode == RENDER_PHASE_EY) {
+    vec2 component = render_mode == RENDER_PHASE_EX ? field.Ex : field.Ey;
+    return hsv_to_rgb((atan(component.y, component.x) + PI) / (2.0 * PI), clamp(dot(component, component) * render_exposure, 0.0, 1.0));
+  } else if (render_mode == RENDER_S1) {
+    return brightness * diverging_color(stokes.y / stokes.x);
+  } else if (render_mode == RENDER_S2) {
+    return brightness * diverging_color(stokes.z / stokes.x);
+  } else if (render_mode == RENDER_S3) {
+    return brightness * diverging_color(stokes.w / stokes.x);
+  }
+
+  float polarized = length(stokes.yzw);
+
+  if (render_mode == RENDER_DEGREE_OF_POLARIZATION) {
+    return brightness * colormap_color(polarized / stokes.x);
+  }
+
+  // azimuth as the hue and ellipticity as the saturation, ellipse_color in render_mode.rs
+  float azimuth = 0.5 * atan(stokes.z, stokes.y);
+  float ellipticity = polarized > 0.0 ? 0.5 * asin(clamp(stokes.w / polarized, -1.0, 1.0)) : 0.0;
+  float saturation = 1.0 - clamp(abs(ellipticity) / (PI / 4.0), 0.0, 1.0);
+
+  return brightness * mix(vec3(1.0), hsv_to_rgb(fract(azimuth / PI), 1.0), saturation);
+}
+
 void main() {
   vec2 screen_pos = ((gl_FragCoord.xy / viewport_dimensions) * 2.) - 1.;
 
@@ -713,6 +2197,8 @@ void main() {
     ray.ended_in_hit = false;
     ray.object_hit = U32_MAX;
     ray.optical_objects_through_which_it_passed = 0;
+    ray.optical_objects_applied = uint(0);
+    ray.crossed_aperture = false;
 
   ObjectGoal empty_goal;
     empty_goal.has_goal = false;
@@ -735,6 +2221,9 @@ void main() {
     for (uint light_source_index = uint(0); light_source_index < light_sources_count; light_source_index++) {
       WorldObject light_object = get_object_at_index(lights_definitions_indices[light_source_index]);
 
+      lights_polarizations[light_source_index].Ex = vec2(0.0);
+      lights_polarizations[light_source_index].Ey = vec2(0.0);
+
       ObjectGoal light_source_goal;
         light_source_goal.goal = light_object;
         light_source_goal.goal_index = lights_definitions_indices[light_source_index];
@@ -745,7 +2234,7 @@ void main() {
       // before we try reaching the light, we should check if we can
       // hit it without crossing the object we already hit
       // we won't be doing this for optical objects
-      if (object_hit.type == ROUND_WALL) {
+      if (object_hit.type == ROUND_WALL || object_hit.type == DETECTOR || object_hit.type == APERTURE) {
         vec3 wall_normal = rotate3dY(rotate3dX(vec3(0.0, 1.0, 0.0), object_hit.rotation.y), object_hit.rotation.x);
         float past_plane_product_light = dot(wall_normal, light_object.center - object_hit.center);
         float past_plane_product_ray = dot(wall_normal, ray.pos - object_hit.center);
@@ -777,16 +2266,11 @@ void main() {
           bounced.side_dist = (sign(bounced.dir) * (vec3(bounced.map_pos) - bounced.pos) + (sign(bounced.dir) * 0.5) + 0.5) * bounced.delta_dist;
           bounced.mask = lessThanEqual(bounced.side_dist.xyz, min(bounced.side_dist.yzx, bounced.side_dist.zxy));
           bounced.ended_in_hit = false;
+          bounced.crossed_aperture = false;
+          bounced.optical_objects_through_which_it_passed = 0;
+          bounced.optical_objects_applied = uint(0);
 
         if (iterateRayTowardsLightSource(bounced, light_source_goal)) {
-          vec3 light_dir = vec3(0.0, 0.0, -1.0);
-          light_dir = rotate3dX(light_dir, light_object.rotation.y);
-          light_dir = rotate3dY(light_dir, light_object.rotation.x);
-          light_dir = normalize(light_dir);
-
-          // virtual distance
-          float radius = computeDistance(light_object.center, light_object.center + light_dir, bounced.pos) * cube_scaling_factor;
-          float z = length(light_object.center - ray.current_real_position) * cube_scaling_factor;
           float n = 1.0;
 
           Polarization polarization = light_object.polarization;
@@ -796,34 +2280,19 @@ void main() {
           }
 
           if (true) {
-            // Gaussian beam definition
-            // TODO: this should also be part of some light definition
-            float wavelength = light_object.wavelength;
-            float w0 = 5.0;
-            float z_r = (PI * w0 * w0 * n) / wavelength;
-            float w_z = w0 * sqrt(1.0 + pow(z / z_r, 2.0));
-            float R_z = z * (1.0 + pow(z_r / z, 2.0));
-            float gouy_z = atan(z / z_r);
-            float k = (2.0 * PI * n) / wavelength;
-
-            // Electric field definition
-            // I just break it down into two parts for readability
-            vec2 first_part_x_hat = cx_mul(polarization.Ex, vec2((w0 / w_z) * exp(-pow(radius, 2.0) / pow(w_z, 2.0)), 0));
-            vec2 first_part_y_hat = cx_mul(polarization.Ey, vec2((w0 / w_z) * exp(-pow(radius, 2.0) / pow(w_z, 2.0)), 0));
-            vec2 second_part = cx_exp(vec2(0.0, k * z + k * (pow(radius, 2.0) / (2.0 * R_z)) - gouy_z));
-
-            if (bounced.optical_objects_through_which_it_passed > 0) {
-              polarization = cx_2x2_mat_x_cx_pol_mul(bounced.optical_objects_found_product, polarization);
-            }
+            // Electric field definition, light that went through an aperture
+            // is replaced by the field diffracted by its openings
+            vec2 amplitude;
 
-            if (dot(light_dir, ray.current_real_position - light_object.center) > 0.0) {
-              polarization.Ex = cx_mul(first_part_x_hat, second_part) * 2.0;
-              polarization.Ey = cx_mul(first_part_y_hat, second_part) * 2.0;
+            if (bounced.crossed_aperture) {
+              amplitude = diffracted_amplitude(light_object, get_object_at_index(bounced.aperture_index), ray.current_real_position);
             } else {
-              polarization.Ex = vec2(0.0);
-              polarization.Ey = vec2(0.0);
+              amplitude = gaussian_beam_amplitude(light_object, ray.current_real_position);
             }
 
+            polarization.Ex = cx_mul(polarization.Ex, amplitude);
+            polarization.Ey = cx_mul(polarization.Ey, amplitude);
+
           } else if (false) {
             // spherical light source
             float A = 1.0;
@@ -843,30 +2312,150 @@ void main() {
           ray.color.xyz += bounced.color.xyz * current_light_intensity;
         }
       }
+
+      // light that reaches us through the orders of a grating, these don't go in a
+      // straight line from the light so they are added regardless of the ray above
+      for (uint grating_index = uint(0); grating_index < NUM_OBJECTS; grating_index++) {
+        WorldObject grating = get_object_at_index(grating_index);
+
+        if (grating.type != GRATING || grating_index == ray.object_hit) {
+          continue;
+        }
+
+        vec3 hit;
+        if (!grating_incidence_point(grating, light_object, hit)) {
+          continue;
+        }
+
+        // same check as above but with the light coming from the grating
+        if (object_hit.type == ROUND_WALL || object_hit.type == DETECTOR || object_hit.type == APERTURE) {
+          vec3 wall_normal = object_normal(object_hit);
+
+          if (sign(dot(wall_normal, hit - object_hit.center)) != sign(dot(wall_normal, ray.pos - object_hit.center))) {
+            continue;
+          }
+        }
+
+        Polarization orders_field = grating_orders_field(light_object, grating, ray.current_real_position);
+
+        lights_polarizations[light_source_index].Ex = cx_add(lights_polarizations[light_source_index].Ex, orders_field.Ex);
+        lights_polarizations[light_source_index].Ey = cx_add(lights_polarizations[light_source_index].Ey, orders_field.Ey);
+
+        float virtual_distance_traveled = (length(hit - light_object.center) + length(ray.current_real_position - hit)) * cube_scaling_factor;
+        float orders_intensity = pow(cx_abs(cx_add(orders_field.Ex, orders_field.Ey)), 2.0) / 2.0;
+        ray.color.xyz += ray.color.xyz * light_object.color * 10.0 / (virtual_distance_traveled * virtual_distance_traveled) * orders_intensity;
+      }
+
+      // light refracted by prisms and split by beam displacers, same idea as the gratings
+      for (uint refracting_index = uint(0); refracting_index < NUM_OBJECTS; refracting_index++) {
+        WorldObject refracting_object = get_object_at_index(refracting_index);
+
+        if ((refracting_object.type != PRISM && refracting_object.type != BEAM_DISPLACER) || refracting_index == ray.object_hit) {
+          continue;
+        }
+
+        // prisms have one outgoing beam, beam displacers the ordinary and the extraordinary one
+        for (int outgoing = 0; outgoing < 2; outgoing++) {
+          RefractedBeam beam;
+          bool goes_through;
+
+          if (refracting_object.type == PRISM) {
+            goes_through = outgoing == 0 && prism_beam(refracting_object, light_object, beam);
+          } else {
+            goes_through = displacer_beam(refracting_object, light_object, outgoing == 1, beam);
+          }
+
+          if (!goes_through) {
+            continue;
+          }
+
+          if (object_hit.type == ROUND_WALL || object_hit.type == DETECTOR || object_hit.type == APERTURE) {
+            vec3 wall_normal = object_normal(object_hit);
+
+            if (sign(dot(wall_normal, beam.exit - object_hit.center)) != sign(dot(wall_normal, ray.pos - object_hit.center))) {
+              continue;
+            }
+          }
+
+          Polarization field = refracted_field(light_object, beam, ray.current_real_position);
+
+          lights_polarizations[light_source_index].Ex = cx_add(lights_polarizations[light_source_index].Ex, field.Ex);
+          lights_polarizations[light_source_index].Ey = cx_add(lights_polarizations[light_source_index].Ey, field.Ey);
+
+          float virtual_distance_traveled = (length(beam.entry - light_object.center) + length(beam.exit - beam.entry) + length(ray.current_real_position - beam.exit)) * cube_scaling_factor;
+          float refracted_intensity = pow(cx_abs(cx_add(field.Ex, field.Ey)), 2.0) / 2.0;
+          ray.color.xyz += ray.color.xyz * light_object.color * 10.0 / (virtual_distance_traveled * virtual_distance_traveled) * refracted_intensity;
+        }
+      }
     }
 
     if (light_sources_count > uint(0)) {
-      Polarization final_electric_field;
-        final_electric_field.Ex = vec2(0, 0);
-        final_electric_field.Ey = vec2(0, 0);
-
-      // add up all electric fields
+      float result = 0.0;
+
+      // for the false color views
+      vec4 stokes = vec4(0.0);
+      Polarization first_field;
+        first_field.Ex = vec2(0.0);
+        first_field.Ey = vec2(0.0);
+      vec2 instantaneous = vec2(0.0);
+      float reference_wavelength = get_object_at_index(lights_definitions_indices[0]).wavelength;
+
+      // add up the electric fields of the lights that share a wavelength, lights
+      // with different wavelengths don't interfere so their intensities add up instead
       for (uint i = uint(0); i < light_sources_count; i++) {
-        final_electric_field.Ex = cx_add(lights_polarizations[i].Ex, final_electric_field.Ex);
-        final_electric_field.Ey = cx_add(lights_polarizations[i].Ey, final_electric_field.Ey);
+        float wavelength = get_object_at_index(lights_definitions_indices[i]).wavelength;
+        bool already_added = false;
+
+        for (uint j = uint(0); j < i; j++) {
+          already_added = already_added || get_object_at_index(lights_definitions_indices[j]).wavelength == wavelength;
+        }
+
+        if (already_added) {
+          continue;
+        }
+
+        Polarization final_electric_field;
+          final_electric_field.Ex = vec2(0, 0);
+          final_electric_field.Ey = vec2(0, 0);
+
+        for (uint j = i; j < light_sources_count; j++) {
+          if (get_object_at_index(lights_definitions_indices[j]).wavelength == wavelength) {
+            final_electric_field.Ex = cx_add(lights_polarizations[j].Ex, final_electric_field.Ex);
+            final_electric_field.Ey = cx_add(lights_polarizations[j].Ey, final_electric_field.Ey);
+          }
+        }
+
+        vec2 Ex = final_electric_field.Ex;
+        vec2 Ey = final_electric_field.Ey;
+        result += cx_add(cx_mul(Ex, cx_conj(Ex)), cx_mul(Ey, cx_conj(Ey))).x;
+
+        if (i == uint(0)) {
+          first_field = final_electric_field;
+        }
+
+        vec2 cross_product = cx_mul(Ex, cx_conj(Ey));
+        stokes += vec4(dot(Ex, Ex) + dot(Ey, Ey), dot(Ex, Ex) - dot(Ey, Ey), 2.0 * cross_product.x, 2.0 * cross_product.y);
+
+        // Re(E e^(-iωt)), summed_instantaneous_field in render_mode.rs
+        vec2 rotation = cx_exp(vec2(0.0, -2.0 * PI * field_time * reference_wavelength / wavelength));
+        instantaneous += vec2(cx_mul(Ex, rotation).x, cx_mul(Ey, rotation).x);
       }
 
-      // color *= pow(cx_abs(final_electric_field.Ex), 2) + pow(cx_abs(final_electric_field.Ey), 2) + 0.2;
-      vec2 Ex = final_electric_field.Ex;
-      vec2 Ey = final_electric_field.Ey;
-      // float result = pow(cx_abs(cx_add(Ex, Ey)), 2.0);
-      float result = cx_add(cx_mul(Ex, cx_conj(Ex)), cx_mul(Ey, cx_conj(Ey))).x;
       result = max(background_light_min, result);
 
       ray.color *= result;
+
+      if (render_mode != RENDER_REALISTIC) {
+        ray.color = vec4(false_color(stokes, first_field, instantaneous), 1.0);
+      }
     }
   }
 
+  // the cross-section plane on top of whatever is behind it
+  float slice_distance;
+  if (slice_mode != SLICE_OFF && light_sources_count > uint(0) && slice_crossing(position, ray_dir, slice_distance) && slice_distance < length(ray.current_real_position - position)) {
+    ray.color.xyz = mix(ray.color.xyz, slice_color(position + ray_dir * slice_distance), slice_opacity);
+  }
+
   out_color = ray.color;
 }
-
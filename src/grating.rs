@@ -0,0 +1,146 @@
+// Diffraction gratings, every order leaves as a new gaussian beam along the grating equation
+use std::f32::consts::PI;
+
+use nalgebra::{Complex, Matrix2, Vector3};
+
+use crate::{diffraction::sinc, field::{light_direction, light_transverse_axes}, world::{GratingType, WorldObject}};
+
+// orders from -MAX_GRATING_ORDER to MAX_GRATING_ORDER are simulated
+pub const MAX_GRATING_ORDER: i32 = 3;
+// index of refraction of the grooves of transmission gratings
+pub const GRATING_REFRACTIVE_INDEX: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct GratingOrder {
+    pub order: i32,
+    pub direction: Vector3<f32>,
+    // angle w.r.t the normal of the grating measured towards the grating
+    // vector (perpendicular to the grooves), in radians
+    pub angle: f32,
+    pub efficiency: f32,
+    // amplitude transmitted for light polarized along the grooves (TE)
+    // and perpendicular to them (TM)
+    pub te_amplitude: f32,
+    pub tm_amplitude: f32,
+    // jones matrix of the order in the basis of the light source
+    pub jones_matrix: Matrix2<Complex<f32>>
+}
+
+// point where the axis of the light hits the grating, if it does
+pub fn grating_incidence_point(grating: &WorldObject, light: &WorldObject) -> Option<Vector3<f32>> {
+    let [_, normal, _] = grating.local_axes();
+    let light_dir = light_direction(light);
+    let grating_center = Vector3::from(grating.center);
+    let light_center = Vector3::from(light.center);
+
+    let cos_incidence = light_dir.dot(&normal);
+    if cos_incidence.abs() < 1e-4 {
+        return None;
+    }
+
+    let distance = (grating_center - light_center).dot(&normal) / cos_incidence;
+    let hit = light_center + light_dir * distance;
+
+    if distance <= 0.0 || (hit - grating_center).norm() > grating.drawn_radius() {
+        return None;
+    }
+
+    Some(hit)
+}
+
+// direction of the order `order`, grating equation in vector form: the component
+// of the wave vector along the grating vector gets m * λ / d added to it, the
+// normal component is whatever is left, None if the order is evanescent, the
+// wavelength is the one inside of the medium
+pub fn order_direction(grating: &WorldObject, incident: Vector3<f32>, order: i32, wavelength: f32) -> Option<Vector3<f32>> {
+    let [grating_vector, normal, _] = grating.local_axes();
+    let period = 1.0 / grating.groove_density;
+
+    let cos_incidence = incident.dot(&normal);
+    let tangential = incident - normal * cos_incidence + grating_vector * (order as f32 * wavelength / period);
+
+    if tangential.norm_squared() >= 1.0 {
+        return None;
+    }
+
+    let normal_component = (1.0 - tangential.norm_squared()).sqrt() * cos_incidence.signum();
+
+    match grating.grating_type {
+        GratingType::Transmission => Some(tangential + normal * normal_component),
+        GratingType::Reflection => Some(tangential - normal * normal_component),
+    }
+}
+
+// scalar efficiency of the order, a blazed grating puts most of the light in the
+// order whose phase matches the phase ramp of the grooves, a blaze angle of zero
+// is taken as a ronchi ruling (grooves and lands of the same width), the optical
+// path difference depends on the medium the grating is in
+pub fn order_efficiency(grating: &WorldObject, order: i32, wavelength: f32, refractive_index: f32) -> f32 {
+    let period = 1.0 / grating.groove_density;
+
+    if grating.blaze_angle == 0.0 {
+        return 0.25 * sinc(PI * order as f32 / 2.0).powi(2);
+    }
+
+    let optical_path_difference = match grating.grating_type {
+        GratingType::Transmission => (GRATING_REFRACTIVE_INDEX - refractive_index) * period * grating.blaze_angle.tan(),
+        GratingType::Reflection => 2.0 * refractive_index * period * grating.blaze_angle.sin(),
+    };
+
+    sinc(PI * (order as f32 - optical_path_difference / wavelength)).powi(2)
+}
+
+// TE light (polarized along the grooves) keeps its amplitude, TM light gets projected
+// onto the new direction of propagation so it loses some of it at large angles
+fn order_amplitudes(grating: &WorldObject, incident: Vector3<f32>, outgoing: Vector3<f32>, efficiency: f32) -> (f32, f32) {
+    let [_, normal, _] = grating.local_axes();
+
+    // direction the light would have without the grating
+    let undiffracted = match grating.grating_type {
+        GratingType::Transmission => incident,
+        GratingType::Reflection => incident - normal * 2.0 * incident.dot(&normal),
+    };
+
+    let te = efficiency.sqrt();
+    let tm = te * outgoing.dot(&undiffracted).abs();
+
+    (te, tm)
+}
+
+// TE/TM amplitudes written in the basis of the jones vector of the light
+fn order_jones_matrix(grating: &WorldObject, light: &WorldObject, te: f32, tm: f32) -> Matrix2<Complex<f32>> {
+    let [_, _, grooves] = grating.local_axes();
+    let [light_x, light_y] = light_transverse_axes(light);
+
+    let angle = grooves.dot(&light_y).atan2(grooves.dot(&light_x));
+    let (sin, cos) = angle.sin_cos();
+
+    let rotation = Matrix2::new(cos, -sin, sin, cos);
+    let transmission = Matrix2::new(te, 0.0, 0.0, tm);
+
+    (rotation * transmission * rotation.transpose()).map(|x| Complex::new(x, 0.0))
+}
+
+// every propagating order of the light that hits the grating, `refractive_index`
+// is the one of the medium at the wavelength of the light
+pub fn diffraction_orders(grating: &WorldObject, light: &WorldObject, refractive_index: f32) -> Vec<GratingOrder> {
+    let [grating_vector, _, _] = grating.local_axes();
+    let incident = light_direction(light);
+
+    (-MAX_GRATING_ORDER..=MAX_GRATING_ORDER).filter_map(|order| {
+        let direction = order_direction(grating, incident, order, light.wavelength / refractive_index)?;
+        let efficiency = order_efficiency(grating, order, light.wavelength, refractive_index);
+        let (te_amplitude, tm_amplitude) = order_amplitudes(grating, incident, direction, efficiency);
+
+        Some(GratingOrder {
+            order,
+            direction,
+            angle: direction.dot(&grating_vector).clamp(-1.0, 1.0).asin(),
+            efficiency,
+            te_amplitude,
+            tm_amplitude,
+            jones_matrix: order_jones_matrix(grating, light, te_amplitude, tm_amplitude)
+        })
+    }).collect()
+}
+
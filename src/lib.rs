@@ -8,5 +8,25 @@ mod menus;
 mod tests;
 mod util;
 mod demos;
+mod field;
+mod diffraction;
+mod grating;
+mod materials;
+mod prism;
+mod displacer;
+mod coatings;
+mod etalon;
+mod spatial;
+mod electro_optics;
+mod detector;
+mod polarimetry;
+mod poincare;
+mod sweep;
+mod optimizer;
+mod fringes;
+mod profile;
+mod cross_section;
+mod beam_path;
+mod render_mode;
 
 pub use app::MainApp;
@@ -0,0 +1,212 @@
+// Refractive index of the materials in the lab, read from assets/materials.txt or the materials window
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+// Amount of u32s each material takes when sent to the gpu,
+// must match MATERIAL_SIZE in main.frag
+pub const GPU_MATERIAL_SIZE: usize = 10;
+// WorldObject has to be Copy, so names are stored in a fixed buffer
+pub const MAX_MATERIAL_NAME_LENGTH: usize = 32;
+
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum DispersionModel {
+    Constant = 0,
+    Cauchy = 1,
+    Sellmeier = 2
+}
+
+impl Display for DispersionModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Constant => write!(f, "constant"),
+            Self::Cauchy => write!(f, "cauchy"),
+            Self::Sellmeier => write!(f, "sellmeier"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub model: DispersionModel,
+    // unused coefficients are 0, see assets/materials.txt for their meaning
+    pub coefficients: [f32; 7],
+    // in micrometers
+    pub wavelength_range: [f32; 2]
+}
+
+// the name of a material as stored by objects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaterialName {
+    bytes: [u8; MAX_MATERIAL_NAME_LENGTH],
+    length: usize
+}
+
+#[derive(Debug, Clone)]
+pub struct MaterialLibrary {
+    pub materials: Vec<Material>
+}
+
+impl MaterialName {
+    // names longer than MAX_MATERIAL_NAME_LENGTH bytes get cut
+    pub fn new(name: &str) -> MaterialName {
+        let mut length = name.len().min(MAX_MATERIAL_NAME_LENGTH);
+        while !name.is_char_boundary(length) {
+            length -= 1;
+        }
+
+        let mut bytes = [0; MAX_MATERIAL_NAME_LENGTH];
+        bytes[..length].copy_from_slice(&name.as_bytes()[..length]);
+
+        MaterialName { bytes, length }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // always built from a valid &str cut at a char boundary
+        std::str::from_utf8(&self.bytes[..self.length]).unwrap_or("")
+    }
+}
+
+impl Display for MaterialName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Material {
+    // λ in micrometers
+    pub fn refractive_index(&self, wavelength: f32) -> f32 {
+        let wavelength = wavelength.clamp(self.wavelength_range[0], self.wavelength_range[1]);
+        let [a, b1, c1, b2, c2, b3, c3] = self.coefficients;
+
+        match self.model {
+            DispersionModel::Constant => a,
+
+            DispersionModel::Cauchy => a + b1 / wavelength.powi(2) + c1 / wavelength.powi(4),
+
+            DispersionModel::Sellmeier => {
+                let l2 = wavelength.powi(2);
+                let n2 = a + b1 * l2 / (l2 - c1) + b2 * l2 / (l2 - c2) + b3 * l2 / (l2 - c3);
+
+                // only happens way outside of the range the fit was made for
+                n2.max(1.0).sqrt()
+            }
+        }
+    }
+
+    pub fn gpu_definition(&self) -> [u32; GPU_MATERIAL_SIZE] {
+        let mut definition = [0; GPU_MATERIAL_SIZE];
+
+        definition[0] = self.model as u32;
+        for (i, coefficient) in self.coefficients.iter().enumerate() {
+            definition[i + 1] = coefficient.to_bits();
+        }
+        definition[8] = self.wavelength_range[0].to_bits();
+        definition[9] = self.wavelength_range[1].to_bits();
+
+        definition
+    }
+}
+
+// parses one line of assets/materials.txt
+fn parse_material(line: &str) -> Result<Material, String> {
+    let fields: Vec<&str> = line.split(';').map(|field| field.trim()).collect();
+
+    if fields.len() != 4 {
+        return Err(format!("Expected 'name; model; coefficients; min wavelength, max wavelength' but got '{}'", line));
+    }
+
+    let name = fields[0];
+    if name.is_empty() || name.len() > MAX_MATERIAL_NAME_LENGTH {
+        return Err(format!("Material names must have between 1 and {} characters", MAX_MATERIAL_NAME_LENGTH));
+    }
+
+    let model = match fields[1].to_lowercase().as_str() {
+        "constant" => DispersionModel::Constant,
+        "cauchy" => DispersionModel::Cauchy,
+        "sellmeier" => DispersionModel::Sellmeier,
+        other => return Err(format!("Unknown dispersion model '{}' for {}", other, name))
+    };
+
+    let parse_numbers = |field: &str| -> Result<Vec<f32>, String> {
+        field.split(',')
+            .map(|number| number.trim().parse::<f32>().map_err(|_| format!("'{}' is not a number ({})", number.trim(), name)))
+            .collect()
+    };
+
+    let numbers = parse_numbers(fields[2])?;
+    let expected = match model {
+        DispersionModel::Constant => 1,
+        DispersionModel::Cauchy => 3,
+        DispersionModel::Sellmeier => 7
+    };
+
+    if numbers.len() != expected {
+        return Err(format!("The {} model takes {} coefficients but {} has {}", model, expected, name, numbers.len()));
+    }
+
+    let mut coefficients = [0.0; 7];
+    coefficients[..expected].copy_from_slice(&numbers);
+
+    let range = parse_numbers(fields[3])?;
+    if range.len() != 2 || range[0] <= 0.0 || range[0] >= range[1] {
+        return Err(format!("The wavelength range of {} must be 'min, max' with 0 < min < max", name));
+    }
+
+    Ok(Material {
+        name: name.to_string(),
+        model,
+        coefficients,
+        wavelength_range: [range[0], range[1]]
+    })
+}
+
+// every material defined in `text`, empty lines and lines starting with # are skipped
+pub fn parse_materials(text: &str) -> Result<Vec<Material>, String> {
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_material)
+        .collect()
+}
+
+impl MaterialLibrary {
+    pub fn new() -> MaterialLibrary {
+        MaterialLibrary {
+            materials: parse_materials(include_str!("../assets/materials.txt")).expect("assets/materials.txt should be valid")
+        }
+    }
+
+    pub fn get(&self, name: &MaterialName) -> Option<&Material> {
+        self.materials.iter().find(|material| material.name == name.as_str())
+    }
+
+    // materials we don't know about act like vacuum
+    pub fn refractive_index(&self, name: &MaterialName, wavelength: f32) -> f32 {
+        self.get(name).map_or(1.0, |material| material.refractive_index(wavelength))
+    }
+
+    pub fn gpu_definition(&self, name: &MaterialName) -> [u32; GPU_MATERIAL_SIZE] {
+        match self.get(name) {
+            Some(material) => material.gpu_definition(),
+            None => Material { name: String::new(), model: DispersionModel::Constant, coefficients: [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], wavelength_range: [0.1, 10.0] }.gpu_definition()
+        }
+    }
+
+    // adds every material defined in `text`, materials with a name that already
+    // exists replace the old ones, returns how many were added
+    pub fn add_materials(&mut self, text: &str) -> Result<usize, String> {
+        let new_materials = parse_materials(text)?;
+        let count = new_materials.len();
+
+        for material in new_materials {
+            match self.materials.iter_mut().find(|existing| existing.name == material.name) {
+                Some(existing) => *existing = material,
+                None => self.materials.push(material)
+            }
+        }
+
+        Ok(count)
+    }
+}
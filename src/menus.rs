@@ -1,13 +1,13 @@
-use std::f32::consts::PI;
+use std::{collections::{HashMap, HashSet}, f32::consts::PI, ops::RangeInclusive};
 
 use egui::{self, color_picker::color_picker_color32, Button, Color32, ColorImage, Label, Shape, Slider, Stroke, TextureHandle, TextureOptions, Ui, Vec2};
 use egui_extras::{Column, TableBuilder};
 use ::image::{ImageBuffer, Rgba};
-use egui_plot::{Line, Plot, PlotPoints};
-use nalgebra::{Complex, ComplexField, Vector2, Vector3};
+use egui_plot::{Arrows, Legend, Line, Plot, PlotPoints, VLine};
+use nalgebra::{Complex, Vector2, Vector3};
 use web_sys::console;
 
-use crate::{app::MainGlowProgram, camera::{rotate3d_x, rotate3d_y}, demos::{coordinated_interference_demo, double_slit_demo, light_profile, no_demo, simple_interference_demo, triple_slit_demo, uncoordinated_interference_demo, Demo}, world::{Alignment, LightPolarizationType, ObjectType, PolarizerType, World, WorldObject}};
+use crate::{app::MainGlowProgram, camera::{rotate3d_x, rotate3d_y}, coatings::{anti_reflection_coating, coating_jones_matrix, dielectric_mirror, quarter_wave_layer, soap_film, spectrum_color, stack_response, Coating, CoatingResponse, MAX_COATING_LAYERS}, detector::{sensor_side, DetectorQuantity, DetectorReadout}, poincare::{beam_trajectory, jones_vector_at, sphere_point}, polarimetry::{degree_of_polarization, ellipse_azimuth, ellipticity_angle, fields_by_wavelength, handedness, instantaneous_field, stokes_parameters, total_stokes, Handedness}, demos::{coordinated_interference_demo, diffraction_grating_demo, double_refraction_demo, double_slit_demo, light_profile, no_demo, optical_isolator_demo, photoelasticity_demo, prism_demo, simple_interference_demo, triple_slit_demo, uncoordinated_interference_demo, Demo}, diffraction::{diffracted_amplitude, expected_fringe_period, fraunhofer_intensity, MAX_SLITS}, displacer::{displaced_beams, walk_off_angle, CrystalRay}, electro_optics::{drive_voltage, electro_optic_jones_matrix, electro_optic_retardance, is_electro_optic}, sweep::{Sweep, SweepParameter}, fringes::{analyze_fringes, detector_profile, expected_fringes, ExpectedFringes, FringeAxis}, profile::{profile_csv, sample_profile}, beam_path::{beam_path, beam_radius, polarization_glyph}, render_mode::{render_color, Colormap, RenderMode, RenderSettings}, cross_section::{section_color, section_fields, CrossSection, CrossSectionMode}, optimizer::{Iteration, Objective, Optimization, OptimizationVariable}, spatial::{disk_coordinates, element_at, is_spatially_varying, local_element, principal_stresses, ElementMap, MapChannel, MAP_CHANNELS}, etalon::{airy_transmittance, closest_resonance, coefficient_of_finesse, cos_inside, etalon_transmission, finesse, free_spectral_range, round_trip_phase}, field::{jones_matrix_for_light, light_direction}, grating::{diffraction_orders, grating_incidence_point}, materials::{MaterialLibrary, MaterialName}, prism::{minimum_deviation, prism_beam}, world::{Alignment, ApertureType, GratingType, DriveWaveform, LightPolarizationType, ObjectType, PolarizerType, StressField, World, WorldObject, GPU_OBJECT_SIZE}};
 
 pub struct MenusState {
     pub selected_demo: Demo, 
@@ -16,9 +16,6 @@ pub struct MenusState {
     selected_polarizer_type: PolarizerType,
     selected_light_polarization: LightPolarizationType,
     selected_color: Color32,
-    angle: f32,
-    relative_phase_retardation: f32,
-    circularity: f32,
     object_creation_state: WorldObject,
     image_texture: TextureHandle,
     debug_texture: TextureHandle,
@@ -26,7 +23,1141 @@ pub struct MenusState {
     image_sizes: Vec<[usize; 2]>,
     pub should_display_debug_menu: bool,
     pub trying_to_align_to_object: bool,
-    should_display_debug_objects_view: bool
+    should_display_debug_objects_view: bool,
+    // distance at which the expected diffraction pattern of an aperture is plotted
+    aperture_screen_distance: f32,
+    // materials typed by the user in the materials window, same format as assets/materials.txt
+    new_materials_definition: String,
+    new_materials_message: String,
+    // wavelength (in micrometers) the coating presets are made for
+    coating_design_wavelength: f32,
+    // angle of incidence (in radians) of the spectra of the coatings
+    coating_plot_angle: f32,
+    // map the png files dropped on the window go to and what happened to the last one
+    element_map_drop_channel: MapChannel,
+    element_maps_message: String,
+    spatial_preview_texture: Option<TextureHandle>,
+    // readouts of the detectors, by the index of the detector
+    detector_windows: HashMap<usize, DetectorWindow>,
+    // [yaw, pitch] of the poincaré sphere of the light inspector
+    poincare_view: [f32; 2],
+    // state going into the element whose polarization ellipse is shown in the inspector
+    ellipse_input_polarization: LightPolarizationType,
+    sweep: Sweep,
+    // [parameter, measured quantity] of the last sweep
+    sweep_results: Vec<[f32; 2]>,
+    optimization: Optimization,
+    optimization_iterations: Vec<Iteration>,
+    // the next click on the viewport picks a point of the line profile
+    pub picking_profile_point: bool,
+    line_profile: LineProfile,
+    cross_section_texture: Option<TextureHandle>,
+    // lights whose beam path is drawn on top of the lab
+    shown_beam_paths: HashSet<usize>,
+    show_beam_envelopes: bool,
+    show_polarization_glyphs: bool
+}
+
+struct LineProfile {
+    object_index: usize,
+    // start and end once both are picked
+    points: Vec<Vector3<f32>>,
+    samples: usize,
+    show_phase: bool,
+    show_stokes: bool
+}
+
+struct DetectorWindow {
+    readout: DetectorReadout,
+    // record every frame instead of when asked to
+    live: bool,
+    // keep adding frames instead of showing only the last one
+    accumulate: bool,
+    quantity: DetectorQuantity,
+    texture: Option<TextureHandle>,
+    fringe_axis: FringeAxis
+}
+
+// a horizontal bar with the colors from left to right and a label under each end and
+// under the middle
+fn color_bar(ui: &mut Ui, colors: &[[f32; 3]], labels: [String; 3]) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(240.0, 14.0), egui::Sense::hover());
+    let width = rect.width() / colors.len() as f32;
+
+    for (i, color) in colors.iter().enumerate() {
+        let [r, g, b] = color.map(|x| (x * 255.0) as u8);
+        let strip = egui::Rect::from_min_size(rect.min + Vec2::new(i as f32 * width, 0.0), Vec2::new(width + 0.5, rect.height()));
+        ui.painter().rect_filled(strip, 0.0, Color32::from_rgb(r, g, b));
+    }
+
+    ui.allocate_ui(Vec2::new(240.0, 14.0), |ui| {
+        ui.columns(3, |columns| {
+            columns[0].label(&labels[0]);
+            columns[1].vertical_centered(|ui| ui.label(&labels[1]));
+            columns[2].with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| ui.label(&labels[2]));
+        });
+    });
+}
+
+// the colors of the current render mode, computed for the light each end stands for
+fn render_mode_legend(ui: &mut Ui, settings: &RenderSettings) {
+    const STEPS: usize = 64;
+
+    let full = 1.0 / settings.exposure;
+    let zero = Complex::new(0.0, 0.0);
+    let along = |i: usize| i as f32 / (STEPS - 1) as f32;
+
+    // fully polarized light of a point of the poincaré sphere
+    let polarized = |point: Vector3<f32>, intensity: f32| vec![(1.0, jones_vector_at(point) * Complex::new(intensity.sqrt(), 0.0))];
+    let ellipse_point = |azimuth: f32, ellipticity: f32| Vector3::new(
+        (2.0 * ellipticity).cos() * (2.0 * azimuth).cos(),
+        (2.0 * ellipticity).cos() * (2.0 * azimuth).sin(),
+        (2.0 * ellipticity).sin()
+    );
+
+    match settings.mode {
+        RenderMode::Realistic => {}
+
+        RenderMode::Intensity => {
+            let colors: Vec<[f32; 3]> = (0..STEPS).map(|i| render_color(settings, &polarized(Vector3::x(), along(i) * full))).collect();
+            color_bar(ui, &colors, ["0".to_string(), format!("{:.3}", full / 2.0), format!("{:.3}", full)]);
+        }
+
+        RenderMode::PhaseEx | RenderMode::PhaseEy => {
+            let colors: Vec<[f32; 3]> = (0..STEPS).map(|i| {
+                let component = Complex::from_polar(full.sqrt(), -PI + 2.0 * PI * along(i));
+                let field = if settings.mode == RenderMode::PhaseEx { Vector2::new(component, zero) } else { Vector2::new(zero, component) };
+
+                render_color(settings, &[(1.0, field)])
+            }).collect();
+
+            color_bar(ui, &colors, ["-π".to_string(), "0".to_string(), "π".to_string()]);
+            ui.label("Phase of the field of the first wavelength, darker where that component is weaker");
+        }
+
+        RenderMode::S1 | RenderMode::S2 | RenderMode::S3 => {
+            let component = settings.mode as usize - RenderMode::S1 as usize;
+            let colors: Vec<[f32; 3]> = (0..STEPS).map(|i| {
+                let value = 2.0 * along(i) - 1.0;
+                let mut point = Vector3::zeros();
+                point[component] = value;
+                point[(component + 1) % 3] = (1.0 - value * value).max(0.0).sqrt();
+
+                render_color(settings, &polarized(point, full))
+            }).collect();
+
+            let ends = match settings.mode {
+                RenderMode::S1 => ["Vertical", "Horizontal"],
+                RenderMode::S2 => ["Anti-diagonal", "Diagonal"],
+                _ => ["Left circular", "Right circular"]
+            };
+
+            color_bar(ui, &colors, [format!("-1 ({})", ends[0]), "0".to_string(), format!("1 ({})", ends[1])]);
+            ui.label("Normalized by the intensity, darker where there's less light");
+        }
+
+        RenderMode::DegreeOfPolarization => {
+            // horizontal and vertical light of different wavelengths add up as intensities
+            let colors: Vec<[f32; 3]> = (0..STEPS).map(|i| render_color(settings, &[
+                (1.0, Vector2::new(Complex::new((full * (1.0 + along(i)) / 2.0).sqrt(), 0.0), zero)),
+                (2.0, Vector2::new(zero, Complex::new((full * (1.0 - along(i)) / 2.0).sqrt(), 0.0)))
+            ])).collect();
+
+            color_bar(ui, &colors, ["Unpolarized".to_string(), "0.5".to_string(), "Polarized".to_string()]);
+        }
+
+        RenderMode::AzimuthEllipticity => {
+            let azimuths: Vec<[f32; 3]> = (0..STEPS).map(|i| render_color(settings, &polarized(ellipse_point(-PI / 2.0 + PI * along(i), 0.0), full))).collect();
+            ui.label("Azimuth of the ellipse");
+            color_bar(ui, &azimuths, ["-90°".to_string(), "0°".to_string(), "90°".to_string()]);
+
+            let ellipticities: Vec<[f32; 3]> = (0..STEPS).map(|i| render_color(settings, &polarized(ellipse_point(0.0, PI / 4.0 * along(i)), full))).collect();
+            ui.label("Ellipticity (the saturation for any azimuth)");
+            color_bar(ui, &ellipticities, ["Linear".to_string(), "".to_string(), "Circular".to_string()]);
+        }
+
+        RenderMode::FieldEx | RenderMode::FieldEy => {
+            let still = RenderSettings { field_time: 0.0, ..*settings };
+            let colors: Vec<[f32; 3]> = (0..STEPS).map(|i| {
+                let component = Complex::new(full.sqrt() * (2.0 * along(i) - 1.0), 0.0);
+                render_color(&still, &[(1.0, Vector2::new(component, component))])
+            }).collect();
+
+            color_bar(ui, &colors, [format!("{:.3}", -full.sqrt()), "0".to_string(), format!("{:.3}", full.sqrt())]);
+            ui.label("The real field at this moment in the basis of each light, wavefronts travel along the beams, light going both ways makes standing waves and lights of different wavelengths beat");
+        }
+    }
+}
+
+fn aperture_parameters_menu(ui: &mut Ui, aperture: &mut WorldObject) {
+    egui::ComboBox::from_label("Aperture type")
+        .selected_text(format!("{}", aperture.aperture_type))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut aperture.aperture_type, ApertureType::SingleSlit, "Single slit");
+            ui.selectable_value(&mut aperture.aperture_type, ApertureType::MultipleSlits, "Multiple slits");
+            ui.selectable_value(&mut aperture.aperture_type, ApertureType::CircularPinhole, "Circular pinhole");
+            ui.selectable_value(&mut aperture.aperture_type, ApertureType::Rectangular, "Rectangular aperture");
+        }
+    );
+
+    match aperture.aperture_type {
+        ApertureType::SingleSlit => {
+            ui.add(Slider::new(&mut aperture.aperture_width, 0.01..=0.5).text("Slit width"));
+            ui.add(Slider::new(&mut aperture.aperture_height, 0.05..=1.0).text("Slit length"));
+        }
+
+        ApertureType::MultipleSlits => {
+            ui.add(Slider::new(&mut aperture.slit_count, 1..=MAX_SLITS).text("Number of slits"));
+            ui.add(Slider::new(&mut aperture.aperture_width, 0.01..=0.5).text("Slit width"));
+            ui.add(Slider::new(&mut aperture.aperture_height, 0.05..=1.0).text("Slit length"));
+            ui.add(Slider::new(&mut aperture.slit_pitch, 0.01..=1.0).text("Slit pitch (distance between slit centers)"));
+
+            // slits can't overlap
+            aperture.slit_pitch = aperture.slit_pitch.max(aperture.aperture_width);
+        }
+
+        ApertureType::CircularPinhole => {
+            ui.add(Slider::new(&mut aperture.aperture_width, 0.01..=1.0).text("Pinhole diameter"));
+        }
+
+        ApertureType::Rectangular => {
+            ui.add(Slider::new(&mut aperture.aperture_width, 0.01..=1.0).text("Aperture width"));
+            ui.add(Slider::new(&mut aperture.aperture_height, 0.01..=1.0).text("Aperture height"));
+        }
+    }
+}
+
+// sliders for the parameters of the selected polarizer type, the jones matrix
+// of the object is recomputed with them right after
+fn polarizer_parameters_menu(ui: &mut Ui, optical_object: &mut WorldObject) {
+    match optical_object.polarizer_type {
+        PolarizerType::LinearTheta                   |
+        PolarizerType::QuarterWavePlateFastAxisTheta |
+        PolarizerType::HalfWavePlateFastAxisTheta    |
+        PolarizerType::HalfWavePlateRotatedTheta     => {
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=2.0*PI).text("θ"));
+        }
+
+        PolarizerType::GeneralWavePlateLinearRetarderTheta => {
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=PI).text("θ"));
+            ui.add(Slider::new(&mut optical_object.relative_phase_retardation, 0.0..=2.0*PI).text("Relative phase retardation (η)"));
+        }
+
+        PolarizerType::ArbitraryBirefringentMaterialTheta => {
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=PI).text("θ"));
+            ui.add(Slider::new(&mut optical_object.relative_phase_retardation, 0.0..=2.0*PI).text("Relative phase retardation (η)"));
+            ui.add(Slider::new(&mut optical_object.circularity, (-PI/2.0)..=(PI/2.0)).text("Circularity (φ)"));
+        }
+
+        PolarizerType::NeutralDensityFilter => {
+            ui.add(Slider::new(&mut optical_object.optical_density, 0.0..=6.0).text("Optical density"));
+            ui.label(format!("Transmittance: {:.2e}", 10f32.powf(-optical_object.optical_density)));
+        }
+
+        PolarizerType::AbsorbingSlab => {
+            ui.add(Slider::new(&mut optical_object.absorption_coefficient, 0.001..=10.0).logarithmic(true).text("Absorption coefficient (1/m)"));
+            ui.add(Slider::new(&mut optical_object.slab_thickness, 0.01..=10.0).logarithmic(true).text("Thickness (m)"));
+            ui.label(format!("Transmittance: {:.2e}", (-optical_object.absorption_coefficient * optical_object.slab_thickness).exp()));
+        }
+
+        PolarizerType::PartialLinearPolarizerTheta => {
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=2.0*PI).text("θ"));
+            ui.add(Slider::new(&mut optical_object.principal_transmittance, 0.01..=1.0).text("Transmittance along the transmission axis (k1)"));
+            ui.add(Slider::new(&mut optical_object.extinction_ratio, 1.0..=1e6).logarithmic(true).text("Extinction ratio (k1/k2)"));
+            ui.label(format!("Transmittance along the blocking axis (k2): {:.2e}", optical_object.principal_transmittance / optical_object.extinction_ratio));
+        }
+
+        PolarizerType::OpticalRotator => {
+            ui.add(Slider::new(&mut optical_object.specific_rotation, -100.0..=100.0).text("Specific rotation [α] (degrees / (dm g/mL))"));
+            ui.add(Slider::new(&mut optical_object.concentration, 0.0..=2.0).text("Concentration (g/mL), 1 for crystals"));
+            ui.add(Slider::new(&mut optical_object.rotator_path_length, 0.0..=10.0).text("Path length (dm)"));
+            ui.label("Sucrose has [α] = 66.5, quartz rotates about 217 degrees per dm along its optic axis");
+            ui.label(format!("Rotation: {:.2} degrees", optical_object.specific_rotation * optical_object.concentration * optical_object.rotator_path_length));
+        }
+
+        PolarizerType::FaradayRotator => {
+            ui.add(Slider::new(&mut optical_object.verdet_constant, 0.0..=200.0).text("Verdet constant (rad / (T m))"));
+            ui.add(Slider::new(&mut optical_object.magnetic_field, -5.0..=5.0).text("Magnetic field along the normal (T)"));
+            ui.add(Slider::new(&mut optical_object.faraday_length, 0.0..=0.1).text("Length (m)"));
+            ui.label(format!("Rotation: {:.2} degrees", (optical_object.verdet_constant * optical_object.magnetic_field * optical_object.faraday_length).to_degrees()));
+        }
+
+        PolarizerType::ChromaticRetarder => {
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=PI).text("Fast axis angle (θ)"));
+            ui.add(Slider::new(&mut optical_object.birefringence, 0.0..=0.2).logarithmic(true).text("Birefringence (Δn)"));
+            ui.add(Slider::new(&mut optical_object.birefringence_dispersion, 0.0..=0.01).logarithmic(true).text("Dispersion (B in Δn + B / λ², μm²)"));
+            ui.add(Slider::new(&mut optical_object.retarder_thickness, 1.0..=5000.0).logarithmic(true).text("Thickness (μm)"));
+            ui.label("Quartz has Δn ≈ 0.009, calcite Δn ≈ -0.17 (use its absolute value)");
+
+            ui.add_space(10.0);
+            ui.label("Design");
+            ui.add(Slider::new(&mut optical_object.retarder_design_wavelength, 0.38..=0.78).text("Design wavelength (μm)"));
+            ui.add(Slider::new(&mut optical_object.retarder_waves, 0.0..=1.0).text("Retardance (waves)"));
+            ui.add(Slider::new(&mut optical_object.retarder_order, 0..=50).text("Order (0 is a zero-order plate)"));
+
+            if ui.add(Button::new("Set thickness from design")).clicked() {
+                optical_object.retarder_thickness = optical_object.retarder_design_thickness();
+            }
+
+            ui.label(format!("Retardance at the design wavelength: {:.3} waves", optical_object.retardance_at(optical_object.retarder_design_wavelength) / (2.0 * PI)));
+        }
+
+        PolarizerType::QPlate => {
+            ui.add(Slider::new(&mut optical_object.topological_charge, -4.0..=4.0).step_by(0.5).text("Topological charge (q)"));
+            ui.add(Slider::new(&mut optical_object.spatial_retardance, 0.0..=2.0*PI).text("Retardance (radians)"));
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=PI).text("Fast axis at azimuth 0 (α₀)"));
+            ui.label("The fast axis is at q φ + α₀ at azimuth φ, a half-wave q-plate gives circular light a vortex of charge ±2q");
+        }
+
+        PolarizerType::VortexRetarder => {
+            ui.add(Slider::new(&mut optical_object.topological_charge, 1.0..=16.0).step_by(1.0).text("Order (m)"));
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=PI).text("Fast axis at azimuth 0 (α₀)"));
+            ui.label("Half-wave plate with its fast axis at m φ / 2 + α₀ at azimuth φ, m = 1 makes radial polarization out of horizontal");
+        }
+
+        PolarizerType::ImageElement => {
+            ui.add(Slider::new(&mut optical_object.spatial_retardance, 0.0..=2.0*PI).text("Retardance without a map (radians)"));
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=PI).text("Fast axis without a map (θ)"));
+        }
+
+        PolarizerType::PhotoelasticSample => {
+            egui::ComboBox::from_label("Stress field")
+                .selected_text(format!("{}", optical_object.stress_field))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut optical_object.stress_field, StressField::DiskCompression, "Disk under diametral compression");
+                    ui.selectable_value(&mut optical_object.stress_field, StressField::BeamBending, "Beam in pure bending");
+                }
+            );
+
+            match optical_object.stress_field {
+                StressField::DiskCompression => {
+                    ui.add(Slider::new(&mut optical_object.applied_load, 0.0..=5000.0).text("Load along the vertical axis (N)"));
+                    ui.add(Slider::new(&mut optical_object.sample_size, 5.0..=200.0).text("Diameter (mm)"));
+                }
+
+                StressField::BeamBending => {
+                    ui.add(Slider::new(&mut optical_object.applied_load, -50.0..=50.0).text("Bending moment (N m)"));
+                    ui.add(Slider::new(&mut optical_object.sample_size, 5.0..=200.0).text("Height of the beam (mm)"));
+                }
+            }
+
+            ui.add(Slider::new(&mut optical_object.sample_thickness, 0.5..=20.0).text("Thickness (mm)"));
+            ui.add(Slider::new(&mut optical_object.stress_optic_coefficient, 1.0..=200.0).logarithmic(true).text("Stress-optic coefficient (brewsters, 10⁻¹² / Pa)"));
+            ui.label("Polycarbonate has C ≈ 70 brewsters, epoxy ≈ 50, glass ≈ 3");
+
+            let (stress_difference, _) = principal_stresses(optical_object, 0.0, 0.0);
+            let fringe_order = optical_object.stress_optic_coefficient * 1e-12 * stress_difference * optical_object.sample_thickness * 1000.0 / 0.55;
+            ui.label(format!("At the center σ1 - σ2 = {:.3} MPa, fringe order {:.2} at 0.55 μm", stress_difference / 1e6, fringe_order));
+        }
+
+        PolarizerType::LiquidCrystalCell => {
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=PI).text("Rubbing direction (θ)"));
+            ui.add(Slider::new(&mut optical_object.lc_max_retardance, 0.0..=4.0*PI).text("Retardance without voltage (radians)"));
+            ui.add(Slider::new(&mut optical_object.lc_threshold_voltage, 0.1..=5.0).text("Threshold voltage (V)"));
+            ui.add(Slider::new(&mut optical_object.lc_voltage_scale, 0.1..=5.0).text("Voltage scale of the tilt (V)"));
+            ui.label("The molecules start tilting towards the field after the threshold, a nematic cell only feels the magnitude of the voltage");
+
+            drive_menu(ui, optical_object, 0.0..=20.0, 0.1..=10.0);
+        }
+
+        PolarizerType::PockelsCell => {
+            ui.add(Slider::new(&mut optical_object.polarizer_angle, 0.0..=PI).text("Fast axis angle (θ)"));
+            ui.add(Slider::new(&mut optical_object.half_wave_voltage, 1.0..=10000.0).logarithmic(true).text("Half-wave voltage (V)"));
+            ui.label("Transverse lithium niobate modulators need a few hundred volts, longitudinal KD*P cells a few thousand");
+
+            let half_wave_voltage = optical_object.half_wave_voltage;
+            drive_menu(ui, optical_object, -2.0*half_wave_voltage..=2.0*half_wave_voltage, 0.1..=10.0);
+        }
+
+        PolarizerType::FabryPerotEtalon => {
+            ui.add(Slider::new(&mut optical_object.etalon_reflectance, 0.01..=0.98).text("Reflectance of the mirrors (R)"));
+            ui.add(Slider::new(&mut optical_object.etalon_gap, 0.5..=1000.0).logarithmic(true).text("Gap (μm)"));
+            ui.add(Slider::new(&mut optical_object.etalon_index, 1.0..=2.5).text("Refractive index of the gap"));
+
+            ui.label(format!("Finesse: {:.2}, coefficient of finesse: {:.2}", finesse(optical_object.etalon_reflectance), coefficient_of_finesse(optical_object.etalon_reflectance)));
+            ui.label(format!("Free spectral range at 0.55 μm and normal incidence: {:.3} nm", free_spectral_range(optical_object, 0.55, 1.0) * 1000.0));
+        }
+
+        _ => {}
+    }
+
+    optical_object.set_jones_matrix(optical_object.polarizer_type, optical_object.polarizer_angle, optical_object.relative_phase_retardation, optical_object.circularity);
+}
+
+fn material_menu(ui: &mut Ui, label: &str, material: &mut MaterialName, materials: &MaterialLibrary) {
+    egui::ComboBox::from_label(label)
+        .selected_text(format!("{}", material))
+        .show_ui(ui, |ui| {
+            for available_material in &materials.materials {
+                ui.selectable_value(material, MaterialName::new(&available_material.name), available_material.name.as_str());
+            }
+        }
+    );
+
+    if materials.get(material).is_none() {
+        ui.label(format!("There's no material called '{}', it will act like vacuum", material));
+    }
+}
+
+// layers of the coating of a surface plus a few common designs, soap films need
+// air on both sides so that preset also changes the material of the object
+fn coating_menu(ui: &mut Ui, object: &mut WorldObject, materials: &MaterialLibrary, design_wavelength: &mut f32) {
+    ui.label("Coating (from the outside to the surface)");
+
+    let mut removed_layer = None;
+
+    for (index, layer) in object.coating.layers_mut().iter_mut().enumerate() {
+        ui.push_id(index, |ui| {
+            ui.horizontal(|ui| {
+                material_menu(ui, &format!("Layer {}", index + 1), &mut layer.material, materials);
+
+                if ui.button("Remove").clicked() {
+                    removed_layer = Some(index);
+                }
+            });
+
+            ui.add(Slider::new(&mut layer.thickness, 0.001..=2.0).logarithmic(true).text("Thickness (μm)"));
+        });
+    }
+
+    if let Some(index) = removed_layer {
+        object.coating.remove(index);
+    }
+
+    if ui.add_enabled(object.coating.layers().len() < MAX_COATING_LAYERS, Button::new("Add layer")).clicked() {
+        object.coating.push(quarter_wave_layer("MgF2", materials, *design_wavelength));
+    }
+
+    ui.add(Slider::new(design_wavelength, 0.38..=0.78).text("Design wavelength (μm)"));
+
+    ui.horizontal(|ui| {
+        if ui.button("Anti-reflection").clicked() {
+            object.coating = anti_reflection_coating(materials, *design_wavelength);
+        }
+
+        if ui.button("Dielectric mirror").clicked() {
+            object.coating = dielectric_mirror(materials, *design_wavelength);
+        }
+
+        if ui.button("Soap film").clicked() {
+            object.coating = soap_film(0.3);
+            object.material = MaterialName::new("Air");
+        }
+
+        if ui.button("Clear").clicked() {
+            object.coating = Coating::new();
+        }
+    });
+}
+
+// reflectance and transmittance of the coating over the visible spectrum and over
+// the angle of incidence, the light comes from the medium and ends in the object
+fn coating_plots(ui: &mut Ui, object: &WorldObject, world: &World, angle: &mut f32, design_wavelength: f32) {
+    let response = |wavelength: f32, angle: f32| stack_response(
+        &object.coating.indices(&world.materials, wavelength),
+        world.medium_refractive_index(wavelength),
+        world.materials.refractive_index(&object.material, wavelength),
+        angle.cos(),
+        wavelength
+    );
+
+    ui.add(Slider::new(angle, 0.0..=(PI / 2.0 - 0.01)).text("Angle of incidence (radians)"));
+
+    let spectrum: Vec<(f64, CoatingResponse)> = (0..=400).map(|i| {
+        let wavelength = 0.38 + 0.4 * (i as f32 / 400.0);
+        (wavelength as f64, response(wavelength, *angle))
+    }).collect();
+
+    let curve = |name: &str, value: fn(&CoatingResponse) -> f32| {
+        Line::new(spectrum.iter().map(|(wavelength, response)| [*wavelength, value(response) as f64]).collect::<PlotPoints>()).name(name)
+    };
+
+    let lines = [
+        curve("Rs", |response| response.reflectance[0]),
+        curve("Rp", |response| response.reflectance[1]),
+        curve("Ts", |response| response.transmittance[0]),
+        curve("Tp", |response| response.transmittance[1])
+    ];
+
+    ui.label("Spectrum (wavelength in μm)");
+    Plot::new("coating_spectrum_plot")
+        .view_aspect(2.0)
+        .include_y(0.0)
+        .include_y(1.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            for line in lines {
+                plot_ui.line(line);
+            }
+        });
+
+    let angles: Vec<(f64, CoatingResponse)> = (0..=200).map(|i| {
+        let angle = (PI / 2.0 - 0.01) * (i as f32 / 200.0);
+        (angle.to_degrees() as f64, response(design_wavelength, angle))
+    }).collect();
+
+    let angle_curve = |name: &str, value: fn(&CoatingResponse) -> f32| {
+        Line::new(angles.iter().map(|(angle, response)| [*angle, value(response) as f64]).collect::<PlotPoints>()).name(name)
+    };
+
+    let angle_lines = [
+        angle_curve("Rs", |response| response.reflectance[0]),
+        angle_curve("Rp", |response| response.reflectance[1])
+    ];
+
+    ui.label(format!("Reflectance against the angle of incidence (degrees) at {} μm", design_wavelength));
+    Plot::new("coating_angle_plot")
+        .view_aspect(2.0)
+        .include_y(0.0)
+        .include_y(1.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            for line in angle_lines {
+                plot_ui.line(line);
+            }
+        });
+
+    // what white light looks like after the coating, thin-film interference colors
+    let unpolarized = |reflected: bool| spectrum_color(|wavelength| {
+        let response = response(wavelength, *angle);
+        let values = if reflected { response.reflectance } else { response.transmittance };
+        (values[0] + values[1]) / 2.0
+    });
+
+    ui.horizontal(|ui| {
+        for (name, color) in [("Reflected", unpolarized(true)), ("Transmitted", unpolarized(false))] {
+            let [r, g, b] = color.map(|channel| (channel * 255.0) as u8);
+            ui.label(name);
+            let (rect, _) = ui.allocate_exact_size(Vec2::new(30.0, 15.0), egui::Sense::hover());
+            ui.painter().rect_filled(rect, 2.0, Color32::from_rgb(r, g, b));
+        }
+    });
+}
+
+// maps of an image element, png files dropped on the window go to the selected map,
+// a few patterns can be made without any file
+fn element_maps_menu(ui: &mut Ui, world: &mut World, object_index: usize, drop_channel: &mut MapChannel, message: &mut String) {
+    let map = world.element_maps.entry(object_index).or_insert_with(ElementMap::new);
+
+    for channel in MAP_CHANNELS {
+        ui.horizontal(|ui| {
+            match &map.sources[channel as usize] {
+                Some(source) => {
+                    ui.label(format!("{} map: {}", channel, source));
+
+                    if ui.button(format!("Clear {}", channel.to_string().to_lowercase())).clicked() {
+                        map.clear_channel(channel);
+                    }
+                }
+                None => { ui.label(format!("{}: uniform", channel)); }
+            }
+        });
+    }
+
+    egui::ComboBox::from_label("Dropped png files go to")
+        .selected_text(format!("{}", drop_channel))
+        .show_ui(ui, |ui| {
+            for channel in MAP_CHANNELS {
+                ui.selectable_value(drop_channel, channel, channel.to_string());
+            }
+        }
+    );
+
+    let dropped_files = ui.ctx().input(|input| input.raw.dropped_files.clone());
+    for file in dropped_files {
+        // the web gives the contents of the file, native gives its path
+        let bytes = file.bytes.map(|bytes| bytes.to_vec()).or_else(|| file.path.as_ref().and_then(|path| std::fs::read(path).ok()));
+        let name = file.path.as_ref().map_or(file.name.clone(), |path| path.display().to_string());
+
+        *message = match bytes {
+            Some(bytes) => match map.set_channel_from_image(*drop_channel, &name, &bytes) {
+                Ok(()) => format!("Loaded '{}' as the {} map", name, drop_channel.to_string().to_lowercase()),
+                Err(e) => e
+            },
+            None => format!("Couldn't read '{}'", name)
+        };
+        console::log_1(&message.clone().into());
+    }
+
+    ui.horizontal(|ui| {
+        // phase ramps and spiral phases only act on the light along the slow axis,
+        // like a liquid crystal slm
+        if ui.button("Blazed grating").clicked() {
+            map.set_channel(MapChannel::Retardance, "blazed grating", |x, _| (x * 4.0).rem_euclid(1.0));
+        }
+
+        if ui.button("Spiral phase").clicked() {
+            map.set_channel(MapChannel::Retardance, "spiral phase", |x, y| (y.atan2(x) / (2.0 * PI)).rem_euclid(1.0));
+        }
+
+        if ui.button("Circular aperture").clicked() {
+            map.set_channel(MapChannel::Amplitude, "circular aperture", |x, y| if x.hypot(y) < 0.5 { 1.0 } else { 0.0 });
+        }
+    });
+
+    ui.label("Drop a png file on the window to load it, only its brightness is used");
+    if !message.is_empty() {
+        ui.label(message.as_str());
+    }
+}
+
+// the polarization ellipse of a jones vector, the curve Re(E e^(-iωt)) of a whole cycle
+// with the field at `time` moving along it, arrows for the way it turns and the axes of
+// the ellipse, the field is normalized so only the shape matters
+fn polarization_ellipse(ui: &mut Ui, id: &str, jones_vector: Vector2<Complex<f32>>, time: f64) {
+    let amplitude = jones_vector.norm();
+    if amplitude <= 1e-6 {
+        ui.label("There's no light");
+        return;
+    }
+
+    let jones_vector = jones_vector.unscale(amplitude);
+    let stokes = stokes_parameters(jones_vector);
+    let azimuth = ellipse_azimuth(stokes);
+    let ellipticity = ellipticity_angle(stokes);
+
+    // a whole turn every two units of time
+    let angular_frequency = PI as f64;
+    let field_at = |phase: f64| -> [f64; 2] {
+        let field = instantaneous_field(jones_vector, phase as f32);
+        [field.x as f64, field.y as f64]
+    };
+
+    let ellipse: PlotPoints = (0..=200).map(|i| field_at(2.0 * std::f64::consts::PI * i as f64 / 200.0)).collect();
+
+    let tip = field_at(angular_frequency * time);
+
+    // handedness arrows, a little bit of the way from four points of the ellipse
+    let starts: Vec<[f64; 2]> = (0..4).map(|i| field_at(i as f64 * std::f64::consts::FRAC_PI_2)).collect();
+    let ends: Vec<[f64; 2]> = (0..4).map(|i| field_at(i as f64 * std::f64::consts::FRAC_PI_2 + 0.25)).collect();
+
+    // semi-axes, cos χ along the azimuth and sin χ across it
+    let (sin_a, cos_a) = (azimuth as f64).sin_cos();
+    let (major, minor) = ((ellipticity as f64).cos(), (ellipticity as f64).sin().abs());
+    let major_axis = Line::new(vec![[-major * cos_a, -major * sin_a], [major * cos_a, major * sin_a]])
+        .name(format!("Azimuth ψ = {:.1}°", azimuth.to_degrees()));
+    let minor_axis = Line::new(vec![[minor * sin_a, -minor * cos_a], [-minor * sin_a, minor * cos_a]])
+        .style(egui_plot::LineStyle::dashed_dense())
+        .name(format!("Ellipticity χ = {:.1}°", ellipticity.to_degrees()));
+
+    Plot::new(id)
+        .data_aspect(1.0)
+        .view_aspect(1.0)
+        .height(220.0)
+        .include_x(-1.1)
+        .include_x(1.1)
+        .include_y(-1.1)
+        .include_y(1.1)
+        .x_axis_label("Ex")
+        .y_axis_label("Ey")
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(ellipse).name("Re(E e^(-iωt))"));
+            plot_ui.line(major_axis);
+            if minor > 1e-3 {
+                plot_ui.line(minor_axis);
+            }
+
+            if handedness(stokes) != Handedness::None {
+                plot_ui.arrows(Arrows::new(starts, ends).tip_length(12.0).name(format!("{} handed", handedness(stokes))));
+            }
+
+            plot_ui.arrows(Arrows::new(vec![[0.0, 0.0]], vec![tip]).tip_length(10.0).name("Field at t"));
+        });
+
+    ui.label(format!(
+        "ψ = {:.2}°, χ = {:.2}°, axis ratio {:.3}, handedness: {}",
+        azimuth.to_degrees(), ellipticity.to_degrees(), ellipticity.tan().abs(), handedness(stokes)
+    ));
+
+    ui.ctx().request_repaint();
+}
+
+// fringe analysis of a profile with samples `spacing` meters apart next to what the
+// geometry says it should be
+fn fringe_report(ui: &mut Ui, id: &str, intensities: &[f32], spacing: f32, expected: Option<ExpectedFringes>) {
+    let analysis = analyze_fringes(intensities, spacing);
+
+    egui::Grid::new(format!("{}_fringes", id)).striped(true).show(ui, |ui| {
+        ui.label("");
+        ui.label("Measured");
+        ui.label("Expected");
+        ui.end_row();
+
+        ui.label("Visibility");
+        ui.label(format!("{:.3}", analysis.visibility));
+        ui.label(expected.as_ref().map_or("-".to_string(), |expected| format!("{:.3}", expected.visibility)));
+        ui.end_row();
+
+        ui.label("Spatial frequency (cycles/mm)");
+        ui.label(analysis.frequency.map_or("-".to_string(), |frequency| format!("{:.4}", frequency / 1000.0)));
+        ui.label(expected.as_ref().map_or("-".to_string(), |expected| format!("{:.4}", 1.0 / expected.period / 1000.0)));
+        ui.end_row();
+
+        ui.label("Period (mm)");
+        ui.label(analysis.period.map_or("-".to_string(), |period| format!("{:.4}", period * 1000.0)));
+        ui.label(expected.as_ref().map_or("-".to_string(), |expected| format!("{:.4}", expected.period * 1000.0)));
+        ui.end_row();
+    });
+
+    match (&expected, analysis.period) {
+        (Some(expected), Some(period)) => {
+            ui.label(format!("{}, the measured period is {:+.1}% off", expected.description, (period / expected.period - 1.0) * 100.0));
+        }
+        (Some(expected), None) => {
+            ui.label(&expected.description);
+        }
+        (None, _) => {
+            ui.label("Nothing to compare with, fringes are expected from two lights of the same wavelength or a light behind slits");
+        }
+    }
+
+    // the period of the samples can't show anything finer than two pixels
+    if expected.as_ref().is_some_and(|expected| expected.period < 2.0 * spacing) {
+        ui.colored_label(Color32::YELLOW, "The expected fringes are finer than the pixels, the measured ones are aliased");
+    }
+
+    let profile: PlotPoints = intensities.iter().enumerate().map(|(i, intensity)| [i as f64 * spacing as f64 * 1000.0, *intensity as f64]).collect();
+    Plot::new(format!("{}_profile", id))
+        .view_aspect(2.5)
+        .x_axis_label("Position (mm)")
+        .y_axis_label("Intensity")
+        .show(ui, |plot_ui| plot_ui.line(Line::new(profile)));
+
+    let spectrum: PlotPoints = analysis.spectrum.iter().map(|[frequency, magnitude]| [*frequency as f64 / 1000.0, *magnitude as f64]).collect();
+    Plot::new(format!("{}_spectrum", id))
+        .view_aspect(2.5)
+        .x_axis_label("Spatial frequency (cycles/mm)")
+        .y_axis_label("|FFT|")
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(spectrum));
+            if let Some(expected) = &expected {
+                plot_ui.vline(VLine::new(1.0 / expected.period as f64 / 1000.0).name("Expected"));
+            }
+        });
+}
+
+// the field at a probe and the polarization state it describes, computed every
+// time the inspector is drawn so it follows the elements as they move
+fn probe_readout(ui: &mut Ui, world: &World, probe_index: usize, cube_scaling_factor: f32) {
+    let probe = world.objects[probe_index];
+    let fields = fields_by_wavelength(world, Vector3::from(probe.center), cube_scaling_factor, Some(probe_index));
+
+    if fields.is_empty() {
+        ui.label("There are no lights");
+        return;
+    }
+
+    ui.label("Field of every wavelength, in the basis of its lights");
+    egui::Grid::new("probe_fields").striped(true).show(ui, |ui| {
+        ui.label("Wavelength");
+        ui.label("Ex");
+        ui.label("Ey");
+        ui.label("Intensity");
+        ui.end_row();
+
+        for (wavelength, field) in &fields {
+            ui.label(format!("{}", wavelength));
+            for component in [field[0], field[1]] {
+                ui.label(format!("{:.4} ∠ {:.2}°", component.norm(), component.arg().to_degrees()));
+            }
+            ui.label(format!("{:.4e}", field.norm_squared()));
+            ui.end_row();
+        }
+    });
+
+    let stokes = total_stokes(&fields);
+    let azimuth = ellipse_azimuth(stokes);
+    let ellipticity = ellipticity_angle(stokes);
+
+    ui.add_space(10.0);
+    ui.label(format!("Intensity: {:.4e}", stokes[0]));
+    ui.label(format!("S0 = {:.4e}, S1 = {:.4e}, S2 = {:.4e}, S3 = {:.4e}", stokes[0], stokes[1], stokes[2], stokes[3]));
+
+    if stokes[0] > 0.0 {
+        ui.label(format!("Normalized: s1 = {:.3}, s2 = {:.3}, s3 = {:.3}", stokes[1] / stokes[0], stokes[2] / stokes[0], stokes[3] / stokes[0]));
+    }
+
+    ui.label(format!("Degree of polarization: {:.3}", degree_of_polarization(stokes)));
+    ui.label(format!("Azimuth of the ellipse (ψ): {:.2} degrees", azimuth.to_degrees()));
+    ui.label(format!("Ellipticity angle (χ): {:.2} degrees, axis ratio {:.3}", ellipticity.to_degrees(), ellipticity.tan().abs()));
+    ui.label(format!("Handedness: {}", handedness(stokes)));
+}
+
+// where a point of the poincaré sphere ends up on the screen, [yaw, pitch] of the view,
+// the last coordinate is positive on the hemisphere facing the user
+fn sphere_to_screen(point: Vector3<f32>, view: [f32; 2]) -> Vector3<f32> {
+    let [yaw, pitch] = view;
+    let right = Vector3::new(-yaw.sin(), yaw.cos(), 0.0);
+    let up = Vector3::new(-yaw.cos() * pitch.sin(), -yaw.sin() * pitch.sin(), pitch.cos());
+    let towards_user = Vector3::new(yaw.cos() * pitch.cos(), yaw.sin() * pitch.cos(), pitch.sin());
+
+    Vector3::new(point.dot(&right), point.dot(&up), point.dot(&towards_user))
+}
+
+// the point of the front of the sphere under (x, y), None outside of it
+fn screen_to_sphere(x: f32, y: f32, view: [f32; 2]) -> Option<Vector3<f32>> {
+    let depth = 1.0 - x * x - y * y;
+    if depth < 0.0 {
+        return None;
+    }
+
+    let [yaw, pitch] = view;
+    let right = Vector3::new(-yaw.sin(), yaw.cos(), 0.0);
+    let up = Vector3::new(-yaw.cos() * pitch.sin(), -yaw.sin() * pitch.sin(), pitch.cos());
+    let towards_user = Vector3::new(yaw.cos() * pitch.cos(), yaw.sin() * pitch.cos(), pitch.sin());
+
+    Some(right * x + up * y + towards_user * depth.sqrt())
+}
+
+// poincaré sphere of a light source, its state, what every element along its beam does
+// to it and the axes the retarders rotate the sphere around, dragging with the primary
+// button picks a new state for the light and with the secondary one turns the sphere
+fn poincare_sphere(ui: &mut Ui, world: &mut World, light_index: usize, view: &mut [f32; 2]) {
+    let size = ui.available_width().min(300.0);
+    let (response, painter) = ui.allocate_painter(Vec2::splat(size), egui::Sense::click_and_drag());
+    let center = response.rect.center();
+    let radius = size * 0.4;
+
+    if response.dragged_by(egui::PointerButton::Secondary) {
+        view[0] -= response.drag_delta().x * 0.01;
+        view[1] = (view[1] + response.drag_delta().y * 0.01).clamp(-PI / 2.0, PI / 2.0);
+    }
+
+    if response.dragged_by(egui::PointerButton::Primary) || response.clicked() {
+        if let Some(position) = response.interact_pointer_pos() {
+            let offset = (position - center) / radius;
+
+            if let Some(point) = screen_to_sphere(offset.x, -offset.y, *view) {
+                let light = &mut world.objects[light_index];
+                light.polarization_type = LightPolarizationType::Custom;
+                light.polarization = jones_vector_at(point);
+            }
+        }
+    }
+
+    let view = *view;
+    let to_screen = |point: Vector3<f32>| {
+        let projected = sphere_to_screen(point, view);
+        (center + Vec2::new(projected.x, -projected.y) * radius, projected.z >= 0.0)
+    };
+
+    // what's behind the sphere is drawn fainter
+    let curve = |points: &[Vector3<f32>], color: Color32, width: f32| -> Vec<Shape> {
+        points.windows(2).map(|pair| {
+            let (start, front) = to_screen(pair[0]);
+            let (end, _) = to_screen(pair[1]);
+            let color = if front { color } else { color.gamma_multiply(0.3) };
+
+            Shape::line_segment([start, end], Stroke::new(width, color))
+        }).collect()
+    };
+
+    let grid_color = ui.visuals().weak_text_color();
+    let text_color = ui.visuals().text_color();
+    let mut shapes = vec![Shape::circle_stroke(center, radius, Stroke::new(1.0, grid_color))];
+
+    let circle = |axis: usize| -> Vec<Vector3<f32>> {
+        (0..=64).map(|i| {
+            let angle = 2.0 * PI * i as f32 / 64.0;
+            let mut point = Vector3::zeros();
+            point[(axis + 1) % 3] = angle.cos();
+            point[(axis + 2) % 3] = angle.sin();
+            point
+        }).collect()
+    };
+
+    for axis in 0..3 {
+        shapes.extend(curve(&circle(axis), grid_color, 1.0));
+    }
+
+    for (point, name) in [
+        (Vector3::x(), "H"), (-Vector3::x(), "V"),
+        (Vector3::y(), "D"), (-Vector3::y(), "A"),
+        (Vector3::z(), "R"), (-Vector3::z(), "L")
+    ] {
+        shapes.extend(curve(&[Vector3::zeros(), point], grid_color, 1.0));
+        let (position, front) = to_screen(point * 1.12);
+        painter.text(position, egui::Align2::CENTER_CENTER, name, egui::FontId::proportional(14.0), if front { text_color } else { grid_color });
+    }
+
+    let steps = beam_trajectory(world, light_index, 32);
+    let colors = generate_colors_list();
+
+    for (i, step) in steps.iter().enumerate() {
+        let [r, g, b, _] = colors[i % colors.len()];
+        let color = Color32::from_rgb(r, g, b);
+
+        shapes.extend(curve(&step.points, color, 2.5));
+
+        if let Some((axis, _)) = step.rotation {
+            shapes.extend(curve(&[-axis * 1.05, axis * 1.05], color, 1.0));
+            let (position, _) = to_screen(axis * 1.05);
+            shapes.push(Shape::circle_filled(position, 3.0, color));
+        }
+    }
+
+    let mut dot = |point: Option<Vector3<f32>>, color: Color32| {
+        if let Some(point) = point {
+            let (position, front) = to_screen(point);
+            shapes.push(Shape::circle_filled(position, 5.0, if front { color } else { color.gamma_multiply(0.4) }));
+            shapes.push(Shape::circle_stroke(position, 5.0, Stroke::new(1.0, text_color)));
+        }
+    };
+
+    dot(sphere_point(world.objects[light_index].polarization), Color32::WHITE);
+    dot(steps.last().and_then(|step| step.points.last().copied()), Color32::BLACK);
+
+    painter.extend(shapes);
+
+    ui.label("White is the state of the light and black what comes out of the last element, drag with the left button to pick a state and with the right one to turn the sphere");
+
+    for (i, step) in steps.iter().enumerate() {
+        let [r, g, b, _] = colors[i % colors.len()];
+        let description = match step.rotation {
+            Some((axis, angle)) => format!(
+                "Object {}: rotates {:.1} degrees around ({:.2}, {:.2}, {:.2})",
+                step.element_index, angle.to_degrees(), axis.x, axis.y, axis.z
+            ),
+            None => format!("Object {}: not a retarder", step.element_index)
+        };
+
+        ui.colored_label(Color32::from_rgb(r, g, b), description);
+    }
+}
+
+fn detector_parameters_menu(ui: &mut Ui, detector: &mut WorldObject) {
+    ui.add(Slider::new(&mut detector.detector_pixels[0], 4..=256).text("Columns"));
+    ui.add(Slider::new(&mut detector.detector_pixels[1], 4..=256).text("Rows"));
+    ui.label("The pixels cover the square inscribed in the disk of the detector");
+}
+
+// gray from the min to the max for positive quantities, blue to red for the stokes
+// parameters that can go negative, scaled by the biggest magnitude
+fn readout_image(readout: &DetectorReadout, quantity: DetectorQuantity) -> ColorImage {
+    let values = readout.values(quantity);
+    let (min, max, _) = readout.statistics(quantity);
+    let scale = min.abs().max(max.abs()).max(1e-12);
+
+    let pixels = values.iter().map(|value| match quantity {
+        DetectorQuantity::Intensity | DetectorQuantity::DegreeOfPolarization => {
+            Color32::from_gray((value / scale * 255.0).clamp(0.0, 255.0) as u8)
+        }
+
+        _ => {
+            let level = ((value / scale).abs() * 255.0).clamp(0.0, 255.0) as u8;
+            if *value >= 0.0 { Color32::from_rgb(level, 0, 0) } else { Color32::from_rgb(0, 0, level) }
+        }
+    }).collect();
+
+    ColorImage { size: readout.pixels, pixels }
+}
+
+// voltage applied to an electro-optic element, the range is the one of the offset and the amplitude
+fn drive_menu(ui: &mut Ui, element: &mut WorldObject, voltages: RangeInclusive<f32>, frequencies: RangeInclusive<f32>) {
+    ui.add_space(10.0);
+    ui.label("Drive");
+
+    egui::ComboBox::from_label("Waveform")
+        .selected_text(format!("{}", element.drive_waveform))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut element.drive_waveform, DriveWaveform::Constant, "Constant");
+            ui.selectable_value(&mut element.drive_waveform, DriveWaveform::Sine, "Sine");
+            ui.selectable_value(&mut element.drive_waveform, DriveWaveform::Square, "Square");
+        }
+    );
+
+    ui.add(Slider::new(&mut element.drive_offset, voltages.clone()).text("Offset (V)"));
+
+    if element.drive_waveform != DriveWaveform::Constant {
+        let max_amplitude = voltages.end().abs().max(voltages.start().abs());
+        ui.add(Slider::new(&mut element.drive_amplitude, 0.0..=max_amplitude).text("Amplitude (V)"));
+        ui.add(Slider::new(&mut element.drive_frequency, frequencies).logarithmic(true).text("Frequency (Hz)"));
+    }
+}
+
+// voltage and retardance right now, the retardance against the voltage and what gets
+// through a pair of crossed and of parallel polarizers over the next couple of periods
+fn electro_optic_plots(ui: &mut Ui, element: &WorldObject, time: f64) {
+    let voltage = drive_voltage(element, time);
+    let retardance = electro_optic_retardance(element, voltage);
+    ui.label(format!("Voltage: {:.2} V, retardance: {:.3} waves", voltage, retardance / (2.0 * PI)));
+
+    let max_voltage = match element.polarizer_type {
+        PolarizerType::PockelsCell => 2.0 * element.half_wave_voltage,
+        _ => element.lc_threshold_voltage + 6.0 * element.lc_voltage_scale,
+    }.max(voltage.abs());
+
+    let curve: PlotPoints = (0..=200).map(|i| {
+        let voltage = -max_voltage + 2.0 * max_voltage * i as f32 / 200.0;
+        [voltage as f64, (electro_optic_retardance(element, voltage) / (2.0 * PI)) as f64]
+    }).collect();
+
+    Plot::new("electro_optic_retardance_plot")
+        .view_aspect(2.0)
+        .include_y(0.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(curve).name("Retardance (waves) against the voltage (V)"));
+            plot_ui.vline(VLine::new(voltage).name("Voltage right now"));
+        });
+
+    // horizontal light in, vertical (crossed) or horizontal (parallel) analyzer out
+    let duration = if element.drive_waveform == DriveWaveform::Constant { 4.0 } else { 2.0 / element.drive_frequency as f64 };
+    let transmission = |row: usize| -> PlotPoints {
+        (0..=200).map(|i| {
+            let t = time + duration * i as f64 / 200.0;
+            [t, electro_optic_jones_matrix(element, t)[(row, 0)].norm_sqr() as f64]
+        }).collect()
+    };
+
+    Plot::new("electro_optic_time_plot")
+        .view_aspect(2.0)
+        .include_y(0.0)
+        .include_y(1.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(transmission(1)).name("Between crossed polarizers (horizontal in)"));
+            plot_ui.line(Line::new(transmission(0)).name("Between parallel polarizers (horizontal in)"));
+        });
+}
+
+// what the element looks like between crossed polarizers (horizontal in, vertical out),
+// in white light for chromatic ones like photoelastic samples (their isochromatics)
+fn spatial_preview(ui: &mut Ui, world: &World, object_index: usize, texture: &mut Option<TextureHandle>) {
+    let element = world.objects[object_index];
+    let map = world.element_maps.get(&object_index);
+    let size = 96;
+
+    let mut image = ColorImage::new([size, size], Color32::TRANSPARENT);
+    for row in 0..size {
+        for column in 0..size {
+            let x = 2.0 * (column as f32 + 0.5) / size as f32 - 1.0;
+            let y = 1.0 - 2.0 * (row as f32 + 0.5) / size as f32;
+
+            if x.hypot(y) <= 1.0 {
+                let local = local_element(&element, x, y, map);
+
+                image.pixels[row * size + column] = if local.polarizer_type == PolarizerType::ChromaticRetarder {
+                    let [r, g, b] = spectrum_color(|wavelength| local.chromatic_retarder_jones_matrix(wavelength)[(1, 0)].norm_sqr())
+                        .map(|channel| (channel * 255.0) as u8);
+                    Color32::from_rgb(r, g, b)
+                } else {
+                    Color32::from_gray((local.jones_matrix[(1, 0)].norm_sqr().clamp(0.0, 1.0) * 255.0) as u8)
+                };
+            }
+        }
+    }
+
+    let texture = texture.get_or_insert_with(|| ui.ctx().load_texture("spatial_preview", image.clone(), TextureOptions::NEAREST));
+    texture.set(image, TextureOptions::NEAREST);
+
+    ui.label("Between crossed polarizers (horizontal in, vertical out)");
+    ui.add(egui::Image::new(&*texture).fit_to_exact_size(Vec2::new(192.0, 192.0)));
+}
+
+// transmission of an etalon for every light plus its transmission against the
+// wavelength and against the gap, the coherent sum of round trips next to the airy function
+fn etalon_plots(ui: &mut Ui, etalon: &WorldObject, world: &World) {
+    let normal = etalon.local_axes()[1];
+    let mut center_wavelength = 0.55;
+    let mut cos_incidence = 1.0;
+
+    for (i, light_index) in world.light_sources.iter().enumerate() {
+        let light = world.objects[*light_index as usize];
+        let light_cos = (Vector3::from(etalon.center) - Vector3::from(light.center)).normalize().dot(&normal).abs();
+        let (order, resonance) = closest_resonance(etalon, light.material_wavelength, light_cos);
+
+        ui.label(format!(
+            "Light source {} (wavelength {} μm, {:.2} degrees from the normal) gets {:.2}% through, round trip phase {:.3} waves",
+            light_index, light.material_wavelength, light_cos.acos().to_degrees(),
+            etalon_transmission(etalon, light.material_wavelength, light_cos).norm_sqr() * 100.0,
+            round_trip_phase(etalon, light.material_wavelength, light_cos) / (2.0 * PI)
+        ));
+        ui.label(format!(
+            "Closest peak: order {} at {:.5} μm, free spectral range {:.3} nm, peaks {:.4} nm wide",
+            order, resonance,
+            free_spectral_range(etalon, light.material_wavelength, light_cos) * 1000.0,
+            free_spectral_range(etalon, light.material_wavelength, light_cos) * 1000.0 / finesse(etalon.etalon_reflectance)
+        ));
+
+        if i == 0 {
+            center_wavelength = light.material_wavelength;
+            cos_incidence = light_cos;
+        }
+    }
+
+    let free_spectral_range = free_spectral_range(etalon, center_wavelength, cos_incidence);
+    let samples = 2000;
+
+    let spectrum: Vec<[f64; 3]> = (0..=samples).map(|i| {
+        let wavelength = center_wavelength + free_spectral_range * 4.0 * (i as f32 / samples as f32 - 0.5);
+        let phase = round_trip_phase(etalon, wavelength, cos_incidence);
+
+        [wavelength as f64, etalon_transmission(etalon, wavelength, cos_incidence).norm_sqr() as f64, airy_transmittance(etalon.etalon_reflectance, phase) as f64]
+    }).collect();
+
+    ui.label("Transmission against the wavelength (μm)");
+    Plot::new("etalon_spectrum_plot")
+        .view_aspect(2.0)
+        .include_y(0.0)
+        .include_y(1.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(spectrum.iter().map(|point| [point[0], point[1]]).collect::<PlotPoints>()).name("Sum of round trips"));
+            plot_ui.line(Line::new(spectrum.iter().map(|point| [point[0], point[2]]).collect::<PlotPoints>()).name("Airy function"));
+            plot_ui.vline(VLine::new(center_wavelength).name("Light"));
+        });
+
+    // moving one of the mirrors half a wavelength goes through a whole free spectral range
+    let gap_range = center_wavelength / (2.0 * etalon.etalon_index * cos_inside(etalon, cos_incidence).max(1e-3));
+    let gaps: PlotPoints = (0..=samples).map(|i| {
+        let gap = etalon.etalon_gap + gap_range * 2.0 * (i as f32 / samples as f32 - 0.5);
+        let swept = WorldObject { etalon_gap: gap, ..*etalon };
+
+        [gap as f64, etalon_transmission(&swept, center_wavelength, cos_incidence).norm_sqr() as f64]
+    }).collect();
+
+    ui.label(format!("Transmission against the gap (μm) at {} μm", center_wavelength));
+    Plot::new("etalon_gap_plot")
+        .view_aspect(2.0)
+        .include_y(0.0)
+        .include_y(1.0)
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(gaps));
+            plot_ui.vline(VLine::new(etalon.etalon_gap));
+        });
+}
+
+fn grating_parameters_menu(ui: &mut Ui, grating: &mut WorldObject) {
+    egui::ComboBox::from_label("Grating type")
+        .selected_text(format!("{}", grating.grating_type))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut grating.grating_type, GratingType::Transmission, "Transmission grating");
+            ui.selectable_value(&mut grating.grating_type, GratingType::Reflection, "Reflection grating");
+        }
+    );
+
+    ui.add(Slider::new(&mut grating.groove_density, 1.0..=100.0).logarithmic(true).text("Grooves per meter"));
+    ui.add(Slider::new(&mut grating.blaze_angle, 0.0..=PI/3.0).text("Blaze angle (radians)"));
+}
+
+// returns whether the size of the prism changed
+fn prism_parameters_menu(ui: &mut Ui, prism: &mut WorldObject) -> bool {
+    ui.add(Slider::new(&mut prism.apex_angle, (PI / 18.0)..=(PI * 2.0 / 3.0)).text("Apex angle (radians)")).changed()
+}
+
+// returns whether the size of the beam displacer changed
+fn displacer_parameters_menu(ui: &mut Ui, displacer: &mut WorldObject, materials: &MaterialLibrary) -> bool {
+    let resized = ui.add(Slider::new(&mut displacer.displacer_length, 0.25..=8.0).text("Length (cubes)")).changed();
+
+    ui.add(Slider::new(&mut displacer.optic_axis[0], 0.0..=(PI / 2.0)).text("Optic axis angle from the normal (radians)"));
+    ui.add(Slider::new(&mut displacer.optic_axis[1], 0.0..=(PI * 2.0)).text("Optic axis angle around the normal (radians)"));
+
+    material_menu(ui, "Ordinary index", &mut displacer.material, materials);
+    material_menu(ui, "Extraordinary index", &mut displacer.extraordinary_material, materials);
+
+    resized
 }
 
 // rand doesnt work good with wasm, so we will just generate them
@@ -48,108 +1179,763 @@ fn generate_colors_list() -> Vec<[u8; 4]> {
     ]
 }
 
-impl MenusState {
-    pub fn new(image_texture: TextureHandle, debug_texture: TextureHandle, raw_images: Vec<ImageBuffer<Rgba<u8>, Vec<u8>>>, image_sizes: Vec<[usize; 2]>) -> MenusState {
-        return MenusState {
-            selected_demo: Demo::None,
-            last_selected_demo: Demo::None,
+impl MenusState {
+    pub fn new(image_texture: TextureHandle, debug_texture: TextureHandle, raw_images: Vec<ImageBuffer<Rgba<u8>, Vec<u8>>>, image_sizes: Vec<[usize; 2]>) -> MenusState {
+        return MenusState {
+            selected_demo: Demo::None,
+            last_selected_demo: Demo::None,
+
+            selected_polarizer_type: PolarizerType::LinearHorizontal,
+            selected_color: Color32::from_rgb(250, 50, 250),
+            selected_light_polarization: LightPolarizationType::LinearHorizontal,
+            object_creation_state: WorldObject::new(),
+            image_texture,
+            debug_texture,
+            raw_images,
+            image_sizes,
+            should_display_debug_menu: false,
+            trying_to_align_to_object: false,
+            should_display_debug_objects_view: false,
+            aperture_screen_distance: 10.0,
+            new_materials_definition: String::new(),
+            new_materials_message: String::new(),
+            coating_design_wavelength: 0.55,
+            coating_plot_angle: 0.0,
+            element_map_drop_channel: MapChannel::Retardance,
+            element_maps_message: String::new(),
+            spatial_preview_texture: None,
+            detector_windows: HashMap::new(),
+            poincare_view: [0.6, 0.4],
+            ellipse_input_polarization: LightPolarizationType::LinearHorizontal,
+            sweep: Sweep { object_index: 0, parameter: SweepParameter::PolarizerAngle, start: 0.0, end: PI, steps: 37, target_index: 0, quantity: DetectorQuantity::Intensity },
+            sweep_results: vec![],
+            optimization: Optimization { variables: vec![], probe_index: 0, objective: Objective::MaximizeIntensity, target_polarization: LightPolarizationType::CircularRightHand, max_iterations: 200 },
+            optimization_iterations: vec![],
+            picking_profile_point: false,
+            line_profile: LineProfile { object_index: 0, points: vec![], samples: 200, show_phase: false, show_stokes: false },
+            cross_section_texture: None,
+            shown_beam_paths: HashSet::new(),
+            show_beam_envelopes: true,
+            show_polarization_glyphs: true
+        };
+    }
+
+    pub fn debug_menu(&mut self, ui: &mut Ui, world: &mut World, glow_program: MainGlowProgram) {
+        ui.add(Label::new(format!("Current resolution: {:?}", glow_program.current_texture_resolution)));
+
+        egui::CollapsingHeader::new("Gpu compatible objects list")
+            .show(ui, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.add(Label::new(format!("{:?}", world.get_gpu_compatible_world_objects_list().chunks(GPU_OBJECT_SIZE).into_iter().map(|chunk| chunk).collect::<Vec<&[u32]>>())));
+            });
+        });
+
+        ui.add(Label::new(format!("Light sources: {:?}", world.light_sources)));
+        ui.add(Label::new(format!("World objects stack: {:?}", world.objects_stack)));
+        ui.add(Label::new(format!("World objects associations: {:?}", world.objects_associations)));
+        ui.add(Label::new(format!("Aligned objects: {:?}", world.aligned_objects)));
+
+        TableBuilder::new(ui)
+            .column(Column::auto().resizable(true))
+            .column(Column::remainder())
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.heading("Index");
+                });
+                header.col(|ui| {
+                    ui.heading("World object");
+                });
+            })
+            .body(|mut body| {
+                for i in 0..world.objects.len() {
+                    body.row(10.0, |mut row| {
+                        row.col(|ui| {
+                            ui.label(format!("{:?}", i));
+                        });
+                        row.col(|ui| {
+                            ui.label(format!("{:?}", world.objects[i]));
+                        });
+                    });
+                }
+            });
+
+        if ui.add(Button::new("Print hashmap")).clicked() {
+            console::log_1(&format!("{:?}", world.hash_map).into());
+        }
+
+        ui.add(Label::new(format!("Current object jones matrix: {:#?}", self.object_creation_state.jones_matrix)));
+
+        if self.should_display_debug_objects_view {
+            if ui.add(Button::new("Hide debug objects view")).clicked() {
+                self.should_display_debug_objects_view = false;
+            }
+
+        } else {
+            if ui.add(Button::new("Show debug objects view (WARNING, EXTREMELY SLOW)")).clicked() {
+                self.should_display_debug_objects_view = true;
+            }
+        }
+
+        if self.should_display_debug_objects_view {
+            let colors = generate_colors_list();
+            let objects_colored: Vec<u8> = glow_program.objects_found.chunks(4).flat_map(|x| colors[(x[0] % 12) as usize]).rev().collect();
+
+            let curr_image = &objects_colored;
+
+            self.debug_texture.set(
+                ColorImage::from_rgba_unmultiplied([
+                    glow_program.current_texture_resolution[0] as usize,
+                    glow_program.current_texture_resolution[1] as usize
+                ], &curr_image),
+                TextureOptions::default(),
+            );
+
+            ui.add(
+                egui::Image::new(&self.debug_texture)
+                    .max_height(400.0)
+                    .max_width(500.0)
+            );
+        }
+    }
+
+    // one window per open detector readout, live ones record a new frame every time
+    pub fn detector_windows(&mut self, ctx: &egui::Context, world: &World, cube_scaling_factor: f32) {
+        // detectors that were removed or turned into something else
+        self.detector_windows.retain(|index, _| world.objects_associations.contains_key(index) && world.objects[*index].object_type == ObjectType::Detector);
+
+        let mut closed = vec![];
+
+        for (detector_index, window) in self.detector_windows.iter_mut() {
+            let mut open = true;
+
+            egui::Window::new(format!("Detector {}", detector_index)).open(&mut open).show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut window.live, "Live update");
+                    ui.checkbox(&mut window.accumulate, "Accumulate frames");
+                });
+
+                let record = window.live || ui.add(Button::new("Record frame")).clicked();
+                if ui.add(Button::new("Reset")).clicked() || (record && !window.accumulate) {
+                    window.readout = DetectorReadout::new(&world.objects[*detector_index]);
+                }
+
+                if record {
+                    window.readout.record(world, *detector_index, cube_scaling_factor);
+                }
+
+                egui::ComboBox::from_label("Quantity")
+                    .selected_text(format!("{}", window.quantity))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut window.quantity, DetectorQuantity::Intensity, "Intensity (S0)");
+                        ui.selectable_value(&mut window.quantity, DetectorQuantity::S1, "S1 (horizontal - vertical)");
+                        ui.selectable_value(&mut window.quantity, DetectorQuantity::S2, "S2 (diagonal - anti-diagonal)");
+                        ui.selectable_value(&mut window.quantity, DetectorQuantity::S3, "S3 (right - left circular)");
+                        ui.selectable_value(&mut window.quantity, DetectorQuantity::DegreeOfPolarization, "Degree of polarization");
+                    }
+                );
+
+                let (min, max, mean) = window.readout.statistics(window.quantity);
+                ui.label(format!("{} frames, min: {:.4e}, max: {:.4e}, mean: {:.4e}", window.readout.frames, min, max, mean));
+
+                let image = readout_image(&window.readout, window.quantity);
+                let texture = window.texture.get_or_insert_with(|| ui.ctx().load_texture(format!("detector_{}", detector_index), image.clone(), TextureOptions::NEAREST));
+                texture.set(image, TextureOptions::NEAREST);
+
+                let [columns, rows] = window.readout.pixels;
+                let size = Vec2::new(columns as f32, rows as f32) * (320.0 / columns.max(rows) as f32);
+                ui.add(egui::Image::new(&*texture).fit_to_exact_size(size));
+
+                egui::CollapsingHeader::new("Fringe analysis").show(ui, |ui| {
+                    egui::ComboBox::from_label("Profile")
+                        .selected_text(format!("{}", window.fringe_axis))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut window.fringe_axis, FringeAxis::Horizontal, format!("{}", FringeAxis::Horizontal));
+                            ui.selectable_value(&mut window.fringe_axis, FringeAxis::Vertical, format!("{}", FringeAxis::Vertical));
+                        }
+                    );
+
+                    let detector = world.objects[*detector_index];
+                    let side = sensor_side(&detector) * cube_scaling_factor;
+                    let (intensities, spacing) = detector_profile(&window.readout, side, window.fringe_axis);
+
+                    let [horizontal, _, vertical] = detector.local_axes();
+                    let axis = if window.fringe_axis == FringeAxis::Horizontal { horizontal } else { vertical };
+                    let expected = expected_fringes(world, Vector3::from(detector.center), axis, cube_scaling_factor, Some(*detector_index));
+
+                    fringe_report(ui, &format!("detector_{}", detector_index), &intensities, spacing, expected);
+                });
+            });
 
-            selected_polarizer_type: PolarizerType::LinearHorizontal,
-            selected_color: Color32::from_rgb(250, 50, 250),
-            selected_light_polarization: LightPolarizationType::LinearHorizontal,
-            angle: 0f32,
-            relative_phase_retardation: 0f32,
-            circularity: 0f32,
-            object_creation_state: WorldObject::new(),
-            image_texture,
-            debug_texture,
-            raw_images,
-            image_sizes,
-            should_display_debug_menu: false,
-            trying_to_align_to_object: false,
-            should_display_debug_objects_view: false
-        };
+            if !open {
+                closed.push(*detector_index);
+            }
+        }
+
+        for detector_index in closed {
+            self.detector_windows.remove(&detector_index);
+        }
+
+        // keep the live ones going
+        if self.detector_windows.values().any(|window| window.live) {
+            ctx.request_repaint();
+        }
     }
 
-    pub fn debug_menu(&mut self, ui: &mut Ui, world: &mut World, glow_program: MainGlowProgram) {
-        ui.add(Label::new(format!("Current resolution: {:?}", glow_program.current_texture_resolution)));
+    pub fn sweep_menu(&mut self, ui: &mut Ui, world: &World, cube_scaling_factor: f32) {
+        let mut indices: Vec<usize> = world.objects_associations.keys().copied().collect();
+        indices.sort();
 
-        egui::CollapsingHeader::new("Gpu compatible objects list")
-            .show(ui, |ui| {
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.add(Label::new(format!("{:?}", world.get_gpu_compatible_world_objects_list().chunks(24).into_iter().map(|chunk| chunk).collect::<Vec<&[u32]>>())));
+        let name = |index: usize| {
+            if world.objects_associations.contains_key(&index) {
+                format!("{}: {:?}", index, world.objects[index].object_type)
+            } else {
+                "None".to_string()
+            }
+        };
+
+        egui::ComboBox::from_label("Object")
+            .selected_text(name(self.sweep.object_index))
+            .show_ui(ui, |ui| {
+                for index in &indices {
+                    ui.selectable_value(&mut self.sweep.object_index, *index, name(*index));
+                }
+            }
+        );
+
+        let previous_parameter = self.sweep.parameter;
+        egui::ComboBox::from_label("Parameter")
+            .selected_text(format!("{}", self.sweep.parameter))
+            .show_ui(ui, |ui| {
+                for parameter in SweepParameter::ALL {
+                    ui.selectable_value(&mut self.sweep.parameter, parameter, format!("{}", parameter));
+                }
+            }
+        );
+
+        if self.sweep.parameter != previous_parameter {
+            (self.sweep.start, self.sweep.end) = self.sweep.parameter.default_range();
+        }
+
+        if world.objects_associations.contains_key(&self.sweep.object_index) || self.sweep.parameter == SweepParameter::CubeScalingFactor {
+            ui.label(format!("Current value: {:.4}", self.sweep.parameter.value(world, self.sweep.object_index, cube_scaling_factor)));
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.sweep.start).speed(0.01).prefix("From "));
+            ui.add(egui::DragValue::new(&mut self.sweep.end).speed(0.01).prefix("to "));
+        });
+        ui.add(Slider::new(&mut self.sweep.steps, 2..=500).text("Steps"));
+
+        egui::ComboBox::from_label("Measured at")
+            .selected_text(name(self.sweep.target_index))
+            .show_ui(ui, |ui| {
+                for index in indices.iter().filter(|index| matches!(world.objects[**index].object_type, ObjectType::Probe | ObjectType::Detector)) {
+                    ui.selectable_value(&mut self.sweep.target_index, *index, name(*index));
+                }
+            }
+        );
+
+        egui::ComboBox::from_label("Measured quantity")
+            .selected_text(format!("{}", self.sweep.quantity))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.sweep.quantity, DetectorQuantity::Intensity, "Intensity (S0)");
+                ui.selectable_value(&mut self.sweep.quantity, DetectorQuantity::S1, "S1 (horizontal - vertical)");
+                ui.selectable_value(&mut self.sweep.quantity, DetectorQuantity::S2, "S2 (diagonal - anti-diagonal)");
+                ui.selectable_value(&mut self.sweep.quantity, DetectorQuantity::S3, "S3 (right - left circular)");
+                ui.selectable_value(&mut self.sweep.quantity, DetectorQuantity::DegreeOfPolarization, "Degree of polarization");
+            }
+        );
+
+        let object_exists = self.sweep.parameter == SweepParameter::CubeScalingFactor || world.objects_associations.contains_key(&self.sweep.object_index);
+        let target_exists = world.objects_associations.contains_key(&self.sweep.target_index)
+            && matches!(world.objects[self.sweep.target_index].object_type, ObjectType::Probe | ObjectType::Detector);
+
+        if !target_exists {
+            ui.label("Add a probe or a detector to measure at");
+        }
+
+        if ui.add_enabled(object_exists && target_exists, Button::new("Run sweep")).clicked() {
+            self.sweep_results = self.sweep.run(world, cube_scaling_factor);
+        }
+
+        if self.sweep_results.is_empty() {
+            return;
+        }
+
+        let points: PlotPoints = self.sweep_results.iter().map(|[value, measured]| [*value as f64, *measured as f64]).collect();
+        let markers: PlotPoints = self.sweep_results.iter().map(|[value, measured]| [*value as f64, *measured as f64]).collect();
+
+        Plot::new("sweep_plot")
+            .view_aspect(2.0)
+            .x_axis_label(format!("{}", self.sweep.parameter))
+            .y_axis_label(format!("{}", self.sweep.quantity))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(points));
+                plot_ui.points(egui_plot::Points::new(markers).radius(2.0));
+            });
+
+        let csv = self.sweep.csv(&self.sweep_results);
+
+        if ui.add(Button::new("Copy as CSV")).clicked() {
+            ui.output_mut(|output| output.copied_text = csv.clone());
+        }
+
+        egui::CollapsingHeader::new("CSV").show(ui, |ui| {
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut csv.as_str()).code_editor().desired_width(f32::INFINITY));
             });
         });
+    }
 
-        ui.add(Label::new(format!("Light sources: {:?}", world.light_sources)));
-        ui.add(Label::new(format!("World objects stack: {:?}", world.objects_stack)));
-        ui.add(Label::new(format!("World objects associations: {:?}", world.objects_associations)));
-        ui.add(Label::new(format!("Aligned objects: {:?}", world.aligned_objects)));
+    pub fn optimizer_menu(&mut self, ui: &mut Ui, world: &mut World, cube_scaling_factor: &mut f32) {
+        let mut indices: Vec<usize> = world.objects_associations.keys().copied().collect();
+        indices.sort();
 
-        TableBuilder::new(ui)
-            .column(Column::auto().resizable(true))
-            .column(Column::remainder())
-            .header(20.0, |mut header| {
-                header.col(|ui| {
-                    ui.heading("Index");
+        let name = |world: &World, index: usize| {
+            if world.objects_associations.contains_key(&index) {
+                format!("{}: {:?}", index, world.objects[index].object_type)
+            } else {
+                "None".to_string()
+            }
+        };
+
+        ui.label("Variables");
+        let mut removed = None;
+
+        for (i, variable) in self.optimization.variables.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source(format!("optimization_object_{}", i))
+                    .selected_text(name(world, variable.object_index))
+                    .show_ui(ui, |ui| {
+                        for index in &indices {
+                            ui.selectable_value(&mut variable.object_index, *index, name(world, *index));
+                        }
+                    }
+                );
+
+                let previous_parameter = variable.parameter;
+                egui::ComboBox::from_id_source(format!("optimization_parameter_{}", i))
+                    .selected_text(format!("{}", variable.parameter))
+                    .show_ui(ui, |ui| {
+                        for parameter in SweepParameter::ALL {
+                            ui.selectable_value(&mut variable.parameter, parameter, format!("{}", parameter));
+                        }
+                    }
+                );
+
+                if variable.parameter != previous_parameter {
+                    (variable.min, variable.max) = variable.parameter.default_range();
+                }
+
+                ui.add(egui::DragValue::new(&mut variable.min).speed(0.01).prefix("From "));
+                ui.add(egui::DragValue::new(&mut variable.max).speed(0.01).prefix("to "));
+
+                if ui.add(Button::new("Remove")).clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+
+        if let Some(i) = removed {
+            self.optimization.variables.remove(i);
+        }
+
+        if ui.add(Button::new("Add variable")).clicked() {
+            let (min, max) = SweepParameter::PolarizerAngle.default_range();
+            self.optimization.variables.push(OptimizationVariable { object_index: 0, parameter: SweepParameter::PolarizerAngle, min, max });
+        }
+
+        ui.add_space(10.0);
+
+        egui::ComboBox::from_label("Objective")
+            .selected_text(format!("{}", self.optimization.objective))
+            .show_ui(ui, |ui| {
+                for objective in [Objective::MaximizeIntensity, Objective::MinimizeIntensity, Objective::Circular, Objective::TargetPolarization] {
+                    ui.selectable_value(&mut self.optimization.objective, objective, format!("{}", objective));
+                }
+            }
+        );
+
+        if self.optimization.objective == Objective::TargetPolarization {
+            egui::ComboBox::from_label("Target polarization")
+                .selected_text(format!("{}", self.optimization.target_polarization))
+                .show_ui(ui, |ui| {
+                    for polarization_type in [
+                        LightPolarizationType::LinearHorizontal, LightPolarizationType::LinearVertical,
+                        LightPolarizationType::LinearDiagonal, LightPolarizationType::LinearAntiDiagonal,
+                        LightPolarizationType::CircularRightHand, LightPolarizationType::CircularLeftHand
+                    ] {
+                        ui.selectable_value(&mut self.optimization.target_polarization, polarization_type, format!("{}", polarization_type));
+                    }
+                }
+            );
+        }
+
+        egui::ComboBox::from_label("Measured at")
+            .selected_text(name(world, self.optimization.probe_index))
+            .show_ui(ui, |ui| {
+                for index in indices.iter().filter(|index| world.objects[**index].object_type == ObjectType::Probe) {
+                    ui.selectable_value(&mut self.optimization.probe_index, *index, name(world, *index));
+                }
+            }
+        );
+
+        ui.add(Slider::new(&mut self.optimization.max_iterations, 10..=1000).text("Maximum iterations"));
+
+        let probe_exists = world.objects_associations.contains_key(&self.optimization.probe_index) && world.objects[self.optimization.probe_index].object_type == ObjectType::Probe;
+        let variables_exist = !self.optimization.variables.is_empty() && self.optimization.variables.iter().all(|variable| variable.parameter == SweepParameter::CubeScalingFactor || world.objects_associations.contains_key(&variable.object_index));
+
+        if !probe_exists {
+            ui.label("Add a probe to measure at");
+        }
+
+        if ui.add_enabled(probe_exists && variables_exist, Button::new("Optimize")).clicked() {
+            self.optimization_iterations = self.optimization.run(world, *cube_scaling_factor);
+        }
+
+        let Some(result) = self.optimization_iterations.last().cloned() else { return };
+
+        ui.label(format!("{} iterations, objective {:.6e}", self.optimization_iterations.len(), result.objective));
+        for (variable, value) in self.optimization.variables.iter().zip(&result.values) {
+            ui.label(format!("Object {}, {}: {:.5}", variable.object_index, variable.parameter, value));
+        }
+
+        let objective: PlotPoints = self.optimization_iterations.iter().enumerate().map(|(i, iteration)| [i as f64, iteration.objective as f64]).collect();
+        Plot::new("optimization_plot")
+            .view_aspect(2.0)
+            .x_axis_label("Iteration")
+            .y_axis_label("Objective")
+            .show(ui, |plot_ui| plot_ui.line(Line::new(objective)));
+
+        egui::CollapsingHeader::new("Iterations").show(ui, |ui| {
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                egui::Grid::new("optimization_iterations").striped(true).show(ui, |ui| {
+                    for (i, iteration) in self.optimization_iterations.iter().enumerate() {
+                        ui.label(format!("{}", i));
+                        ui.label(format!("{:.6e}", iteration.objective));
+                        ui.label(iteration.values.iter().map(|value| format!("{:.5}", value)).collect::<Vec<_>>().join(", "));
+                        ui.end_row();
+                    }
                 });
-                header.col(|ui| {
-                    ui.heading("World object");
+            });
+        });
+
+        if ui.add_enabled(variables_exist, Button::new("Apply to the lab")).clicked() {
+            self.optimization.apply(world, &result.values, cube_scaling_factor);
+        }
+    }
+
+    // a point picked in the viewport for the line profile, segments stay on one object
+    pub fn add_profile_point(&mut self, object_index: usize, point: Vector3<f32>) {
+        let profile = &mut self.line_profile;
+
+        if profile.points.len() >= 2 || profile.object_index != object_index {
+            profile.points.clear();
+        }
+
+        profile.object_index = object_index;
+        profile.points.push(point);
+        self.picking_profile_point = profile.points.len() < 2;
+    }
+
+    // points of the line profile to draw over the viewport
+    pub fn profile_points(&self) -> &[Vector3<f32>] {
+        &self.line_profile.points
+    }
+
+    pub fn line_profile_menu(&mut self, ui: &mut Ui, world: &World, cube_scaling_factor: f32) {
+        let profile = &mut self.line_profile;
+
+        if self.picking_profile_point {
+            ui.label(if profile.points.len() == 1 { "Click on the end of the segment" } else { "Click on the start of the segment, on a wall or a screen" });
+
+            if ui.add(Button::new("Cancel")).clicked() {
+                self.picking_profile_point = false;
+            }
+        } else if ui.add(Button::new("Pick a segment in the lab")).clicked() {
+            profile.points.clear();
+            self.picking_profile_point = true;
+        }
+
+        if profile.points.len() < 2 || !world.objects_associations.contains_key(&profile.object_index) {
+            return;
+        }
+
+        ui.label(format!(
+            "On object {} ({}), {:.4} m long",
+            profile.object_index, world.objects[profile.object_index].object_type, (profile.points[1] - profile.points[0]).norm() * cube_scaling_factor
+        ));
+
+        ui.add(Slider::new(&mut profile.samples, 2..=1000).text("Samples"));
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut profile.show_phase, "Phase");
+            ui.checkbox(&mut profile.show_stokes, "Stokes parameters");
+        });
+
+        let samples = sample_profile(world, profile.object_index, profile.points[0], profile.points[1], profile.samples, cube_scaling_factor);
+
+        let intensity: PlotPoints = samples.iter().map(|sample| [sample.distance as f64 * 1000.0, sample.stokes[0] as f64]).collect();
+        Plot::new("line_profile_intensity")
+            .view_aspect(2.5)
+            .x_axis_label("Distance (mm)")
+            .y_axis_label("Intensity")
+            .legend(Legend::default())
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(intensity).name("S0"));
+
+                if profile.show_stokes {
+                    for (i, name) in [(1, "S1"), (2, "S2"), (3, "S3")] {
+                        let line: PlotPoints = samples.iter().map(|sample| [sample.distance as f64 * 1000.0, sample.stokes[i] as f64]).collect();
+                        plot_ui.line(Line::new(line).name(name));
+                    }
+                }
+            });
+
+        if profile.show_phase {
+            Plot::new("line_profile_phase")
+                .view_aspect(2.5)
+                .x_axis_label("Distance (mm)")
+                .y_axis_label("Phase (radians)")
+                .include_y(-PI)
+                .include_y(PI)
+                .legend(Legend::default())
+                .show(ui, |plot_ui| {
+                    for (i, name) in [(0, "Ex"), (1, "Ey")] {
+                        let points: PlotPoints = samples.iter().filter_map(|sample| sample.phase.map(|phase| [sample.distance as f64 * 1000.0, phase[i] as f64])).collect();
+                        plot_ui.points(egui_plot::Points::new(points).radius(1.5).name(name));
+                    }
                 });
-            })
-            .body(|mut body| {
-                for i in 0..world.objects.len() {
-                    body.row(10.0, |mut row| {
-                        row.col(|ui| {
-                            ui.label(format!("{:?}", i));
-                        });
-                        row.col(|ui| {
-                            ui.label(format!("{:?}", world.objects[i]));
-                        });
-                    });
+        }
+
+        let csv = profile_csv(&samples, profile.show_phase, profile.show_stokes);
+        if ui.add(Button::new("Copy as CSV")).clicked() {
+            ui.output_mut(|output| output.copied_text = csv.clone());
+        }
+
+        egui::CollapsingHeader::new("CSV").show(ui, |ui| {
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut csv.as_str()).code_editor().desired_width(f32::INFINITY));
+            });
+        });
+
+        egui::CollapsingHeader::new("Fringe analysis").show(ui, |ui| {
+            let intensities: Vec<f32> = samples.iter().map(|sample| sample.stokes[0]).collect();
+            let spacing = samples[1].distance - samples[0].distance;
+            let [start, end] = [profile.points[0], profile.points[1]];
+            let expected = expected_fringes(world, (start + end) / 2.0, (end - start).normalize(), cube_scaling_factor, Some(profile.object_index));
+
+            fringe_report(ui, "line_profile", &intensities, spacing, expected);
+        });
+    }
+
+    pub fn cross_section_menu(&mut self, ui: &mut Ui, world: &World, section: &mut CrossSection, selected_center: Option<[f32; 3]>, camera_position: Vector3<f32>, cube_scaling_factor: f32) {
+        ui.label("A slice of the lab that shows the light between the objects, like the waist of a beam, its gouy phase or where two beams interfere");
+
+        egui::ComboBox::from_label("Shows")
+            .selected_text(format!("{}", section.mode))
+            .show_ui(ui, |ui| {
+                for mode in [CrossSectionMode::Off, CrossSectionMode::Intensity, CrossSectionMode::Phase, CrossSectionMode::Polarization] {
+                    ui.selectable_value(&mut section.mode, mode, format!("{}", mode));
+                }
+            }
+        );
+
+        if section.mode == CrossSectionMode::Off {
+            return;
+        }
+
+        for (axis, name) in ["X", "Y", "Z"].iter().enumerate() {
+            ui.add(Slider::new(&mut section.center[axis], 1.0..=24.0).text(format!("{} position", name)));
+        }
+
+        ui.add(Slider::new(&mut section.rotation[0], -PI..=PI).text("X rotation"));
+        ui.add(Slider::new(&mut section.rotation[1], -PI..=PI).text("Y rotation"));
+
+        ui.horizontal(|ui| {
+            if ui.add(Button::new("Facing the camera")).clicked() {
+                let to_camera = camera_position - Vector3::from(section.center);
+                section.rotation = [to_camera.x.atan2(to_camera.z), -(to_camera.y / to_camera.norm()).asin() + PI / 2.0];
+            }
+
+            if let Some(center) = selected_center {
+                if ui.add(Button::new("Through the selected object")).clicked() {
+                    section.center = center;
+                }
+            }
+        });
+
+        ui.add(Slider::new(&mut section.size, 0.5..=24.0).text("Size"));
+        ui.add(Slider::new(&mut section.opacity, 0.0..=1.0).text("Opacity"));
+        ui.add(Slider::new(&mut section.exposure, 0.001..=100.0).logarithmic(true).text("Exposure"))
+            .on_hover_text("Intensities over 1 / exposure show up at full brightness");
+
+        match section.mode {
+            CrossSectionMode::Intensity => ui.label("From black through red and yellow to white"),
+            CrossSectionMode::Phase => ui.label("The hue is the phase of the strongest component of the field of the first wavelength, dark where there's no light"),
+            _ => ui.label("Red is horizontal (S1), green diagonal (S2) and blue right circular (S3), unpolarized light is gray"),
+        };
+
+        // the same slice computed on the cpu, seen from the side its normal points to
+        egui::CollapsingHeader::new("Preview").show(ui, |ui| {
+            const PREVIEW_PIXELS: usize = 48;
+
+            let pixels: Vec<Color32> = (0..PREVIEW_PIXELS * PREVIEW_PIXELS).map(|i| {
+                let (column, row) = (i % PREVIEW_PIXELS, i / PREVIEW_PIXELS);
+                let x = section.size * ((column as f32 + 0.5) / PREVIEW_PIXELS as f32 - 0.5);
+                let y = section.size * (0.5 - (row as f32 + 0.5) / PREVIEW_PIXELS as f32);
+
+                let (stokes, phase) = section_fields(world, section.point_at(x, y), cube_scaling_factor);
+                let [r, g, b] = section_color(section.mode, stokes, phase, section.exposure).map(|x| (x * 255.0) as u8);
+
+                Color32::from_rgb(r, g, b)
+            }).collect();
+
+            let image = ColorImage { size: [PREVIEW_PIXELS, PREVIEW_PIXELS], pixels };
+            let texture = self.cross_section_texture.get_or_insert_with(|| ui.ctx().load_texture("cross_section_preview", image.clone(), TextureOptions::NEAREST));
+            texture.set(image, TextureOptions::NEAREST);
+
+            ui.add(egui::Image::new(&*texture).fit_to_exact_size(Vec2::splat(240.0)));
+        });
+    }
+
+    pub fn render_mode_menu(&mut self, ui: &mut Ui, settings: &mut RenderSettings) {
+        egui::ComboBox::from_label("Render mode")
+            .selected_text(format!("{}", settings.mode))
+            .show_ui(ui, |ui| {
+                for mode in RenderMode::ALL {
+                    ui.selectable_value(&mut settings.mode, mode, format!("{}", mode));
+                }
+            }
+        );
+
+        if settings.mode == RenderMode::Realistic {
+            return;
+        }
+
+        if settings.mode.uses_colormap() {
+            egui::ComboBox::from_label("Colormap")
+                .selected_text(format!("{}", settings.colormap))
+                .show_ui(ui, |ui| {
+                    for colormap in [Colormap::Grayscale, Colormap::Inferno, Colormap::Viridis, Colormap::Jet] {
+                        ui.selectable_value(&mut settings.colormap, colormap, format!("{}", colormap));
+                    }
                 }
+            );
+        }
+
+        ui.add(Slider::new(&mut settings.exposure, 0.001..=100.0).logarithmic(true).text("Exposure"))
+            .on_hover_text("Intensities over 1 / exposure show up at full brightness, every view but the intensity one is dimmed where there's less light");
+
+        if settings.mode.is_time_resolved() {
+            ui.horizontal(|ui| {
+                ui.add(Slider::new(&mut settings.field_speed, 0.01..=20.0).logarithmic(true).text("Cycles per time unit"))
+                    .on_hover_text("How many times the field of the first light goes around per time unit, the field of the others goes as many times faster as their frequency is higher");
+                ui.checkbox(&mut settings.field_paused, "Paused");
             });
 
-        if ui.add(Button::new("Print hashmap")).clicked() {
-            console::log_1(&format!("{:?}", world.hash_map).into());
+            if !settings.field_paused {
+                ui.ctx().request_repaint();
+            }
         }
 
-        ui.add(Label::new(format!("Current object jones matrix: {:#?}", self.object_creation_state.jones_matrix)));
+        render_mode_legend(ui, settings);
+    }
+
+    fn beam_path_checkbox(&mut self, ui: &mut Ui, light_index: usize, text: String) {
+        let mut shown = self.shown_beam_paths.contains(&light_index);
+
+        if ui.checkbox(&mut shown, text).changed() {
+            if shown {
+                self.shown_beam_paths.insert(light_index);
+            } else {
+                self.shown_beam_paths.remove(&light_index);
+            }
+        }
+    }
+
+    pub fn beam_paths_menu(&mut self, ui: &mut Ui, world: &World) {
+        ui.label("Draws the axis of the beam of a light through every element it crosses until something solid stops it");
+
+        if world.light_sources.is_empty() {
+            ui.label("There are no lights in the lab");
+        }
+
+        for light_index in world.light_sources.iter().map(|index| *index as usize) {
+            let light = world.objects[light_index];
+            self.beam_path_checkbox(ui, light_index, format!("Light {} ({})", light_index, light.polarization_type));
+        }
+
+        ui.separator();
+
+        ui.checkbox(&mut self.show_beam_envelopes, "Beam envelope")
+            .on_hover_text("The radius where the intensity of the gaussian beam falls to 1/e² of the one on its axis");
+        ui.checkbox(&mut self.show_polarization_glyphs, "Polarization glyphs")
+            .on_hover_text("The polarization ellipse between every pair of elements, red is horizontal, green diagonal and blue right circular like the cross-section, the dot goes around it the way the field does");
+    }
+
+    // the beam paths on top of the lab, `to_screen` takes a point of the world to the
+    // viewport if it's in front of the camera
+    pub fn paint_beam_paths(&self, painter: &egui::Painter, world: &World, to_screen: impl Fn(Vector3<f32>) -> Option<egui::Pos2>, camera_position: Vector3<f32>, cube_scaling_factor: f32, time: f64) {
+        const SAMPLES: usize = 32;
+        const GLYPH_SIZE: f32 = 0.3;
+
+        let paint_polyline = |points: &[Vector3<f32>], stroke: Stroke| {
+            for pair in points.windows(2) {
+                if let (Some(start), Some(end)) = (to_screen(pair[0]), to_screen(pair[1])) {
+                    painter.line_segment([start, end], stroke);
+                }
+            }
+        };
+
+        for light_index in world.light_sources.iter().map(|index| *index as usize).filter(|index| self.shown_beam_paths.contains(index)) {
+            let light = world.objects[light_index];
+            let direction = light_direction(&light);
+            let medium_index = world.medium_refractive_index(light.material_wavelength);
+
+            for segment in beam_path(world, light_index) {
+                let length = (segment.end - segment.start).norm();
+                let samples: Vec<(f32, Vector3<f32>)> = (0..=SAMPLES).map(|i| {
+                    let t = i as f32 / SAMPLES as f32;
+                    (segment.start_distance + t * length, segment.start.lerp(&segment.end, t))
+                }).collect();
+
+                paint_polyline(&samples.iter().map(|(_, point)| *point).collect::<Vec<_>>(), Stroke::new(2.0, light.color));
 
-        if self.should_display_debug_objects_view {
-            if ui.add(Button::new("Hide debug objects view")).clicked() {
-                self.should_display_debug_objects_view = false;
-            }
+                // the silhouette of the beam seen from the camera
+                if self.show_beam_envelopes {
+                    for side in [-1.0, 1.0] {
+                        let edge: Vec<Vector3<f32>> = samples.iter().filter_map(|(distance, point)| {
+                            let outwards = direction.cross(&(point - camera_position)).try_normalize(1e-6)?;
+                            Some(point + outwards * side * beam_radius(&light, *distance, medium_index, cube_scaling_factor))
+                        }).collect();
 
-        } else {
-            if ui.add(Button::new("Show debug objects view (WARNING, EXTREMELY SLOW)")).clicked() {
-                self.should_display_debug_objects_view = true;
-            }
-        }
+                        paint_polyline(&edge, Stroke::new(1.0, light.color.gamma_multiply(0.5)));
+                    }
+                }
 
-        if self.should_display_debug_objects_view {
-            let colors = generate_colors_list();
-            let objects_colored: Vec<u8> = glow_program.objects_found.chunks(4).flat_map(|x| colors[(x[0] % 12) as usize]).rev().collect();
+                if self.show_polarization_glyphs && length > 2.0 * GLYPH_SIZE {
+                    let stokes = stokes_parameters(segment.polarization);
+                    let [r, g, b] = section_color(CrossSectionMode::Polarization, stokes, 0.0, 1.0 / stokes[0]).map(|x| (x * 255.0) as u8);
+                    let color = Color32::from_rgb(r, g, b);
 
-            let curr_image = &objects_colored;
+                    let glyph = polarization_glyph(&light, segment.polarization, segment.start.lerp(&segment.end, 0.5), GLYPH_SIZE, SAMPLES);
+                    paint_polyline(&glyph, Stroke::new(2.0, color));
 
-            self.debug_texture.set(
-                ColorImage::from_rgba_unmultiplied([
-                    glow_program.current_texture_resolution[0] as usize,
-                    glow_program.current_texture_resolution[1] as usize
-                ], &curr_image),
-                TextureOptions::default(),
-            );
+                    // same speed as the polarization ellipse of the inspector, ω = π per time unit
+                    let current = ((time / 2.0).fract() * SAMPLES as f64) as usize;
+                    if let Some(point) = glyph.get(current).and_then(|point| to_screen(*point)) {
+                        painter.circle_filled(point, 3.0, color);
+                    }
+                }
+            }
+        }
 
-            ui.add(
-                egui::Image::new(&self.debug_texture)
-                    .max_height(400.0)
-                    .max_width(500.0)
-            );
+        if self.show_polarization_glyphs && !self.shown_beam_paths.is_empty() {
+            painter.ctx().request_repaint();
         }
     }
 
-    pub fn inspect_object_menu(&mut self, ui: &mut Ui, world: &mut World, time: f64, selected_object_index: &mut usize) {
+    pub fn inspect_object_menu(&mut self, ui: &mut Ui, world: &mut World, time: f64, cube_scaling_factor: f32, selected_object_index: &mut usize) {
         ui.add(Label::new(format!("{:?}", world.objects[*selected_object_index].object_type)));
         ui.add(Label::new(format!("Object index: {:?}", *selected_object_index)));
 
@@ -222,7 +2008,10 @@ impl MenusState {
 
         match world.objects[*selected_object_index].object_type {
             ObjectType::LightSource => {
-                ui.add(Slider::new(&mut world.objects[*selected_object_index].wavelength, 0.001..=1.0).text("Wavelength"));
+                ui.add(Slider::new(&mut world.objects[*selected_object_index].wavelength, 0.001..=1.0).text("Wavelength"))
+                    .on_hover_text("In meters, like the distances scaled by the cube size, sets how the light propagates");
+                ui.add(Slider::new(&mut world.objects[*selected_object_index].material_wavelength, 0.2..=2.5).text("Wavelength for materials (μm)"))
+                    .on_hover_text("What materials and dispersive elements see, visible light goes from 0.38 to 0.78");
                 ui.add(Label::new("Light polarization"));
 
                 egui::ComboBox::from_label("Light source polarization")
@@ -237,53 +2026,455 @@ impl MenusState {
                         ui.selectable_value(&mut world.objects[*selected_object_index].polarization_type, LightPolarizationType::CircularRightHand, "Right circular");
                         ui.selectable_value(&mut world.objects[*selected_object_index].polarization_type, LightPolarizationType::CircularLeftHand, "Left circular");
 
+                        // only the poincaré sphere can make it custom
+                        ui.add_enabled(false, egui::SelectableLabel::new(world.objects[*selected_object_index].polarization_type == LightPolarizationType::Custom, "Custom (drag it on the Poincaré sphere)"));
+
                         // ui.selectable_value(&mut world.objects[*selected_object_index].polarization_type, LightPolarizationType::NotPolarized, "Not polarized");
                     }
                 );
 
                 world.objects[*selected_object_index].set_light_polarization();
 
-                let retardation = Vector2::new(0.0, 0.0);
-                let angular_frequency = 1.0;
+                self.beam_path_checkbox(ui, *selected_object_index, "Show beam path".to_string());
 
-                let jones_vector: Vector2<Complex<f32>> = Vector2::new(
-                    (Complex::new(0.0f32, 1.0f32 * retardation.x)).exp(),
-                    (Complex::new(0.0f32, 1.0f32 * retardation.y)).exp(),
-                ) * Complex::new(0.0f32, (-angular_frequency * time) as f32).exp();
+                polarization_ellipse(ui, "light_polarization_ellipse", world.objects[*selected_object_index].polarization, time);
 
-                let final_jones_vector = [0, 1].map(|i| jones_vector[i] * world.objects[*selected_object_index].polarization[i]);
+                egui::CollapsingHeader::new("Poincaré sphere").default_open(true).show(ui, |ui| {
+                    poincare_sphere(ui, world, *selected_object_index, &mut self.poincare_view);
+                });
+            }
+            ObjectType::CubeWall => todo!(),
+            ObjectType::SquareWall => todo!(),
+            ObjectType::RoundWall => {}
+            ObjectType::Detector => {
+                detector_parameters_menu(ui, &mut world.objects[*selected_object_index]);
+
+                let detector = world.objects[*selected_object_index];
+                let side = sensor_side(&detector);
+                ui.label(format!(
+                    "Sensor of {:.3} x {:.3} m, pixels of {:.4} x {:.4} m",
+                    side * cube_scaling_factor, side * cube_scaling_factor,
+                    side * cube_scaling_factor / detector.detector_pixels[0] as f32, side * cube_scaling_factor / detector.detector_pixels[1] as f32
+                ));
+
+                if ui.add(Button::new("Open readout")).clicked() {
+                    self.detector_windows.entry(*selected_object_index).or_insert_with(|| DetectorWindow {
+                        readout: DetectorReadout::new(&detector),
+                        live: true,
+                        accumulate: false,
+                        quantity: DetectorQuantity::Intensity,
+                        texture: None,
+                        fringe_axis: FringeAxis::Horizontal
+                    });
+                }
+            }
+            ObjectType::Probe => {
+                probe_readout(ui, world, *selected_object_index, cube_scaling_factor);
+            }
+            ObjectType::OpticalObjectSquareWall => todo!(),
+            ObjectType::OpticalObjectCube |
+            ObjectType::OpticalObjectRoundWall => {
+                ui.label(format!("{}", world.objects[*selected_object_index].polarizer_type));
+                polarizer_parameters_menu(ui, &mut world.objects[*selected_object_index]);
 
-                let real: PlotPoints = (0..1000).map(|i| {
-                    [(final_jones_vector[0] * 0.001 * i as f32).real() as f64, (final_jones_vector[1] * 0.001 * i as f32).real() as f64]
-                }).collect();
+                ui.add_space(10.0);
+                material_menu(ui, "Material", &mut world.objects[*selected_object_index].material, &world.materials);
 
-                let real_line = Line::new(real);
+                ui.add_space(10.0);
+                coating_menu(ui, &mut world.objects[*selected_object_index], &world.materials, &mut self.coating_design_wavelength);
 
-                let imaginary: PlotPoints = (0..1000).map(|i| {
-                    [(final_jones_vector[0] * 0.001 * i as f32).imaginary() as f64, (final_jones_vector[1] * 0.001 * i as f32).imaginary() as f64]
-                }).collect();
+                let optical_object = world.objects[*selected_object_index];
+
+                for light_index in &world.light_sources {
+                    let light = world.objects[*light_index as usize];
+                    let wavelength = light.material_wavelength;
+                    ui.label(format!("n = {:.5} for light source {} (wavelength {} μm)", world.materials.refractive_index(&optical_object.material, wavelength), light_index, wavelength));
+
+                    if !optical_object.coating.is_empty() {
+                        let propagation = (Vector3::from(optical_object.center) - Vector3::from(light.center)).normalize();
+                        let transmitted = coating_jones_matrix(&optical_object, &light, propagation, &world.materials, world.medium_refractive_index(wavelength)) * light.polarization;
+
+                        ui.label(format!("The coating lets {:.1}% of it through", transmitted.norm_squared() / light.polarization.norm_squared() * 100.0));
+                    }
+                }
+
+                egui::CollapsingHeader::new("Polarization ellipse").show(ui, |ui| {
+                    egui::ComboBox::from_label("Input polarization")
+                        .selected_text(format!("{}", self.ellipse_input_polarization))
+                        .show_ui(ui, |ui| {
+                            for polarization_type in [
+                                LightPolarizationType::LinearHorizontal, LightPolarizationType::LinearVertical,
+                                LightPolarizationType::LinearDiagonal, LightPolarizationType::LinearAntiDiagonal,
+                                LightPolarizationType::CircularRightHand, LightPolarizationType::CircularLeftHand
+                            ] {
+                                ui.selectable_value(&mut self.ellipse_input_polarization, polarization_type, format!("{}", polarization_type));
+                            }
+                        }
+                    );
+
+                    let mut input = WorldObject { polarization_type: self.ellipse_input_polarization, ..WorldObject::new() };
+                    input.set_light_polarization();
+
+                    ui.label("Input");
+                    polarization_ellipse(ui, "element_input_ellipse", input.polarization, time);
+                    ui.label("Output");
+                    polarization_ellipse(ui, "element_output_ellipse", optical_object.jones_matrix * input.polarization, time);
+                });
+
+                if !optical_object.coating.is_empty() {
+                    ui.add_space(10.0);
+                    coating_plots(ui, &optical_object, world, &mut self.coating_plot_angle, self.coating_design_wavelength);
+                }
+
+                // the whole point of faraday rotators is that lights going in opposite
+                // directions see different rotations, so we show what each one sees
+                if let PolarizerType::OpticalRotator | PolarizerType::FaradayRotator = optical_object.polarizer_type {
+                    ui.add_space(10.0);
+
+                    for light_index in &world.light_sources {
+                        let light = world.objects[*light_index as usize];
+                        let propagation = (Vector3::from(optical_object.center) - Vector3::from(light.center)).normalize();
+                        let jones_matrix = jones_matrix_for_light(&optical_object, &light, propagation);
+
+                        let rotation = jones_matrix[(1, 0)].re.atan2(jones_matrix[(0, 0)].re);
+                        ui.label(format!("Light source {} gets rotated {:.2} degrees", light_index, rotation.to_degrees()));
+                    }
+                }
+
+                // spectral response over the visible range, a thick (multi-order) plate
+                // only has the design retardance close to the design wavelength
+                if optical_object.polarizer_type == PolarizerType::ChromaticRetarder {
+                    ui.add_space(10.0);
+
+                    let spectrum: Vec<(f64, f32)> = (0..=400).map(|i| {
+                        let wavelength = 0.38 + 0.4 * (i as f32 / 400.0);
+                        (wavelength as f64, optical_object.retardance_at(wavelength))
+                    }).collect();
+
+                    let retardance: PlotPoints = spectrum.iter().map(|(wavelength, retardance)| [*wavelength, (retardance / (2.0 * PI)).rem_euclid(1.0) as f64]).collect();
+                    // fast axis at 45 degrees of both polarizers
+                    let transmission: PlotPoints = spectrum.iter().map(|(wavelength, retardance)| [*wavelength, (retardance / 2.0).sin().powi(2) as f64]).collect();
+
+                    for light_index in &world.light_sources {
+                        let light = world.objects[*light_index as usize];
+                        ui.label(format!("Light source {} (wavelength {} μm) sees {:.3} waves of retardance", light_index, light.material_wavelength, optical_object.retardance_at(light.material_wavelength) / (2.0 * PI)));
+                    }
+
+                    Plot::new("retarder_spectrum_plot")
+                        .view_aspect(2.0)
+                        .include_y(0.0)
+                        .include_y(1.0)
+                        .legend(Legend::default())
+                        .show(ui, |plot_ui| {
+                            plot_ui.line(Line::new(retardance).name("Retardance (waves, modulo 1)"));
+                            plot_ui.line(Line::new(transmission).name("Transmission between crossed polarizers"));
+                            plot_ui.vline(VLine::new(optical_object.retarder_design_wavelength).name("Design wavelength"));
+                        });
+                }
+
+                // the peaks of an etalon are a lot narrower than the visible range, so the
+                // plots go over a few free spectral ranges around the first light
+                if optical_object.polarizer_type == PolarizerType::FabryPerotEtalon {
+                    ui.add_space(10.0);
+                    etalon_plots(ui, &optical_object, world);
+                }
+
+                if is_electro_optic(&optical_object) {
+                    ui.add_space(10.0);
+                    electro_optic_plots(ui, &optical_object, time);
+                }
+
+                if is_spatially_varying(&optical_object) {
+                    ui.add_space(10.0);
+
+                    if optical_object.polarizer_type == PolarizerType::ImageElement {
+                        element_maps_menu(ui, world, *selected_object_index, &mut self.element_map_drop_channel, &mut self.element_maps_message);
+                        ui.add_space(10.0);
+                    }
+
+                    spatial_preview(ui, world, *selected_object_index, &mut self.spatial_preview_texture);
+
+                    // what each light gets where its axis goes through the element
+                    let normal = optical_object.local_axes()[1];
+                    for light_index in &world.light_sources {
+                        let light = world.objects[*light_index as usize];
+                        let direction = light_direction(&light);
+                        if direction.dot(&normal).abs() < 1e-4 {
+                            continue;
+                        }
+
+                        let distance = (Vector3::from(optical_object.center) - Vector3::from(light.center)).dot(&normal) / direction.dot(&normal);
+                        let point = Vector3::from(light.center) + direction * distance;
+                        let (x, y) = disk_coordinates(&optical_object, point);
+
+                        let local_element = element_at(&optical_object, point, world.element_maps.get(selected_object_index));
+                        let transmitted = jones_matrix_for_light(&local_element, &light, direction) * light.polarization;
+
+                        ui.label(format!(
+                            "Light source {} goes through ({:.2}, {:.2}) of the disk and comes out as Ex = {:.2}, Ey = {:.2} ({:.1}% of it)",
+                            light_index, x, y, transmitted[0], transmitted[1], transmitted.norm_squared() / light.polarization.norm_squared().max(1e-12) * 100.0
+                        ));
+                    }
+                }
+            }
+            ObjectType::Grating => {
+                grating_parameters_menu(ui, &mut world.objects[*selected_object_index]);
+
+                let grating = world.objects[*selected_object_index];
 
-                let imaginary_line = Line::new(imaginary);
+                for light_index in &world.light_sources {
+                    let light = world.objects[*light_index as usize];
 
-                Plot::new("my_plot")
-                    .view_aspect(2.0)
-                    .include_x(1.0)
-                    .include_y(1.0)
-                    .include_x(-1.0)
-                    .include_y(-1.0)
-                    .show(ui, |plot_ui| {plot_ui.line(real_line); plot_ui.line(imaginary_line)});
+                    ui.add_space(10.0);
+                    ui.label(format!("Light source {} (wavelength {})", light_index, light.wavelength));
+
+                    if grating_incidence_point(&grating, &light).is_none() {
+                        ui.label("Doesn't hit the grating");
+                        continue;
+                    }
+
+                    egui::Grid::new(format!("grating_orders_{}", light_index)).striped(true).show(ui, |ui| {
+                        ui.label("Order");
+                        ui.label("Angle (degrees)");
+                        ui.label("Efficiency");
+                        ui.label("TE amplitude");
+                        ui.label("TM amplitude");
+                        ui.end_row();
+
+                        for order in diffraction_orders(&grating, &light, world.medium_refractive_index(light.material_wavelength)) {
+                            ui.label(format!("{}", order.order));
+                            ui.label(format!("{:.2}", order.angle.to_degrees()));
+                            ui.label(format!("{:.3}", order.efficiency));
+                            ui.label(format!("{:.3}", order.te_amplitude));
+                            ui.label(format!("{:.3}", order.tm_amplitude));
+                            ui.end_row();
+                        }
+                    });
+                }
+            }
+            ObjectType::Prism => {
+                // the footprint of the prism changes with its apex angle
+                if prism_parameters_menu(ui, &mut world.objects[*selected_object_index]) {
+                    let prism = world.objects[*selected_object_index];
+                    world.update_object_position(*selected_object_index, prism);
+                }
+
+                material_menu(ui, "Material", &mut world.objects[*selected_object_index].material, &world.materials);
+
+                ui.add_space(10.0);
+                coating_menu(ui, &mut world.objects[*selected_object_index], &world.materials, &mut self.coating_design_wavelength);
+
+                let prism = world.objects[*selected_object_index];
+
+                if !prism.coating.is_empty() {
+                    coating_plots(ui, &prism, world, &mut self.coating_plot_angle, self.coating_design_wavelength);
+                }
+
+                for light_index in &world.light_sources {
+                    let light = world.objects[*light_index as usize];
+                    let prism_index = world.materials.refractive_index(&prism.material, light.material_wavelength);
+                    let medium_index = world.medium_refractive_index(light.material_wavelength);
+
+                    ui.add_space(10.0);
+                    ui.label(format!("Light source {} (wavelength {} μm), n = {:.5}", light_index, light.material_wavelength, prism_index));
+
+                    match minimum_deviation(&prism, prism_index, medium_index) {
+                        Some(deviation) => ui.label(format!("Minimum deviation: {:.3} degrees", deviation.to_degrees())),
+                        None => ui.label("Every ray gets totally reflected inside")
+                    };
+
+                    match prism_beam(&prism, &light, prism_index, medium_index, &prism.coating.indices(&world.materials, light.material_wavelength)) {
+                        Some(beam) => ui.label(format!("Deviated {:.3} degrees, transmits {:.1}% of s and {:.1}% of p light", beam.deviation.to_degrees(), beam.transmittance[0] * 100.0, beam.transmittance[1] * 100.0)),
+                        None => ui.label("Doesn't go through both faces of the prism")
+                    };
+                }
+            }
+            ObjectType::BeamDisplacer => {
+                // the footprint of the block changes with its length
+                if displacer_parameters_menu(ui, &mut world.objects[*selected_object_index], &world.materials) {
+                    let displacer = world.objects[*selected_object_index];
+                    world.update_object_position(*selected_object_index, displacer);
+                }
+
+                let displacer = world.objects[*selected_object_index];
+
+                for light_index in &world.light_sources {
+                    let light = world.objects[*light_index as usize];
+                    let indices = [
+                        world.materials.refractive_index(&displacer.material, light.material_wavelength),
+                        world.materials.refractive_index(&displacer.extraordinary_material, light.material_wavelength)
+                    ];
+
+                    ui.add_space(10.0);
+                    ui.label(format!("Light source {} (wavelength {} μm), no = {:.5}, ne = {:.5}", light_index, light.material_wavelength, indices[0], indices[1]));
+
+                    let beams = displaced_beams(&displacer, &light, indices, world.medium_refractive_index(light.material_wavelength));
+                    if beams.is_empty() {
+                        ui.label("Doesn't go through the faces of the crystal");
+                        continue;
+                    }
+
+                    for beam in &beams {
+                        let transmitted = beam.jones_matrix * light.polarization;
+                        let power = transmitted.norm_squared() / light.polarization.norm_squared();
+
+                        ui.label(format!("The {} ray sees n = {:.5}, walks off {:.3} degrees and carries {:.1}% of the light", beam.ray, beam.refractive_index, beam.walk_off.to_degrees(), power * 100.0));
+
+                        if beam.ray == CrystalRay::Extraordinary && beam.walk_off > 1e-4 {
+                            let axis_angle = beam.wave_direction.dot(&displacer.optic_axis_direction()).clamp(-1.0, 1.0).acos();
+                            let direction = if walk_off_angle(indices[0], indices[1], axis_angle) > 0.0 { "away from" } else { "towards" };
+
+                            ui.label(format!("It goes {:.2} degrees from the optic axis and walks off {} it", axis_angle.to_degrees(), direction));
+                        }
+                    }
+
+                    if let [ordinary, extraordinary] = beams.as_slice() {
+                        ui.label(format!("The rays leave {:.3} cubes apart", (extraordinary.exit - ordinary.exit).norm()));
+                    }
+                }
+            }
+            ObjectType::Aperture => {
+                aperture_parameters_menu(ui, &mut world.objects[*selected_object_index]);
+
+                // the pattern depends on the light that goes through the aperture,
+                // for now we just use the first light source in the world
+                if let Some(light_index) = world.light_sources.first() {
+                    let aperture = world.objects[*selected_object_index];
+                    let refractive_index = world.medium_refractive_index(world.objects[*light_index as usize].material_wavelength);
+                    // wavelength inside of the medium
+                    let wavelength = world.objects[*light_index as usize].wavelength / refractive_index;
+
+                    ui.add(Slider::new(&mut self.aperture_screen_distance, 0.5..=100.0).logarithmic(true).text("Screen distance in meters"));
+
+                    let fringe_period = expected_fringe_period(&aperture, wavelength, self.aperture_screen_distance, cube_scaling_factor);
+                    ui.label(format!("Expected fringe period: {:.3} meters", fringe_period));
+
+                    let distance = self.aperture_screen_distance;
+                    let far_field: PlotPoints = (-500..=500).map(|i| {
+                        let x = (i as f32 / 500.0) * 4.0 * fringe_period;
+                        let sin_theta = x / (x.powi(2) + distance.powi(2)).sqrt();
+
+                        [x as f64, fraunhofer_intensity(&aperture, sin_theta, 0.0, wavelength, cube_scaling_factor) as f64]
+                    }).collect();
+
+                    // what the renderer actually draws, a screen placed behind the aperture
+                    // (on the opposite side of the light) sampled with the huygens-fresnel integral
+                    let light = world.objects[*light_index as usize];
+                    let [horizontal, mut normal, _] = aperture.local_axes();
+                    if normal.dot(&(Vector3::from(aperture.center) - Vector3::from(light.center))) < 0.0 {
+                        normal = -normal;
+                    }
+
+                    let screen_center = Vector3::from(aperture.center) + normal * (distance / cube_scaling_factor);
+                    let near_field: Vec<(f32, f32)> = (-200..=200).map(|i| {
+                        let x = (i as f32 / 200.0) * 4.0 * fringe_period;
+                        let point = screen_center + horizontal * (x / cube_scaling_factor);
+
+                        (x, diffracted_amplitude(&light, &aperture, point, refractive_index, cube_scaling_factor).norm_sqr())
+                    }).collect();
+
+                    let max_intensity = near_field.iter().map(|(_, intensity)| *intensity).fold(0.0, f32::max).max(1e-12);
+                    let near_field: PlotPoints = near_field.iter().map(|(x, intensity)| [*x as f64, (*intensity / max_intensity) as f64]).collect();
+
+                    Plot::new("fraunhofer_plot")
+                        .view_aspect(2.0)
+                        .include_y(0.0)
+                        .include_y(1.0)
+                        .legend(Legend::default())
+                        .show(ui, |plot_ui| {
+                            plot_ui.line(Line::new(far_field).name("Far field (fraunhofer)"));
+                            plot_ui.line(Line::new(near_field).name("Huygens-Fresnel (rendered)"));
+                        });
+                }
             }
-            ObjectType::CubeWall => todo!(),
-            ObjectType::SquareWall => todo!(),
-            ObjectType::RoundWall => {}
-            ObjectType::OpticalObjectCube => {}
-            ObjectType::OpticalObjectSquareWall => todo!(),
-            ObjectType::OpticalObjectRoundWall => {}
         }
 
         color_picker_color32(ui, &mut world.objects[*selected_object_index].color, egui::color_picker::Alpha::Opaque);
     }
 
+    pub fn materials_menu(&mut self, ui: &mut Ui, world: &mut World) {
+        egui::ComboBox::from_label("Medium the lab is filled with")
+            .selected_text(format!("{}", world.medium))
+            .show_ui(ui, |ui| {
+                for material in &world.materials.materials {
+                    ui.selectable_value(&mut world.medium, MaterialName::new(&material.name), material.name.as_str());
+                }
+            }
+        );
+
+        ui.add_space(10.0);
+
+        // refractive index at the F, d and C fraunhofer lines and the abbe number
+        egui::Grid::new("materials_table").striped(true).show(ui, |ui| {
+            ui.label("Material");
+            ui.label("Model");
+            ui.label("n (486.1 nm)");
+            ui.label("n (587.6 nm)");
+            ui.label("n (656.3 nm)");
+            ui.label("Abbe number");
+            ui.end_row();
+
+            for material in &world.materials.materials {
+                let n_f = material.refractive_index(0.4861);
+                let n_d = material.refractive_index(0.5876);
+                let n_c = material.refractive_index(0.6563);
+
+                ui.label(&material.name);
+                ui.label(format!("{}", material.model));
+                ui.label(format!("{:.5}", n_f));
+                ui.label(format!("{:.5}", n_d));
+                ui.label(format!("{:.5}", n_c));
+
+                if n_f - n_c > 1e-7 {
+                    ui.label(format!("{:.1}", (n_d - 1.0) / (n_f - n_c)));
+                } else {
+                    ui.label("-");
+                }
+                ui.end_row();
+            }
+        });
+
+        ui.add_space(10.0);
+
+        let curves: Vec<Line> = world.materials.materials.iter().map(|material| {
+            let points: PlotPoints = (0..=200).map(|i| {
+                let wavelength = 0.38 + 0.4 * (i as f32 / 200.0);
+                [wavelength as f64, material.refractive_index(wavelength) as f64]
+            }).collect();
+
+            Line::new(points).name(&material.name)
+        }).collect();
+
+        Plot::new("materials_plot")
+            .view_aspect(2.0)
+            .legend(Legend::default())
+            .show(ui, |plot_ui| {
+                for curve in curves {
+                    plot_ui.line(curve);
+                }
+            });
+
+        ui.add_space(10.0);
+        ui.label("New materials, one per line: name; model; coefficients; min wavelength, max wavelength (in μm)");
+        ui.label("Models: 'constant' (n), 'cauchy' (A, B, C) or 'sellmeier' (A, B1, C1, B2, C2, B3, C3)");
+        ui.add(egui::TextEdit::multiline(&mut self.new_materials_definition).hint_text("Sapphire (ordinary); sellmeier; 1, 1.4313493, 0.0052799261, 0.65054713, 0.0142382647, 5.3414021, 325.017834; 0.2, 5.0"));
+
+        if ui.add(Button::new("Add materials")).clicked() {
+            match world.materials.add_materials(&self.new_materials_definition) {
+                Ok(count) => {
+                    self.new_materials_message = format!("Added {} materials", count);
+                    self.new_materials_definition.clear();
+                }
+
+                Err(e) => {
+                    self.new_materials_message = e;
+                }
+            }
+        }
+
+        ui.label(&self.new_materials_message);
+    }
+
     pub fn info_menu(&mut self, ui: &mut Ui) {
         match self.selected_demo {
             Demo::None => {
@@ -308,6 +2499,9 @@ impl MenusState {
 
                 ui.label("The light is modeled as a gaussian beam (a laser light)");
                 ui.hyperlink("https://en.wikipedia.org/wiki/Gaussian_beam");
+                ui.add_space(4.0);
+
+                ui.label("To get its cross-section put a wall in front of it and pick a segment across it in the line profile window");
             }
 
             Demo::SimpleInterferenceDemo => {
@@ -328,37 +2522,36 @@ impl MenusState {
             }
 
             Demo::DoubleSlit => {
-                ui.label("This demo shows an approximation of the double slit experiment, in this experiment we make a single light source interact with itself, here we can simply create two light sources with the same properties and make them interact");
+                ui.label("This demo shows the double slit experiment, a single light source shines on an opaque screen with two slits and the light that goes through them interferes with itself on the wall in front of you");
                 ui.add_space(4.0);
 
-                ui.label("Try selecting the red light source and moving the 'Distance from object' slider, see how it changes!");
+                ui.label("The field behind the screen is computed by adding up the huygens wavelets coming out of every point of the slits, so the fringes and their envelope come straight from the slit geometry");
                 ui.add_space(4.0);
 
-                ui.label("Also try creating new light sources changing their wavelengths and playing with the slider 'Cube size in meters' to see how this pattern develops as you get closer or farther from the light source");
+                ui.label("Try selecting the screen and changing the slit width and pitch, the fringes get closer together as the pitch grows and the envelope gets narrower as the slits get wider, the object inspector also shows the expected far field pattern");
                 ui.add_space(4.0);
 
                 ui.hyperlink("https://en.wikipedia.org/wiki/Double-slit_experiment");
                 ui.add_space(4.0);
 
-                ui.label("We are simulating the bottom set of images");
-                ui.hyperlink("https://www.researchgate.net/publication/335957159/figure/fig2/AS:805377650204672@1569028403287/a-d-Interference-patterns-of-the-light-passing-through-the-axicon-interfering-with-the.ppm");
+                ui.hyperlink("https://en.wikipedia.org/wiki/Huygens%E2%80%93Fresnel_principle");
                 ui.add_space(4.0);
             }
 
             Demo::TripleSlit => {
-                ui.label("This demo shows an approximation of the triple slit experiment, in this experiment we make a single light source interact with itself, here we can simply create three light sources with the same properties and make them interact");
+                ui.label("This demo shows the triple slit experiment, a single light source shines on an opaque screen with three slits and the light that goes through them interferes with itself on the wall in front of you");
                 ui.add_space(4.0);
 
-                ui.label("Try selecting the red light source and moving the 'Distance from object' slider, see how it changes!");
+                ui.label("With three slits a weaker secondary maximum appears between every pair of bright fringes, try changing the number of slits and see how the bright fringes get sharper");
                 ui.add_space(4.0);
 
-                ui.label("Also try creating new light sources changing their wavelengths and playing with the slider 'Cube size in meters' to see how this pattern develops as you get closer or farther from the light source");
+                ui.label("Also try changing the aperture type to a pinhole or a rectangle, and playing with the slider 'Cube size in meters' to see how the pattern goes from the near field (fresnel) to the far field (fraunhofer)");
                 ui.add_space(4.0);
 
-                ui.hyperlink("https://en.wikipedia.org/wiki/Double-slit_experiment");
+                ui.hyperlink("https://en.wikipedia.org/wiki/Diffraction_grating");
                 ui.add_space(4.0);
 
-                ui.label("Here is an example of what we are trying to simulate, note that the profile seen in the experiment is precisely what we get in this visualization");
+                ui.label("Here is an example of what we are trying to simulate");
                 ui.hyperlink("https://www.researchgate.net/publication/263025761/figure/fig1/AS:324895427842053@1454472514561/A-schematic-diagram-of-the-three-slit-interference-experiment-with-a-quantum-which-path.png");
                 ui.add_space(4.0);
             }
@@ -387,6 +2580,70 @@ impl MenusState {
                 ui.label("Here is an image from a real experiment of the same effect");
                 ui.hyperlink("https://atoptics.co.uk/img/blog/venus-diffraction-gratings-opod-1.png");
             }
+
+            Demo::DiffractionGrating => {
+                ui.label("This experiment demonstrates a diffraction grating splitting three lights of different wavelengths (red, green and blue) into their diffraction orders");
+                ui.add_space(4.0);
+
+                ui.label("Every order leaves the grating at the angle given by the grating equation sin(θm) = sin(θi) + mλ/d, so longer wavelengths get bent more and the colors separate");
+                ui.add_space(4.0);
+
+                ui.label("Try selecting the grating and changing the groove density and the blaze angle, the inspector shows the angle and efficiency of every order for each light");
+                ui.add_space(4.0);
+
+                ui.label("Also try turning it into a reflection grating and rotating it");
+                ui.add_space(4.0);
+            }
+
+            Demo::OpticalIsolator => {
+                ui.label("This experiment demonstrates an optical isolator, a horizontal polarizer, a Faraday rotator that rotates the polarization by 45 degrees and a polarizer at 45 degrees");
+                ui.add_space(4.0);
+
+                ui.label("The red light goes through all of them, the blue light goes the other way (like the red light would after bouncing off something) and gets rotated another 45 degrees in the same direction, so the horizontal polarizer blocks it");
+                ui.add_space(4.0);
+
+                ui.label("This only works because the rotation of a Faraday rotator follows the magnetic field and not the light, try replacing it with an optical rotator (like a sugar solution) that also rotates 45 degrees and see how the blue light goes through");
+                ui.add_space(4.0);
+            }
+
+            Demo::Prism => {
+                ui.label("This experiment demonstrates a prism made of dense flint glass (SF11) splitting five lights that go through the same spot, from blue to red, like white light would");
+                ui.add_space(4.0);
+
+                ui.label("The refractive index of glass goes up towards the blue, so blue light gets refracted more at both faces and leaves the prism with a bigger deviation than red light");
+                ui.add_space(4.0);
+
+                ui.label("The prism is turned so that the green light goes through it at minimum deviation, try selecting it and changing its material, apex angle and rotation, the inspector shows the deviation of every light");
+                ui.add_space(4.0);
+            }
+
+            Demo::DoubleRefraction => {
+                ui.label("This experiment demonstrates double refraction in a calcite crystal, the light is polarized at 45 degrees and the crystal splits it into two rays with perpendicular polarizations");
+                ui.add_space(4.0);
+
+                ui.label("The ordinary ray goes straight through, the extraordinary ray sees an index that depends on its angle with the optic axis and its energy walks off to the side, so two spots show up behind the crystal");
+                ui.add_space(4.0);
+
+                ui.label("Try changing the polarization of the light to send all of it into one of the rays, or turning the optic axis of the crystal, along the normal both rays see the same index and there's no walk-off");
+                ui.add_space(4.0);
+            }
+
+            Demo::Photoelasticity => {
+                ui.label("This experiment demonstrates photoelasticity, a polycarbonate disk squeezed along its vertical diameter sits between crossed polarizers and five lights from blue to red act as white light");
+                ui.add_space(4.0);
+
+                ui.label("Stress makes the disk birefringent, the difference between its refractive indices follows the difference between the principal stresses, Δn = C (σ1 - σ2), where C is the stress-optic coefficient of the material");
+                ui.add_space(4.0);
+
+                ui.label("Every color gets a different retardance, so the colored bands (isochromatics) are lines of equal stress difference, they crowd around the points where the load is applied");
+                ui.add_space(4.0);
+
+                ui.label("The dark bands that don't change with the color (isoclinics) are where a principal stress lines up with a polarizer, try turning the polarization of the lights and the analyzer together to move them, or adding quarter-wave plates at 45 degrees before and after the disk (a circular polariscope) to get rid of them");
+                ui.add_space(4.0);
+
+                ui.label("The inspector of the disk shows a preview between crossed polarizers in white light, try raising the load, changing the material or switching to a beam in bending where the neutral axis stays dark");
+                ui.add_space(4.0);
+            }
         }
     }
 
@@ -401,6 +2658,11 @@ impl MenusState {
                 ui.selectable_value(&mut self.selected_demo, Demo::TripleSlit, "Triple slit demo");
                 ui.selectable_value(&mut self.selected_demo, Demo::UncoordinatedInterference, "Uncoordinated interference demo");
                 ui.selectable_value(&mut self.selected_demo, Demo::CoordinatedInterference, "Coordinated interference demo");
+                ui.selectable_value(&mut self.selected_demo, Demo::DiffractionGrating, "Diffraction grating demo");
+                ui.selectable_value(&mut self.selected_demo, Demo::OpticalIsolator, "Optical isolator demo");
+                ui.selectable_value(&mut self.selected_demo, Demo::Prism, "Dispersive prism demo");
+                ui.selectable_value(&mut self.selected_demo, Demo::DoubleRefraction, "Double refraction demo");
+                ui.selectable_value(&mut self.selected_demo, Demo::Photoelasticity, "Photoelasticity demo");
             }
         );
 
@@ -443,9 +2705,42 @@ impl MenusState {
                     demo_world = coordinated_interference_demo();
                     glow.cube_scaling_factor = 1.0;
                 }
+
+                Demo::DiffractionGrating => {
+                    demo_world = diffraction_grating_demo();
+                    glow.cube_scaling_factor = 2.0;
+                }
+
+                // small cubes so that the beams fit through the elements
+                Demo::OpticalIsolator => {
+                    demo_world = optical_isolator_demo();
+                    glow.cube_scaling_factor = 10.0;
+                }
+
+                // the wavelengths are in micrometers so that the prism sees the
+                // right indices, bigger cubes keep the beams thin
+                Demo::Prism => {
+                    demo_world = prism_demo();
+                    glow.cube_scaling_factor = 20.0;
+                }
+
+                Demo::DoubleRefraction => {
+                    demo_world = double_refraction_demo();
+                    glow.cube_scaling_factor = 20.0;
+                }
+
+                // the wavelengths are in micrometers for the retardance of the disk, small
+                // cubes so that the beams are wider than the disk
+                Demo::Photoelasticity => {
+                    demo_world = photoelasticity_demo();
+                    glow.cube_scaling_factor = 5.0;
+                }
             }
 
+            // materials added by the user survive changing demos
+            let materials = world.materials.clone();
             *world = demo_world;
+            world.materials = materials;
             self.last_selected_demo = self.selected_demo;
         }
     }
@@ -457,6 +2752,12 @@ impl MenusState {
                 ui.selectable_value(&mut self.object_creation_state.object_type, ObjectType::RoundWall,               "Wall (round)");
                 ui.selectable_value(&mut self.object_creation_state.object_type, ObjectType::LightSource,             "Light source (sphere)");
                 ui.selectable_value(&mut self.object_creation_state.object_type, ObjectType::OpticalObjectRoundWall,  "Optical object (round)");
+                ui.selectable_value(&mut self.object_creation_state.object_type, ObjectType::Aperture,                "Aperture screen (round)");
+                ui.selectable_value(&mut self.object_creation_state.object_type, ObjectType::Grating,                 "Diffraction grating (round)");
+                ui.selectable_value(&mut self.object_creation_state.object_type, ObjectType::Prism,                   "Prism (triangular)");
+                ui.selectable_value(&mut self.object_creation_state.object_type, ObjectType::BeamDisplacer,           "Beam displacer (birefringent crystal)");
+                ui.selectable_value(&mut self.object_creation_state.object_type, ObjectType::Detector,                "Detector screen (round)");
+                ui.selectable_value(&mut self.object_creation_state.object_type, ObjectType::Probe,                   "Polarimeter probe (sphere)");
             }
         );
 
@@ -539,53 +2840,59 @@ impl MenusState {
                         ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::GeneralWavePlateLinearRetarderTheta, "General Waveplate (Linear Phase Retarder)");
 
                         ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::ArbitraryBirefringentMaterialTheta, "Arbitrary birefringent material (Elliptical phase retarder)");
-                    }
-                );
 
-                let curr_image = &self.raw_images[self.selected_polarizer_type as usize];
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::NeutralDensityFilter, "Neutral density filter");
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::AbsorbingSlab, "Absorbing slab (Beer-Lambert)");
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::PartialLinearPolarizerTheta, "Non-ideal linear polarizer rotated θ degrees");
 
-                self.image_texture.set(
-                    ColorImage::from_rgba_unmultiplied(self.image_sizes[self.selected_polarizer_type as usize], &curr_image),
-                    TextureOptions::default(),
-                );
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::OpticalRotator, "Optical rotator (optically active medium)");
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::FaradayRotator, "Faraday rotator");
 
-                ui.add_space(10.0);
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::ChromaticRetarder, "Chromatic retarder (birefringent plate)");
 
-                match self.selected_polarizer_type {
-                    PolarizerType::LinearTheta                   | 
-                    PolarizerType::QuarterWavePlateFastAxisTheta | 
-                    PolarizerType::HalfWavePlateFastAxisTheta    | 
-                    PolarizerType::HalfWavePlateRotatedTheta     => {
-                        ui.add(Slider::new(&mut self.angle, 0.0..=2.0*PI).text("θ"));
-                    }
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::FabryPerotEtalon, "Fabry-Pérot etalon (multi-pass interference)");
 
-                    PolarizerType::GeneralWavePlateLinearRetarderTheta => {
-                        ui.add(Slider::new(&mut self.angle, 0.0..=PI).text("θ"));
-                        ui.add(Slider::new(&mut self.relative_phase_retardation, 0.0..=2.0*PI).text("Relative phase retardation (η)"));
-                    }
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::QPlate, "Q-plate");
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::VortexRetarder, "Vortex retarder");
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::ImageElement, "Element driven by images (retardance, fast axis and amplitude maps)");
+
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::PhotoelasticSample, "Photoelastic sample (stress-induced birefringence)");
 
-                    PolarizerType::ArbitraryBirefringentMaterialTheta => {
-                        ui.add(Slider::new(&mut self.angle, 0.0..=PI).text("θ"));
-                        ui.add(Slider::new(&mut self.relative_phase_retardation, 0.0..=2.0*PI).text("Relative phase retardation (η)"));
-                        ui.add(Slider::new(&mut self.circularity, (-PI/2.0)..=(PI/2.0)).text("Circularity (φ)"));
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::LiquidCrystalCell, "Liquid crystal cell");
+                        ui.selectable_value(&mut self.selected_polarizer_type, PolarizerType::PockelsCell, "Pockels cell");
                     }
+                );
 
-                    _ => {}
-                }
+                // not every type of object has a picture
+                let has_image = (self.selected_polarizer_type as usize) < self.raw_images.len();
 
-                self.object_creation_state.set_jones_matrix(self.selected_polarizer_type, self.angle, self.relative_phase_retardation, self.circularity);
+                if has_image {
+                    let curr_image = &self.raw_images[self.selected_polarizer_type as usize];
+
+                    self.image_texture.set(
+                        ColorImage::from_rgba_unmultiplied(self.image_sizes[self.selected_polarizer_type as usize], &curr_image),
+                        TextureOptions::default(),
+                    );
+                }
 
                 ui.add_space(10.0);
 
-                ui.add(
-                    egui::Image::new(&self.image_texture)
-                        .max_height(400.0)
-                        .max_width(500.0)
-                        // .fit_to_exact_size(egui::Vec2 { x: 500.0, y: 500.0 })
-                        // .maintain_aspect_ratio(true)
-                );
+                self.object_creation_state.polarizer_type = self.selected_polarizer_type;
+                polarizer_parameters_menu(ui, &mut self.object_creation_state);
 
                 ui.add_space(10.0);
+
+                if has_image {
+                    ui.add(
+                        egui::Image::new(&self.image_texture)
+                            .max_height(400.0)
+                            .max_width(500.0)
+                            // .fit_to_exact_size(egui::Vec2 { x: 500.0, y: 500.0 })
+                            // .maintain_aspect_ratio(true)
+                    );
+
+                    ui.add_space(10.0);
+                }
             }
 
             ObjectType::CubeWall   |
@@ -595,6 +2902,52 @@ impl MenusState {
                 self.object_creation_state.center = [viewer_position.x, viewer_position.y, viewer_position.z];
                 self.object_creation_state.radius = 0.5;
             }
+
+            ObjectType::Aperture => {
+                self.object_creation_state.center = [viewer_position.x, viewer_position.y, viewer_position.z];
+                self.object_creation_state.radius = 0.5;
+
+                aperture_parameters_menu(ui, &mut self.object_creation_state);
+                ui.add_space(10.0);
+            }
+
+            ObjectType::Grating => {
+                self.object_creation_state.center = [viewer_position.x, viewer_position.y, viewer_position.z];
+                self.object_creation_state.radius = 0.5;
+
+                grating_parameters_menu(ui, &mut self.object_creation_state);
+                ui.add_space(10.0);
+            }
+
+            ObjectType::Probe => {
+                self.object_creation_state.center = [viewer_position.x, viewer_position.y, viewer_position.z];
+                self.object_creation_state.radius = 0.1;
+            }
+
+            ObjectType::Detector => {
+                self.object_creation_state.center = [viewer_position.x, viewer_position.y, viewer_position.z];
+                self.object_creation_state.radius = 0.5;
+
+                detector_parameters_menu(ui, &mut self.object_creation_state);
+                ui.add_space(10.0);
+            }
+
+            ObjectType::Prism => {
+                self.object_creation_state.center = [viewer_position.x, viewer_position.y, viewer_position.z];
+
+                ui.add(Slider::new(&mut self.object_creation_state.radius, 0.25..=2.0).text("Half the length of the faces (cubes)"));
+                prism_parameters_menu(ui, &mut self.object_creation_state);
+                material_menu(ui, "Material", &mut self.object_creation_state.material, &world.materials);
+                ui.add_space(10.0);
+            }
+
+            ObjectType::BeamDisplacer => {
+                self.object_creation_state.center = [viewer_position.x, viewer_position.y, viewer_position.z];
+
+                ui.add(Slider::new(&mut self.object_creation_state.radius, 0.25..=2.0).text("Half the width of the faces (cubes)"));
+                displacer_parameters_menu(ui, &mut self.object_creation_state, &world.materials);
+                ui.add_space(10.0);
+            }
         }
 
         if ui.add(Button::new("Create object in your position")).clicked() {
@@ -0,0 +1,178 @@
+// Optimizer, looks for the values of some properties of the objects that bring what a
+// probe measures as close as possible to what we want, nelder-mead since all we have are
+// values of the objective and no derivatives, works on a copy of the world and the
+// result is applied to the lab only when asked to
+use std::fmt::{self, Display, Formatter};
+
+use nalgebra::Vector3;
+
+use crate::{poincare::sphere_point, polarimetry::stokes_at, sweep::{follow_alignments, SweepParameter}, world::{LightPolarizationType, World, WorldObject}};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Objective {
+    MaximizeIntensity,
+    MinimizeIntensity,
+    // either handedness
+    Circular,
+    // the state of Optimization.target_polarization
+    TargetPolarization
+}
+
+// Needed for the drop down list
+impl Display for Objective {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MaximizeIntensity => write!(f, "Maximize the intensity"),
+            Self::MinimizeIntensity => write!(f, "Minimize the intensity"),
+            Self::Circular => write!(f, "Circular polarization"),
+            Self::TargetPolarization => write!(f, "Reach a polarization"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct OptimizationVariable {
+    pub object_index: usize,
+    pub parameter: SweepParameter,
+    // the optimizer never leaves this range
+    pub min: f32,
+    pub max: f32
+}
+
+// best point found so far after every iteration
+#[derive(PartialEq, Debug, Clone)]
+pub struct Iteration {
+    pub values: Vec<f32>,
+    pub objective: f32
+}
+
+// minimum of `f` starting from a simplex with one vertex at `start` and the others `steps`
+// away along every axis, the usual coefficients (reflection 1, expansion 2, contraction
+// and shrinking 1/2), stops when the values of the simplex are all about the same
+pub fn nelder_mead(mut f: impl FnMut(&[f32]) -> f32, start: &[f32], steps: &[f32], max_iterations: usize) -> Vec<Iteration> {
+    let dimensions = start.len();
+
+    let mut simplex: Vec<(Vec<f32>, f32)> = (0..=dimensions).map(|i| {
+        let mut vertex = start.to_vec();
+        if i > 0 {
+            vertex[i - 1] += steps[i - 1];
+        }
+
+        let value = f(&vertex);
+        (vertex, value)
+    }).collect();
+
+    let mut iterations = vec![];
+    let towards = |from: &[f32], to: &[f32], t: f32| -> Vec<f32> { from.iter().zip(to).map(|(a, b)| a + (b - a) * t).collect() };
+
+    for _ in 0..max_iterations {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        iterations.push(Iteration { values: simplex[0].0.clone(), objective: simplex[0].1 });
+
+        let (best, worst) = (simplex[0].1, simplex[dimensions].1);
+        if (worst - best).abs() <= 1e-6 * best.abs().max(1e-12) || dimensions == 0 {
+            break;
+        }
+
+        let centroid: Vec<f32> = (0..dimensions).map(|axis| simplex[..dimensions].iter().map(|(vertex, _)| vertex[axis]).sum::<f32>() / dimensions as f32).collect();
+        let worst_vertex = simplex[dimensions].0.clone();
+
+        let reflected = towards(&worst_vertex, &centroid, 2.0);
+        let reflected_value = f(&reflected);
+
+        if reflected_value < best {
+            let expanded = towards(&worst_vertex, &centroid, 3.0);
+            let expanded_value = f(&expanded);
+
+            simplex[dimensions] = if expanded_value < reflected_value { (expanded, expanded_value) } else { (reflected, reflected_value) };
+        } else if reflected_value < simplex[dimensions - 1].1 {
+            simplex[dimensions] = (reflected, reflected_value);
+        } else {
+            // outside contraction if the reflection was better than the worst, inside if not
+            let contracted = if reflected_value < worst { towards(&centroid, &reflected, 0.5) } else { towards(&centroid, &worst_vertex, 0.5) };
+            let contracted_value = f(&contracted);
+
+            if contracted_value < worst.min(reflected_value) {
+                simplex[dimensions] = (contracted, contracted_value);
+            } else {
+                let best_vertex = simplex[0].0.clone();
+
+                for (vertex, value) in simplex.iter_mut().skip(1) {
+                    *vertex = towards(&best_vertex, vertex, 0.5);
+                    *value = f(vertex);
+                }
+            }
+        }
+    }
+
+    iterations
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Optimization {
+    pub variables: Vec<OptimizationVariable>,
+    pub probe_index: usize,
+    pub objective: Objective,
+    pub target_polarization: LightPolarizationType,
+    pub max_iterations: usize
+}
+
+impl Optimization {
+    // what gets minimized, from the stokes parameters at the probe, the polarization
+    // objectives go from 0 (reached) to 1 (opposite state or no light)
+    pub fn objective_value(&self, world: &World, cube_scaling_factor: f32) -> f32 {
+        let probe = world.objects[self.probe_index];
+        let stokes = stokes_at(world, Vector3::from(probe.center), cube_scaling_factor, Some(self.probe_index));
+
+        match self.objective {
+            Objective::MaximizeIntensity => -stokes[0],
+            Objective::MinimizeIntensity => stokes[0],
+            Objective::Circular if stokes[0] > 0.0 => 1.0 - (stokes[3] / stokes[0]).abs(),
+            Objective::TargetPolarization if stokes[0] > 0.0 => {
+                let mut target = WorldObject { polarization_type: self.target_polarization, ..WorldObject::new() };
+                target.set_light_polarization();
+
+                let reached = Vector3::new(stokes[1], stokes[2], stokes[3]) / stokes[0];
+                let wanted = sphere_point(target.polarization).unwrap_or(Vector3::zeros());
+
+                (reached - wanted).norm() / 2.0
+            },
+            _ => 1.0,
+        }
+    }
+
+    pub fn current_values(&self, world: &World, cube_scaling_factor: f32) -> Vec<f32> {
+        self.variables.iter().map(|variable| variable.parameter.value(world, variable.object_index, cube_scaling_factor).clamp(variable.min, variable.max)).collect()
+    }
+
+    // sets the values (kept inside the range of every variable) on `world`, and on the
+    // cube size for the variables that are the cube size
+    pub fn apply(&self, world: &mut World, values: &[f32], cube_scaling_factor: &mut f32) {
+        for (variable, value) in self.variables.iter().zip(values) {
+            variable.parameter.set(world, variable.object_index, value.clamp(variable.min, variable.max), cube_scaling_factor);
+        }
+    }
+
+    // starts from the values the objects have now
+    pub fn run(&self, world: &World, cube_scaling_factor: f32) -> Vec<Iteration> {
+        let mut world = world.clone();
+        let mut cube_scaling_factor = cube_scaling_factor;
+        let start = self.current_values(&world, cube_scaling_factor);
+        let steps: Vec<f32> = self.variables.iter().map(|variable| (variable.max - variable.min) * 0.1).collect();
+
+        let mut iterations = nelder_mead(|values| {
+            self.apply(&mut world, values, &mut cube_scaling_factor);
+            follow_alignments(&mut world);
+            self.objective_value(&world, cube_scaling_factor)
+        }, &start, &steps, self.max_iterations);
+
+        // the simplex can wander off the ranges, what would really be set is clamped
+        for iteration in iterations.iter_mut() {
+            for (value, variable) in iteration.values.iter_mut().zip(&self.variables) {
+                *value = value.clamp(variable.min, variable.max);
+            }
+        }
+
+        iterations
+    }
+}
@@ -0,0 +1,146 @@
+// The poincaré sphere, every pure polarization state is a point (s1, s2, s3) of the unit
+// sphere of normalized stokes parameters and every retarder is a rotation of the sphere
+// around an axis, these are the computations behind the widget in the inspector of lights
+use std::f32::consts::PI;
+
+use nalgebra::{Complex, Matrix2, Vector2, Vector3};
+
+use crate::{coatings::coating_jones_matrix, field::{disk_crossing, jones_matrix_for_light, light_direction}, polarimetry::stokes_parameters, spatial::element_at, world::{ObjectType, World}};
+
+// what an element along the beam does on the sphere, from the state before it to the
+// state after it
+#[derive(Debug, Clone)]
+pub struct TrajectoryStep {
+    pub element_index: usize,
+    // the way from one state to the other, starts and ends at them
+    pub points: Vec<Vector3<f32>>,
+    // rotation axis and angle (radians) for retarders, None for anything that absorbs
+    pub rotation: Option<(Vector3<f32>, f32)>
+}
+
+// normalized stokes vector of a jones vector, None if there's no light
+pub fn sphere_point(jones_vector: Vector2<Complex<f32>>) -> Option<Vector3<f32>> {
+    let [s0, s1, s2, s3] = stokes_parameters(jones_vector);
+    if s0 <= 1e-12 {
+        return None;
+    }
+
+    Some(Vector3::new(s1, s2, s3) / s0)
+}
+
+// unit jones vector of a point of the sphere, azimuth ψ and ellipticity χ of the ellipse
+// come from 2ψ = atan2(s2, s1) and 2χ = asin(s3)
+pub fn jones_vector_at(point: Vector3<f32>) -> Vector2<Complex<f32>> {
+    let point = point.normalize();
+    let azimuth = 0.5 * point.y.atan2(point.x);
+    let ellipticity = 0.5 * point.z.clamp(-1.0, 1.0).asin();
+
+    let (sin_a, cos_a) = azimuth.sin_cos();
+    let (sin_e, cos_e) = ellipticity.sin_cos();
+
+    Vector2::new(Complex::new(cos_a * cos_e, sin_a * sin_e), Complex::new(sin_a * cos_e, -cos_a * sin_e))
+}
+
+// the matrices that measure s1, s2 and s3 (ψ† Σk ψ) with the conventions of stokes_parameters
+fn stokes_matrices() -> [Matrix2<Complex<f32>>; 3] {
+    let zero = Complex::new(0.0, 0.0);
+    let one = Complex::new(1.0, 0.0);
+    let i = Complex::new(0.0, 1.0);
+
+    [
+        Matrix2::new(one, zero, zero, -one),
+        Matrix2::new(zero, one, one, zero),
+        Matrix2::new(zero, i, -i, zero)
+    ]
+}
+
+// axis and angle of the rotation of the sphere of an element, only if it's a retarder
+// (unitary up to a constant), the angle is the retardance, kept under π by flipping the axis
+pub fn rotation_on_sphere(jones_matrix: Matrix2<Complex<f32>>) -> Option<(Vector3<f32>, f32)> {
+    let product = jones_matrix.adjoint() * jones_matrix;
+    let scale = product[(0, 0)].re;
+
+    if scale <= 1e-12 || (product - Matrix2::identity() * Complex::new(scale, 0.0)).norm() > 1e-3 * scale {
+        return None;
+    }
+
+    // U = cos(δ/2) I - i sin(δ/2) (n · Σ) once the global phase is gone
+    let mut unitary = jones_matrix / jones_matrix.determinant().sqrt();
+    if unitary.trace().re < 0.0 {
+        unitary = -unitary;
+    }
+
+    let axis = Vector3::from(stokes_matrices().map(|matrix| (Complex::new(0.0, 1.0) * (unitary * matrix).trace() / 2.0).re));
+    let half_angle = axis.norm().atan2(unitary.trace().re / 2.0);
+
+    if axis.norm() < 1e-5 {
+        return None;
+    }
+
+    Some((axis.normalize(), 2.0 * half_angle))
+}
+
+// a fraction `t` of a rotation of the sphere as a jones matrix
+fn partial_rotation(axis: Vector3<f32>, angle: f32, t: f32) -> Matrix2<Complex<f32>> {
+    let [sigma_1, sigma_2, sigma_3] = stokes_matrices();
+    let generator = sigma_1 * Complex::new(axis.x, 0.0) + sigma_2 * Complex::new(axis.y, 0.0) + sigma_3 * Complex::new(axis.z, 0.0);
+    let (sin, cos) = (t * angle / 2.0).sin_cos();
+
+    Matrix2::identity() * Complex::new(cos, 0.0) - generator * Complex::new(0.0, sin)
+}
+
+// the shortest way between two points of the sphere
+fn great_circle(from: Vector3<f32>, to: Vector3<f32>, samples: usize) -> Vec<Vector3<f32>> {
+    let angle = from.dot(&to).clamp(-1.0, 1.0).acos();
+
+    (0..=samples).map(|i| {
+        let t = i as f32 / samples as f32;
+        if angle < 1e-4 || (PI - angle) < 1e-4 {
+            return (from * (1.0 - t) + to * t).try_normalize(1e-6).unwrap_or(from);
+        }
+
+        (from * ((1.0 - t) * angle).sin() + to * (t * angle).sin()) / angle.sin()
+    }).collect()
+}
+
+// states of the light of `light_index` along the axis of its beam, one step for every
+// optical object it goes through until something blocks it completely
+pub fn beam_trajectory(world: &World, light_index: usize, samples: usize) -> Vec<TrajectoryStep> {
+    let light = world.objects[light_index];
+    let direction = light_direction(&light);
+    let origin = Vector3::from(light.center);
+    let medium_index = world.medium_refractive_index(light.material_wavelength);
+
+    let mut elements: Vec<(f32, usize)> = world.objects_associations.keys()
+        .filter(|index| world.objects[**index].object_type == ObjectType::OpticalObjectRoundWall)
+        .filter_map(|index| disk_crossing(&world.objects[*index], origin, direction).map(|distance| (distance, *index)))
+        .collect();
+    elements.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut state = light.polarization;
+    let mut steps = vec![];
+
+    for (distance, index) in elements {
+        let Some(before) = sphere_point(state) else { break };
+
+        let object = world.objects[index];
+        let element = element_at(&object, origin + direction * distance, world.element_maps.get(&index));
+        let jones_matrix = jones_matrix_for_light(&element, &light, direction) * coating_jones_matrix(&object, &light, direction, &world.materials, medium_index);
+
+        let rotation = rotation_on_sphere(jones_matrix);
+        state = jones_matrix * state;
+
+        let Some(after) = sphere_point(state) else { break };
+
+        let points = match rotation {
+            Some((axis, angle)) => (0..=samples)
+                .filter_map(|i| sphere_point(partial_rotation(axis, angle, i as f32 / samples as f32) * jones_vector_at(before)))
+                .collect(),
+            None => great_circle(before, after, samples)
+        };
+
+        steps.push(TrajectoryStep { element_index: index, points, rotation });
+    }
+
+    steps
+}
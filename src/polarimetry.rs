@@ -0,0 +1,107 @@
+// What a polarimeter would measure from the field at a point, the stokes parameters and
+// the polarization ellipse they describe, the field comes from the cpu side mirror of
+// main.frag in field.rs, fields of lights with the same wavelength add up before squaring
+// and different wavelengths add up as intensities just like in main.frag
+use std::fmt::{self, Display, Formatter};
+
+use nalgebra::{Complex, Vector2, Vector3};
+
+use crate::{field::light_fields_at, world::World};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Handedness {
+    Right,
+    Left,
+    // linear light
+    None
+}
+
+impl Display for Handedness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Right => write!(f, "Right"),
+            Self::Left => write!(f, "Left"),
+            Self::None => write!(f, "None (linear)"),
+        }
+    }
+}
+
+// [S0, S1, S2, S3] of a jones vector, right circular light (1, -i) / √2 has S3 = 1
+pub fn stokes_parameters(field: Vector2<Complex<f32>>) -> [f32; 4] {
+    let (ex, ey) = (field[0], field[1]);
+    let cross = ex * ey.conj();
+
+    [ex.norm_sqr() + ey.norm_sqr(), ex.norm_sqr() - ey.norm_sqr(), 2.0 * cross.re, 2.0 * cross.im]
+}
+
+pub fn degree_of_polarization(stokes: [f32; 4]) -> f32 {
+    if stokes[0] <= 0.0 {
+        return 0.0;
+    }
+
+    (stokes[1].powi(2) + stokes[2].powi(2) + stokes[3].powi(2)).sqrt() / stokes[0]
+}
+
+// angle of the major axis of the ellipse from the horizontal axis, ψ = atan2(S2, S1) / 2
+pub fn ellipse_azimuth(stokes: [f32; 4]) -> f32 {
+    0.5 * stokes[2].atan2(stokes[1])
+}
+
+// χ = asin(S3 / |S|) / 2, from -π/4 (left circular) to π/4 (right circular), the
+// ratio between the axes of the ellipse is |tan χ|
+pub fn ellipticity_angle(stokes: [f32; 4]) -> f32 {
+    let polarized = (stokes[1].powi(2) + stokes[2].powi(2) + stokes[3].powi(2)).sqrt();
+    if polarized <= 0.0 {
+        return 0.0;
+    }
+
+    0.5 * (stokes[3] / polarized).clamp(-1.0, 1.0).asin()
+}
+
+pub fn handedness(stokes: [f32; 4]) -> Handedness {
+    let polarized = (stokes[1].powi(2) + stokes[2].powi(2) + stokes[3].powi(2)).sqrt();
+
+    if stokes[3].abs() <= 1e-4 * polarized {
+        Handedness::None
+    } else if stokes[3] > 0.0 {
+        Handedness::Right
+    } else {
+        Handedness::Left
+    }
+}
+
+// the real field Re(E e^(-iωt)) at a phase ωt of the cycle, over a whole cycle it traces
+// the polarization ellipse
+pub fn instantaneous_field(field: Vector2<Complex<f32>>, phase: f32) -> Vector2<f32> {
+    let rotation = Complex::new(0.0, -phase).exp();
+
+    field.map(|component| (component * rotation).re)
+}
+
+// the field at `point` added up for every wavelength, (wavelength, jones vector)
+pub fn fields_by_wavelength(world: &World, point: Vector3<f32>, cube_scaling_factor: f32, exclude: Option<usize>) -> Vec<(f32, Vector2<Complex<f32>>)> {
+    let mut by_wavelength: Vec<(f32, Vector2<Complex<f32>>)> = vec![];
+
+    for (light_index, field) in light_fields_at(world, point, cube_scaling_factor, exclude) {
+        let wavelength = world.objects[light_index].wavelength;
+
+        match by_wavelength.iter_mut().find(|(other, _)| *other == wavelength) {
+            Some((_, total)) => *total += field,
+            None => by_wavelength.push((wavelength, field))
+        }
+    }
+
+    by_wavelength
+}
+
+// stokes parameters of the light of every light source at `point`
+pub fn stokes_at(world: &World, point: Vector3<f32>, cube_scaling_factor: f32, exclude: Option<usize>) -> [f32; 4] {
+    total_stokes(&fields_by_wavelength(world, point, cube_scaling_factor, exclude))
+}
+
+pub fn total_stokes(fields: &[(f32, Vector2<Complex<f32>>)]) -> [f32; 4] {
+    fields.iter().fold([0.0; 4], |total, (_, field)| {
+        let stokes = stokes_parameters(*field);
+        [0, 1, 2, 3].map(|i| total[i] + stokes[i])
+    })
+}
@@ -0,0 +1,187 @@
+// Triangular prisms, light through both refracting faces leaves as a new gaussian beam
+use nalgebra::{Complex, Matrix2, Vector3};
+
+use crate::{coatings::{reversed_layers, stack_response}, field::{light_direction, light_transverse_axes}, world::WorldObject};
+
+// planes that bound the prism, the first ones are the refracting faces, the apex edge goes
+// along the vertical axis and the apex points towards the normal
+pub const PRISM_PLANES: usize = 5;
+pub const PRISM_REFRACTING_FACES: usize = 2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PrismBeam {
+    // where the axis of the light goes in and out of the prism
+    pub entry: Vector3<f32>,
+    pub exit: Vector3<f32>,
+    pub internal_direction: Vector3<f32>,
+    pub direction: Vector3<f32>,
+    // where the waist of the outgoing beam would be if it had always travelled
+    // in the medium, keeps the phase continuous across the prism
+    pub waist: Vector3<f32>,
+    // angle between the incoming and the outgoing light, in radians
+    pub deviation: f32,
+    // outward normal of the face the light leaves through
+    pub exit_normal: Vector3<f32>,
+    // power transmitted through both faces by s (along the apex edge) and p light
+    pub transmittance: [f32; 2],
+    // jones matrix of both faces (fresnel transmission or the one of their coating)
+    // in the basis of the light
+    pub jones_matrix: Matrix2<Complex<f32>>
+}
+
+// outward normals and offsets of the planes that bound the prism, a point p is
+// inside if dot(normal, p - center) <= offset for all of them, the faces are
+// 2 * radius long and so is the apex edge
+pub fn prism_planes(prism: &WorldObject) -> [(Vector3<f32>, f32); PRISM_PLANES] {
+    let [horizontal, normal, vertical] = prism.local_axes();
+    let (sin, cos) = (prism.apex_angle / 2.0).sin_cos();
+    let face_length = prism.radius * 2.0;
+    let half_height = face_length * cos / 2.0;
+
+    [
+        // refracting faces, the apex is at half_height along the normal
+        (-horizontal * cos + normal * sin, sin * half_height),
+        (horizontal * cos + normal * sin, sin * half_height),
+        // base
+        (-normal, half_height),
+        // ends
+        (vertical, prism.radius),
+        (-vertical, prism.radius)
+    ]
+}
+
+// distance to the furthest corner of the prism from its center
+pub fn prism_bounding_radius(prism: &WorldObject) -> f32 {
+    let (sin, cos) = (prism.apex_angle / 2.0).sin_cos();
+    let face_length = prism.radius * 2.0;
+
+    let half_base = face_length * sin;
+    let half_height = face_length * cos / 2.0;
+
+    // the corners of the base are always further away than the apex
+    half_base.hypot(half_height).hypot(prism.radius)
+}
+
+// distances along the ray where it goes in and out of the prism and the index of
+// the planes it goes through, None if it misses it
+pub fn prism_intersection(prism: &WorldObject, origin: Vector3<f32>, direction: Vector3<f32>) -> Option<(f32, f32, usize, usize)> {
+    planes_intersection(&prism_planes(prism), Vector3::from(prism.center), origin, direction)
+}
+
+// same as prism_intersection but for any convex object bounded by `planes`
+// (outward normals and offsets from `center`)
+pub fn planes_intersection(planes: &[(Vector3<f32>, f32)], center: Vector3<f32>, origin: Vector3<f32>, direction: Vector3<f32>) -> Option<(f32, f32, usize, usize)> {
+    let mut near = (f32::NEG_INFINITY, 0);
+    let mut far = (f32::INFINITY, 0);
+
+    for (plane, (normal, offset)) in planes.iter().enumerate() {
+        let denominator = normal.dot(&direction);
+        let distance = offset - normal.dot(&(origin - center));
+
+        if denominator.abs() < 1e-6 {
+            if distance < 0.0 {
+                return None;
+            }
+            continue;
+        }
+
+        let t = distance / denominator;
+
+        if denominator < 0.0 && t > near.0 {
+            near = (t, plane);
+        } else if denominator > 0.0 && t < far.0 {
+            far = (t, plane);
+        }
+    }
+
+    if near.0 > far.0 || far.0 <= 0.0 {
+        return None;
+    }
+
+    Some((near.0, far.0, near.1, far.1))
+}
+
+// same as glsl's refract, `normal` points against `incident`, eta is n1 / n2
+pub fn refract(incident: Vector3<f32>, normal: Vector3<f32>, eta: f32) -> Option<Vector3<f32>> {
+    let cos_incidence = normal.dot(&incident);
+    let k = 1.0 - eta.powi(2) * (1.0 - cos_incidence.powi(2));
+
+    if k < 0.0 {
+        return None;
+    }
+
+    Some(incident * eta - normal * (eta * cos_incidence + k.sqrt()))
+}
+
+// follows the axis of `light` through the prism, None if it misses the refracting
+// faces or gets totally reflected inside, the indices are the ones of the prism
+// and the medium at the wavelength of the light and `coating` has the refractive
+// index and thickness of the layers of the coating of both faces (see coatings.rs)
+pub fn prism_beam(prism: &WorldObject, light: &WorldObject, prism_index: f32, medium_index: f32, coating: &[(f32, f32)]) -> Option<PrismBeam> {
+    let planes = prism_planes(prism);
+    let center = Vector3::from(prism.center);
+    let light_center = Vector3::from(light.center);
+    let incident = light_direction(light);
+
+    let (t_in, _, entry_plane, _) = prism_intersection(prism, light_center, incident)?;
+    if t_in <= 0.0 || entry_plane >= PRISM_REFRACTING_FACES {
+        return None;
+    }
+
+    let entry = light_center + incident * t_in;
+    let entry_normal = planes[entry_plane].0;
+    let internal_direction = refract(incident, entry_normal, medium_index / prism_index)?;
+
+    // first plane the light reaches from the inside
+    let (inside_distance, exit_plane) = planes.iter().enumerate()
+        .filter(|(_, (normal, _))| normal.dot(&internal_direction) > 1e-6)
+        .map(|(plane, (normal, offset))| ((offset - normal.dot(&(entry - center))) / normal.dot(&internal_direction), plane))
+        .fold((f32::INFINITY, entry_plane), |closest, current| if current.0 < closest.0 { current } else { closest });
+
+    if exit_plane >= PRISM_REFRACTING_FACES || exit_plane == entry_plane {
+        return None;
+    }
+
+    let exit = entry + internal_direction * inside_distance;
+    let exit_normal = planes[exit_plane].0;
+    let direction = refract(internal_direction, -exit_normal, prism_index / medium_index)?;
+
+    // the coating faces outwards, so the light sees it backwards when it leaves
+    let [ts_in, tp_in] = stack_response(coating, medium_index, prism_index, -incident.dot(&entry_normal), light.material_wavelength).transmission;
+    let [ts_out, tp_out] = stack_response(&reversed_layers(coating), prism_index, medium_index, internal_direction.dot(&exit_normal), light.material_wavelength).transmission;
+
+    // s light is polarized along the apex edge, exact for light travelling in the
+    // plane of the cross section, which is how prisms are normally used
+    let [_, _, apex_edge] = prism.local_axes();
+    let [light_x, light_y] = light_transverse_axes(light);
+    let angle = apex_edge.dot(&light_y).atan2(apex_edge.dot(&light_x));
+    let (sin, cos) = angle.sin_cos();
+
+    let rotation = Matrix2::new(cos, -sin, sin, cos).map(|x| Complex::new(x, 0.0));
+    let transmission = Matrix2::new(ts_in * ts_out, Complex::new(0.0, 0.0), Complex::new(0.0, 0.0), tp_in * tp_out);
+
+    let optical_distance = t_in + inside_distance * prism_index / medium_index;
+
+    Some(PrismBeam {
+        entry,
+        exit,
+        internal_direction,
+        direction,
+        waist: exit - direction * optical_distance,
+        deviation: incident.dot(&direction).clamp(-1.0, 1.0).acos(),
+        exit_normal,
+        transmittance: [(ts_in * ts_out).norm_sqr(), (tp_in * tp_out).norm_sqr()],
+        jones_matrix: rotation * transmission * rotation.transpose()
+    })
+}
+
+// deviation of a symmetric pass through the prism, in radians
+pub fn minimum_deviation(prism: &WorldObject, prism_index: f32, medium_index: f32) -> Option<f32> {
+    let sin_incidence = prism_index / medium_index * (prism.apex_angle / 2.0).sin();
+
+    if sin_incidence > 1.0 {
+        return None;
+    }
+
+    Some(2.0 * sin_incidence.asin() - prism.apex_angle)
+}
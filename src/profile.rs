@@ -0,0 +1,58 @@
+// Line profiles, the light along a segment picked on a wall or a screen of the lab,
+// evaluated with the cpu side mirror of main.frag in field.rs
+use nalgebra::Vector3;
+
+use crate::{polarimetry::{fields_by_wavelength, total_stokes}, world::World};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileSample {
+    // from the start of the segment, in meters
+    pub distance: f32,
+    pub stokes: [f32; 4],
+    // phases of Ex and Ey of the first wavelength that gets there
+    pub phase: Option<[f32; 2]>
+}
+
+// `samples` points evenly spaced from `start` to `end` (both included) on the object
+// `object_index`, which doesn't block the light that lands on it
+pub fn sample_profile(world: &World, object_index: usize, start: Vector3<f32>, end: Vector3<f32>, samples: usize, cube_scaling_factor: f32) -> Vec<ProfileSample> {
+    let samples = samples.max(2);
+    let length = (end - start).norm() * cube_scaling_factor;
+
+    (0..samples).map(|i| {
+        let t = i as f32 / (samples - 1) as f32;
+        let fields = fields_by_wavelength(world, start + (end - start) * t, cube_scaling_factor, Some(object_index));
+
+        ProfileSample {
+            distance: length * t,
+            stokes: total_stokes(&fields),
+            phase: fields.first().map(|(_, field)| [field[0].arg(), field[1].arg()])
+        }
+    }).collect()
+}
+
+pub fn profile_csv(samples: &[ProfileSample], phase: bool, stokes: bool) -> String {
+    let mut csv = "distance (m),intensity".to_string();
+    if phase {
+        csv += ",phase Ex (rad),phase Ey (rad)";
+    }
+    if stokes {
+        csv += ",S1,S2,S3";
+    }
+    csv += "\n";
+
+    for sample in samples {
+        csv += &format!("{},{}", sample.distance, sample.stokes[0]);
+
+        if phase {
+            let [x, y] = sample.phase.unwrap_or([0.0, 0.0]);
+            csv += &format!(",{},{}", x, y);
+        }
+        if stokes {
+            csv += &format!(",{},{},{}", sample.stokes[1], sample.stokes[2], sample.stokes[3]);
+        }
+        csv += "\n";
+    }
+
+    csv
+}
@@ -0,0 +1,211 @@
+// False color views of the lab, instead of the realistic intensity main.frag can paint the
+// walls with a map of the intensity, the phase of a component of the field, the stokes
+// parameters, the degree of polarization, the polarization ellipse or the real field at the
+// current time slowed down enough to watch it oscillate, these are the cpu side versions of
+// its colors for the legends of the main menu
+use std::f32::consts::PI;
+use std::fmt::{self, Display, Formatter};
+
+use nalgebra::{Complex, Vector2};
+
+use crate::{cross_section::hue_color, polarimetry::{degree_of_polarization, ellipse_azimuth, ellipticity_angle, instantaneous_field, total_stokes}};
+
+// must match the constants in main.frag
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum RenderMode {
+    Realistic = 0,
+    Intensity = 1,
+    PhaseEx = 2,
+    PhaseEy = 3,
+    S1 = 4,
+    S2 = 5,
+    S3 = 6,
+    DegreeOfPolarization = 7,
+    AzimuthEllipticity = 8,
+    FieldEx = 9,
+    FieldEy = 10
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 11] = [
+        RenderMode::Realistic, RenderMode::Intensity, RenderMode::PhaseEx, RenderMode::PhaseEy,
+        RenderMode::S1, RenderMode::S2, RenderMode::S3, RenderMode::DegreeOfPolarization, RenderMode::AzimuthEllipticity,
+        RenderMode::FieldEx, RenderMode::FieldEy
+    ];
+
+    pub fn uses_colormap(&self) -> bool {
+        *self == RenderMode::Intensity || *self == RenderMode::DegreeOfPolarization
+    }
+
+    pub fn is_time_resolved(&self) -> bool {
+        *self == RenderMode::FieldEx || *self == RenderMode::FieldEy
+    }
+}
+
+// Needed for the drop down list
+impl Display for RenderMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Realistic => write!(f, "Realistic"),
+            Self::Intensity => write!(f, "Intensity"),
+            Self::PhaseEx => write!(f, "Phase of Ex"),
+            Self::PhaseEy => write!(f, "Phase of Ey"),
+            Self::S1 => write!(f, "S1 (horizontal - vertical)"),
+            Self::S2 => write!(f, "S2 (diagonal - anti-diagonal)"),
+            Self::S3 => write!(f, "S3 (right - left circular)"),
+            Self::DegreeOfPolarization => write!(f, "Degree of polarization"),
+            Self::AzimuthEllipticity => write!(f, "Azimuth and ellipticity"),
+            Self::FieldEx => write!(f, "Ex at the current time"),
+            Self::FieldEy => write!(f, "Ey at the current time"),
+        }
+    }
+}
+
+// must match the constants in main.frag
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Colormap {
+    Grayscale = 0,
+    Inferno = 1,
+    Viridis = 2,
+    Jet = 3
+}
+
+// Needed for the drop down list
+impl Display for Colormap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Grayscale => write!(f, "Grayscale"),
+            Self::Inferno => write!(f, "Inferno"),
+            Self::Viridis => write!(f, "Viridis"),
+            Self::Jet => write!(f, "Jet"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct RenderSettings {
+    pub mode: RenderMode,
+    pub colormap: Colormap,
+    // intensity that shows up as full brightness is 1 / exposure
+    pub exposure: f32,
+    // cycles of the field of the first light per time unit, the slow motion of the
+    // time-resolved modes
+    pub field_speed: f32,
+    pub field_paused: bool,
+    // ωt of the first light is 2π field_time
+    pub field_time: f32
+}
+
+impl RenderSettings {
+    pub fn new() -> RenderSettings {
+        RenderSettings { mode: RenderMode::Realistic, colormap: Colormap::Inferno, exposure: 0.25, field_speed: 1.0, field_paused: false, field_time: 0.0 }
+    }
+
+    // called every frame with the time that went by
+    pub fn advance(&mut self, time_step: f32) {
+        if !self.field_paused {
+            self.field_time += self.field_speed * time_step;
+        }
+    }
+}
+
+// polynomial fits of matplotlib's colormaps, same coefficients as main.frag
+fn polynomial_colormap(coefficients: [[f32; 3]; 7], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|channel| coefficients.iter().rev().fold(0.0, |total, c| total * t + c[channel]).clamp(0.0, 1.0))
+}
+
+// colormap_color in main.frag, `t` goes from 0 to 1
+pub fn colormap_color(colormap: Colormap, t: f32) -> [f32; 3] {
+    let t = t.clamp(0.0, 1.0);
+
+    match colormap {
+        Colormap::Grayscale => [t; 3],
+        Colormap::Inferno => polynomial_colormap([
+            [0.000_218_94, 0.001_651, -0.019_480_9],
+            [0.106_513_42, 0.563_956_4, 3.932_712_4],
+            [11.602_493, -3.972_854, -15.942_394],
+            [-41.703_996, 17.436_4, 44.354_145],
+            [77.162_94, -33.402_36, -81.807_31],
+            [-71.319_43, 32.626_064, 73.209_52],
+            [25.131_126, -12.242_669, -23.070_325]
+        ], t),
+        Colormap::Viridis => polynomial_colormap([
+            [0.277_727_33, 0.005_407_344_5, 0.334_099_8],
+            [0.105_093_04, 1.404_613_5, 1.384_590_2],
+            [-0.330_861_83, 0.214_847_56, 0.095_095_16],
+            [-4.634_230_5, -5.799_101, -19.332_441],
+            [6.228_27, 14.179_933, 56.690_55],
+            [4.776_385, -13.745_145, -65.353_03],
+            [-5.435_456, 4.645_852_6, 26.312_435]
+        ], t),
+        Colormap::Jet => [3.0, 2.0, 1.0].map(|center| (1.5 - (4.0 * t - center).abs()).clamp(0.0, 1.0))
+    }
+}
+
+// blue for -1, white for 0 and red for 1, diverging_color in main.frag
+pub fn diverging_color(value: f32) -> [f32; 3] {
+    let value = value.clamp(-1.0, 1.0);
+
+    if value >= 0.0 {
+        [1.0, 1.0 - value, 1.0 - value]
+    } else {
+        [1.0 + value, 1.0 + value, 1.0]
+    }
+}
+
+// hue is the azimuth of the ellipse, going around once from 0° to 180° so horizontal light
+// is red either way, linear light is fully saturated and circular light is white
+pub fn ellipse_color(azimuth: f32, ellipticity: f32, value: f32) -> [f32; 3] {
+    let saturation = 1.0 - (ellipticity.abs() / (PI / 4.0)).clamp(0.0, 1.0);
+
+    hue_color((azimuth / PI).rem_euclid(1.0), 1.0).map(|channel| value * (1.0 - saturation + saturation * channel))
+}
+
+// red where the field points along its axis, blue where it points against it and black
+// where it's zero, signed_color in main.frag
+pub fn signed_color(value: f32) -> [f32; 3] {
+    let value = value.clamp(-1.0, 1.0);
+
+    [value.max(0.0), 0.0, (-value).max(0.0)]
+}
+
+// Re(E e^(-iωt)) added up for every wavelength, the first one goes through 2π every unit of
+// `field_time` and the others as many times faster as their frequency is higher, which is
+// what makes lights of different wavelengths beat
+pub fn summed_instantaneous_field(fields: &[(f32, Vector2<Complex<f32>>)], field_time: f32) -> Vector2<f32> {
+    let Some((reference, _)) = fields.first() else { return Vector2::zeros() };
+
+    fields.iter()
+        .map(|(wavelength, field)| instantaneous_field(*field, 2.0 * PI * field_time * reference / wavelength))
+        .sum()
+}
+
+// false_color in main.frag, `fields` is the field of every wavelength that reaches the
+// point like fields_by_wavelength gives them, the phases are the ones of the first one
+pub fn render_color(settings: &RenderSettings, fields: &[(f32, Vector2<Complex<f32>>)]) -> [f32; 3] {
+    let stokes = total_stokes(fields);
+    let field = fields.first().map_or(Vector2::zeros(), |(_, field)| *field);
+
+    let brightness = (stokes[0] * settings.exposure).clamp(0.0, 1.0);
+    let phase_color = |component: Complex<f32>| hue_color((component.arg() + PI) / (2.0 * PI), (component.norm_sqr() * settings.exposure).clamp(0.0, 1.0));
+
+    if stokes[0] <= 0.0 && settings.mode != RenderMode::Intensity && !settings.mode.is_time_resolved() {
+        return [0.0; 3];
+    }
+
+    match settings.mode {
+        RenderMode::Realistic => [brightness; 3],
+        RenderMode::Intensity => colormap_color(settings.colormap, brightness),
+        RenderMode::PhaseEx => phase_color(field[0]),
+        RenderMode::PhaseEy => phase_color(field[1]),
+        RenderMode::S1 | RenderMode::S2 | RenderMode::S3 => {
+            let component = settings.mode as usize - RenderMode::S1 as usize + 1;
+            diverging_color(stokes[component] / stokes[0]).map(|channel| brightness * channel)
+        }
+        RenderMode::DegreeOfPolarization => colormap_color(settings.colormap, degree_of_polarization(stokes)).map(|channel| brightness * channel),
+        RenderMode::AzimuthEllipticity => ellipse_color(ellipse_azimuth(stokes), ellipticity_angle(stokes), brightness),
+        // the amplitude of light as intense as 1 / exposure is full brightness
+        RenderMode::FieldEx => signed_color(summed_instantaneous_field(fields, settings.field_time).x * settings.exposure.sqrt()),
+        RenderMode::FieldEy => signed_color(summed_instantaneous_field(fields, settings.field_time).y * settings.exposure.sqrt())
+    }
+}
@@ -0,0 +1,225 @@
+// Spatially varying optical elements, q-plates, vortex retarders, image maps and photoelastic samples
+use std::fmt::{self, Display, Formatter};
+use std::f32::consts::PI;
+
+use image::imageops::{self, FilterType};
+use nalgebra::{Complex, Matrix2, Vector3};
+
+use crate::world::{retarder_jones_matrix, PolarizerType, StressField, WorldObject};
+
+// side of the maps in pixels, images get resized to it
+pub const MAP_RESOLUTION: usize = 128;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum MapChannel {
+    // black is no retardance and white a whole wave
+    Retardance = 0,
+    // black is a fast axis along the horizontal axis of the element and white
+    // half a turn from it
+    FastAxis = 1,
+    // amplitude transmittance, black blocks the light and white lets it all through
+    Amplitude = 2
+}
+
+// Needed for the drop down list
+impl Display for MapChannel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Retardance => write!(f, "Retardance"),
+            Self::FastAxis => write!(f, "Fast axis"),
+            Self::Amplitude => write!(f, "Amplitude"),
+        }
+    }
+}
+
+pub const MAP_CHANNELS: [MapChannel; 3] = [MapChannel::Retardance, MapChannel::FastAxis, MapChannel::Amplitude];
+
+// the maps of one element packed as rgba, one channel per MapChannel, from the
+// top left of the disk (along its vertical axis) just like the images
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementMap {
+    pub pixels: Vec<u8>,
+    // where every channel came from, None if the element doesn't use it
+    pub sources: [Option<String>; 3]
+}
+
+impl ElementMap {
+    pub fn new() -> ElementMap {
+        ElementMap {
+            pixels: vec![255; MAP_RESOLUTION * MAP_RESOLUTION * 4],
+            sources: [None, None, None]
+        }
+    }
+
+    pub fn uses(&self, channel: MapChannel) -> bool {
+        self.sources[channel as usize].is_some()
+    }
+
+    // bit i is set if the channel i is used, how main.frag knows which ones to read
+    pub fn channels_mask(&self) -> u32 {
+        MAP_CHANNELS.iter().filter(|channel| self.uses(**channel)).map(|channel| 1 << *channel as u32).sum()
+    }
+
+    // fills a channel with `value`, which gets the coordinates on the disk (from -1 to 1
+    // along the horizontal and the vertical axis) and returns something from 0 to 1
+    pub fn set_channel(&mut self, channel: MapChannel, source: &str, value: impl Fn(f32, f32) -> f32) {
+        for row in 0..MAP_RESOLUTION {
+            for column in 0..MAP_RESOLUTION {
+                let x = 2.0 * (column as f32 + 0.5) / MAP_RESOLUTION as f32 - 1.0;
+                let y = 1.0 - 2.0 * (row as f32 + 0.5) / MAP_RESOLUTION as f32;
+
+                self.pixels[(row * MAP_RESOLUTION + column) * 4 + channel as usize] = (value(x, y).clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+
+        self.sources[channel as usize] = Some(source.to_string());
+    }
+
+    // reads a png (or anything the image crate can read), only its brightness is used
+    pub fn set_channel_from_image(&mut self, channel: MapChannel, source: &str, bytes: &[u8]) -> Result<(), String> {
+        let image = image::load_from_memory(bytes).map_err(|e| format!("Couldn't read '{}': {}", source, e))?.to_luma8();
+        let image = imageops::resize(&image, MAP_RESOLUTION as u32, MAP_RESOLUTION as u32, FilterType::Triangle);
+
+        for (i, pixel) in image.pixels().enumerate() {
+            self.pixels[i * 4 + channel as usize] = pixel.0[0];
+        }
+
+        self.sources[channel as usize] = Some(source.to_string());
+        Ok(())
+    }
+
+    pub fn clear_channel(&mut self, channel: MapChannel) {
+        for pixel in self.pixels.chunks_mut(4) {
+            pixel[channel as usize] = 255;
+        }
+
+        self.sources[channel as usize] = None;
+    }
+
+    // value of the pixel under the point (x, y) of the disk, from 0 to 1
+    pub fn value(&self, channel: MapChannel, x: f32, y: f32) -> f32 {
+        let column = (((x + 1.0) / 2.0 * MAP_RESOLUTION as f32) as usize).min(MAP_RESOLUTION - 1);
+        let row = (((1.0 - y) / 2.0 * MAP_RESOLUTION as f32) as usize).min(MAP_RESOLUTION - 1);
+
+        self.pixels[(row * MAP_RESOLUTION + column) * 4 + channel as usize] as f32 / 255.0
+    }
+}
+
+pub fn is_spatially_varying(element: &WorldObject) -> bool {
+    matches!(element.polarizer_type, PolarizerType::QPlate | PolarizerType::VortexRetarder | PolarizerType::ImageElement | PolarizerType::PhotoelasticSample)
+}
+
+// coordinates of `point` on the disk of the element as main.frag draws it, from -1 to 1
+// along its horizontal and its vertical axis
+pub fn disk_coordinates(element: &WorldObject, point: Vector3<f32>) -> (f32, f32) {
+    let [horizontal, _, vertical] = element.local_axes();
+    let offset = point - Vector3::from(element.center);
+
+    (offset.dot(&horizontal) / element.drawn_radius(), offset.dot(&vertical) / element.drawn_radius())
+}
+
+// difference between the principal stresses (in pascals) at (x, y) of the disk of a
+// photoelastic sample and the angle of the first one from the horizontal axis, plane
+// stress with the sample filling the whole disk
+pub fn principal_stresses(sample: &WorldObject, x: f32, y: f32) -> (f32, f32) {
+    // in meters
+    let size = sample.sample_size / 1000.0;
+    let thickness = sample.sample_thickness / 1000.0;
+
+    let (normal_x, normal_y, shear) = match sample.stress_field {
+        // squeezed by two opposite forces along the vertical axis, the classic
+        // closed form for a disk of diameter D under diametral compression
+        StressField::DiskCompression => {
+            let radius = size / 2.0;
+            let (x, y) = (x * radius, y * radius);
+            let r1 = (x.powi(2) + (radius - y).powi(2)).powi(2).max(1e-12);
+            let r2 = (x.powi(2) + (radius + y).powi(2)).powi(2).max(1e-12);
+            let scale = 2.0 * sample.applied_load / (PI * thickness);
+
+            (
+                -scale * (x.powi(2) * (radius - y) / r1 + x.powi(2) * (radius + y) / r2 - 1.0 / size),
+                -scale * ((radius - y).powi(3) / r1 + (radius + y).powi(3) / r2 - 1.0 / size),
+                scale * (x * (radius - y).powi(2) / r1 - x * (radius + y).powi(2) / r2)
+            )
+        }
+
+        // pure bending of a beam of height h, σx = M y / I with I = t h³ / 12
+        StressField::BeamBending => {
+            let y = y * size / 2.0;
+            (12.0 * sample.applied_load * y / (thickness * size.powi(3)), 0.0, 0.0)
+        }
+    };
+
+    (
+        ((normal_x - normal_y).powi(2) + 4.0 * shear.powi(2)).sqrt(),
+        0.5 * (2.0 * shear).atan2(normal_x - normal_y)
+    )
+}
+
+// the element as it is at (x, y) of its disk, everything stays the same but its jones
+// matrix, except for photoelastic samples that turn into chromatic retarders so that
+// every light gets the retardance of its wavelength
+pub fn local_element(element: &WorldObject, x: f32, y: f32, map: Option<&ElementMap>) -> WorldObject {
+    if element.polarizer_type == PolarizerType::PhotoelasticSample {
+        // stress-optic law, Δn = C (σ1 - σ2), which of the principal directions is the
+        // fast axis only changes the sign of the retardance
+        let (stress_difference, angle) = principal_stresses(element, x, y);
+        let mut retarder = WorldObject {
+            polarizer_type: PolarizerType::ChromaticRetarder,
+            polarizer_angle: angle,
+            birefringence: element.stress_optic_coefficient * 1e-12 * stress_difference,
+            birefringence_dispersion: 0.0,
+            retarder_thickness: element.sample_thickness * 1000.0,
+            ..*element
+        };
+        retarder.jones_matrix = retarder.chromatic_retarder_jones_matrix(retarder.retarder_design_wavelength);
+
+        return retarder;
+    }
+
+    WorldObject { jones_matrix: local_jones_matrix(element, x, y, map), ..*element }
+}
+
+// jones matrix of the element at (x, y) of its disk, in the basis of the element
+pub fn local_jones_matrix(element: &WorldObject, x: f32, y: f32, map: Option<&ElementMap>) -> Matrix2<Complex<f32>> {
+    let azimuth = y.atan2(x);
+
+    match element.polarizer_type {
+        // the fast axis turns q times as fast as the azimuth
+        PolarizerType::QPlate => {
+            retarder_jones_matrix(element.spatial_retardance, element.topological_charge * azimuth + element.polarizer_angle)
+        }
+
+        // a half-wave plate whose fast axis turns m / 2 times as fast as the azimuth,
+        // circular light comes out with a vortex of charge ±m
+        PolarizerType::VortexRetarder => {
+            retarder_jones_matrix(PI, element.topological_charge * azimuth / 2.0 + element.polarizer_angle)
+        }
+
+        // whatever the maps don't give is uniform
+        PolarizerType::ImageElement => {
+            let read = |channel: MapChannel| map.filter(|map| map.uses(channel)).map(|map| map.value(channel, x, y));
+
+            let retardance = read(MapChannel::Retardance).map_or(element.spatial_retardance, |value| value * 2.0 * PI);
+            let fast_axis = read(MapChannel::FastAxis).map_or(element.polarizer_angle, |value| value * PI);
+            let amplitude = read(MapChannel::Amplitude).unwrap_or(1.0);
+
+            retarder_jones_matrix(retardance, fast_axis) * Complex::new(amplitude, 0.0)
+        }
+
+        PolarizerType::PhotoelasticSample => local_element(element, x, y, map).jones_matrix,
+
+        _ => element.jones_matrix
+    }
+}
+
+// copy of the element with the jones matrix it has at `point`, so that it can
+// go through jones_matrix_for_light like any other element
+pub fn element_at(element: &WorldObject, point: Vector3<f32>, map: Option<&ElementMap>) -> WorldObject {
+    if !is_spatially_varying(element) {
+        return *element;
+    }
+
+    let (x, y) = disk_coordinates(element, point);
+    local_element(element, x, y, map)
+}
@@ -0,0 +1,159 @@
+// Parameter sweeps, one property of one object goes over a range and the scene is
+// evaluated at a probe or a detector for every value, the world is a copy so the lab
+// itself never changes, the evaluation is the cpu side one of field.rs
+use std::fmt::{self, Display, Formatter};
+use std::f32::consts::PI;
+
+use nalgebra::Vector3;
+
+use crate::{detector::{measured_quantity, DetectorQuantity, DetectorReadout}, polarimetry::stokes_at, world::{ObjectType, World}};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SweepParameter {
+    HorizontalRotation,
+    VerticalRotation,
+    PolarizerAngle,
+    Retardation,
+    Wavelength,
+    MaterialWavelength,
+    AlignedDistance,
+    // not a property of the object but of the whole lab
+    CubeScalingFactor
+}
+
+// Needed for the drop down list
+impl Display for SweepParameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HorizontalRotation => write!(f, "X rotation (radians)"),
+            Self::VerticalRotation => write!(f, "Y rotation (radians)"),
+            Self::PolarizerAngle => write!(f, "θ (radians)"),
+            Self::Retardation => write!(f, "Relative phase retardation (radians)"),
+            Self::Wavelength => write!(f, "Wavelength (m)"),
+            Self::MaterialWavelength => write!(f, "Wavelength for materials (μm)"),
+            Self::AlignedDistance => write!(f, "Distance from the aligned object"),
+            Self::CubeScalingFactor => write!(f, "Cube size in meters"),
+        }
+    }
+}
+
+impl SweepParameter {
+    pub const ALL: [SweepParameter; 8] = [
+        Self::HorizontalRotation, Self::VerticalRotation, Self::PolarizerAngle, Self::Retardation,
+        Self::Wavelength, Self::MaterialWavelength, Self::AlignedDistance, Self::CubeScalingFactor
+    ];
+
+    // the range of the slider of the inspector
+    pub fn default_range(&self) -> (f32, f32) {
+        match self {
+            Self::HorizontalRotation | Self::VerticalRotation => (-PI, PI),
+            Self::PolarizerAngle => (0.0, PI),
+            Self::Retardation => (0.0, 2.0 * PI),
+            Self::Wavelength => (0.01, 0.2),
+            // the visible spectrum
+            Self::MaterialWavelength => (0.38, 0.78),
+            Self::AlignedDistance => (-1.0, 1.0),
+            Self::CubeScalingFactor => (0.5, 100.0),
+        }
+    }
+
+    pub fn value(&self, world: &World, object_index: usize, cube_scaling_factor: f32) -> f32 {
+        let object = &world.objects[object_index];
+
+        match self {
+            Self::HorizontalRotation => object.rotation[0],
+            Self::VerticalRotation => object.rotation[1],
+            Self::PolarizerAngle => object.polarizer_angle,
+            Self::Retardation => object.relative_phase_retardation,
+            Self::Wavelength => object.wavelength,
+            Self::MaterialWavelength => object.material_wavelength,
+            Self::AlignedDistance => object.aligned_distance,
+            Self::CubeScalingFactor => cube_scaling_factor,
+        }
+    }
+
+    // sets the property and the jones matrix that depends on it, the objects aligned to
+    // others follow them in the next frame of the app (see follow_alignments)
+    pub fn set(&self, world: &mut World, object_index: usize, value: f32, cube_scaling_factor: &mut f32) {
+        let object = &mut world.objects[object_index];
+
+        match self {
+            Self::HorizontalRotation => object.rotation[0] = value,
+            Self::VerticalRotation => object.rotation[1] = value,
+            Self::PolarizerAngle => object.polarizer_angle = value,
+            Self::Retardation => object.relative_phase_retardation = value,
+            Self::Wavelength => object.wavelength = value,
+            Self::MaterialWavelength => object.material_wavelength = value,
+            Self::AlignedDistance => object.aligned_distance = value,
+            Self::CubeScalingFactor => *cube_scaling_factor = value,
+        }
+
+        if object.object_type == ObjectType::OpticalObjectRoundWall {
+            object.set_jones_matrix(object.polarizer_type, object.polarizer_angle, object.relative_phase_retardation, object.circularity);
+        }
+    }
+}
+
+// what app.rs does every frame to the objects aligned to others, for copies of the world
+pub fn follow_alignments(world: &mut World) {
+    for aligned_index in world.aligned_objects.clone() {
+        let aligned_to = world.objects[world.objects[aligned_index].aligned_to_object];
+        world.objects[aligned_index].update_object_aligned_position(&aligned_to);
+    }
+}
+
+// what a probe sees at its center or the mean over the pixels of a detector
+pub fn measure(world: &World, target_index: usize, quantity: DetectorQuantity, cube_scaling_factor: f32) -> f32 {
+    let target = world.objects[target_index];
+
+    if target.object_type == ObjectType::Detector {
+        let mut readout = DetectorReadout::new(&target);
+        readout.record(world, target_index, cube_scaling_factor);
+        return readout.statistics(quantity).2;
+    }
+
+    measured_quantity(stokes_at(world, Vector3::from(target.center), cube_scaling_factor, Some(target_index)), quantity)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sweep {
+    pub object_index: usize,
+    pub parameter: SweepParameter,
+    pub start: f32,
+    pub end: f32,
+    pub steps: usize,
+    // probe or detector the scene is evaluated at
+    pub target_index: usize,
+    pub quantity: DetectorQuantity
+}
+
+impl Sweep {
+    // evenly spaced, both ends included
+    pub fn parameter_values(&self) -> Vec<f32> {
+        let steps = self.steps.max(2);
+
+        (0..steps).map(|i| self.start + (self.end - self.start) * i as f32 / (steps - 1) as f32).collect()
+    }
+
+    // [parameter, measured quantity] for every step
+    pub fn run(&self, world: &World, cube_scaling_factor: f32) -> Vec<[f32; 2]> {
+        let mut world = world.clone();
+        let mut cube_scaling_factor = cube_scaling_factor;
+
+        self.parameter_values().into_iter().map(|value| {
+            self.parameter.set(&mut world, self.object_index, value, &mut cube_scaling_factor);
+            follow_alignments(&mut world);
+            [value, measure(&world, self.target_index, self.quantity, cube_scaling_factor)]
+        }).collect()
+    }
+
+    pub fn csv(&self, results: &[[f32; 2]]) -> String {
+        let mut csv = format!("\"object {} {}\",\"{} at object {}\"\n", self.object_index, self.parameter, self.quantity, self.target_index);
+
+        for [value, measured] in results {
+            csv += &format!("{},{}\n", value, measured);
+        }
+
+        csv
+    }
+}
@@ -1,4 +1,957 @@
 #[cfg(test)]
 mod tests {
+    use std::f32::consts::PI;
 
+    use nalgebra::{Complex, Vector2, Vector3};
+
+    use crate::{render_mode::{colormap_color, diverging_color, render_color, summed_instantaneous_field, Colormap, RenderMode, RenderSettings}, beam_path::{beam_path, beam_radius, polarization_glyph}, cross_section::{hue_color, section_color, section_fields, CrossSection, CrossSectionMode}, poincare::{beam_trajectory, jones_vector_at, rotation_on_sphere, sphere_point}, sweep::{Sweep, SweepParameter}, camera::Camera, field::surface_distance, profile::{profile_csv, sample_profile}, fringes::{analyze_fringes, detector_profile, expected_fringes, FringeAxis}, optimizer::{nelder_mead, Objective, Optimization, OptimizationVariable}, detector::{pixel_position, sensor_side, DetectorQuantity, DetectorReadout}, polarimetry::{ellipse_azimuth, ellipticity_angle, fields_by_wavelength, handedness, instantaneous_field, stokes_parameters, stokes_at, Handedness}, coatings::{anti_reflection_coating, dielectric_mirror, stack_response}, spatial::{disk_coordinates, element_at, local_element, local_jones_matrix, principal_stresses, ElementMap, MapChannel, MAP_RESOLUTION}, etalon::{airy_transmittance, closest_resonance, etalon_transmission, finesse, free_spectral_range, round_trip_phase}, diffraction::{aperture_transmits, diffracted_amplitude, expected_fringe_period}, displacer::{displaced_beams, walk_off_angle, CrystalRay}, electro_optics::{drive_voltage, electro_optic_jones_matrix, liquid_crystal_retardance, pockels_retardance}, field::{jones_matrix_for_light, light_direction, light_fields_at, BEAM_WAIST}, grating::{diffraction_orders, order_efficiency}, materials::{MaterialLibrary, MaterialName}, prism::{minimum_deviation, prism_beam, prism_intersection}, world::{retarder_jones_matrix, ApertureType, DriveWaveform, GratingType, LightPolarizationType, ObjectType, PolarizerType, StressField, World, WorldObject}};
+
+    // light going towards +z through a vertical slit screen one cube in front of it
+    fn slits_setup(slit_count: u32) -> (WorldObject, WorldObject) {
+        let light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 5.0], wavelength: 0.02, ..WorldObject::new() };
+        let slits = WorldObject { object_type: ObjectType::Aperture, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 6.0], aperture_type: ApertureType::MultipleSlits, aperture_width: 0.15, aperture_height: 0.8, slit_count, slit_pitch: 0.45, ..WorldObject::new() };
+
+        (light, slits)
+    }
+
+    #[test]
+    fn slits_only_transmit_through_openings() {
+        let (_, slits) = slits_setup(2);
+
+        assert!(aperture_transmits(&slits, Vector2::new(0.225, 0.0)));
+        assert!(aperture_transmits(&slits, Vector2::new(-0.225, 0.3)));
+        assert!(!aperture_transmits(&slits, Vector2::new(0.0, 0.0)));
+        assert!(!aperture_transmits(&slits, Vector2::new(0.225, 0.5)));
+    }
+
+    #[test]
+    fn double_slit_fringe_period_matches_geometry() {
+        let (light, slits) = slits_setup(2);
+        let distance = 20.0;
+
+        let intensities: Vec<(f32, f32)> = (-1000..=1000).map(|i| {
+            let x = i as f32 * 0.002;
+            let point = Vector3::new(10.0 + x, 10.0, 6.0 + distance);
+
+            (x, diffracted_amplitude(&light, &slits, point, 1.0, 1.0).norm_sqr())
+        }).collect();
+
+        let max_intensity = intensities.iter().map(|(_, intensity)| *intensity).fold(0.0, f32::max);
+        let maxima: Vec<f32> = intensities.windows(3)
+            .filter(|w| w[1].1 > w[0].1 && w[1].1 > w[2].1 && w[1].1 > 0.5 * max_intensity)
+            .map(|w| w[1].0)
+            .collect();
+
+        assert!(maxima.len() >= 3);
+
+        let measured_period = (maxima[maxima.len() - 1] - maxima[0]) / (maxima.len() - 1) as f32;
+        let expected_period = expected_fringe_period(&slits, light.wavelength, distance, 1.0);
+
+        assert!((measured_period - expected_period).abs() / expected_period < 0.05, "measured {measured_period}, expected {expected_period}");
+    }
+
+    fn grating_setup(wavelength: f32) -> (WorldObject, WorldObject) {
+        let light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 5.0], wavelength, ..WorldObject::new() };
+        let grating = WorldObject { object_type: ObjectType::Grating, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 6.0], grating_type: GratingType::Transmission, groove_density: 20.0, blaze_angle: 0.0, ..WorldObject::new() };
+
+        (light, grating)
+    }
+
+    #[test]
+    fn stacked_apertures_diffract_at_the_first_one() {
+        let (mut light, slits) = slits_setup(2);
+        light.set_light_polarization();
+        let rectangle = WorldObject { center: [10.0, 10.0, 8.0], aperture_type: ApertureType::Rectangular, aperture_width: 0.3, aperture_height: 0.3, ..slits };
+
+        let mut world = World::new();
+        for (index, object) in [(1, light), (2, rectangle), (3, slits)] {
+            world.objects[index] = object;
+            world.objects_associations.insert(index, vec![]);
+        }
+        world.light_sources.push(1);
+
+        // the point sees both screens on its way back to the light, like main.frag only
+        // the one the light meets first counts
+        let point = Vector3::new(10.2, 10.0, 12.0);
+        let (_, field) = light_fields_at(&world, point, 1.0, None)[0];
+        let medium_index = world.medium_refractive_index(light.material_wavelength);
+        let through_slits = diffracted_amplitude(&light, &slits, point, medium_index, 1.0);
+
+        assert!((field[0] - through_slits).norm() < 1e-5 * through_slits.norm());
+        assert!((field[0] - diffracted_amplitude(&light, &rectangle, point, medium_index, 1.0)).norm() > 1e-3 * through_slits.norm());
+    }
+
+    #[test]
+    fn grating_orders_follow_grating_equation() {
+        for wavelength in [0.02, 0.026, 0.032] {
+            let (light, grating) = grating_setup(wavelength);
+            let orders = diffraction_orders(&grating, &light, 1.0);
+
+            for order in &orders {
+                let expected = (order.order as f32 * wavelength * grating.groove_density).asin();
+                assert!((order.angle - expected).abs() < 1e-4, "order {}: {} vs {}", order.order, order.angle, expected);
+            }
+
+            // |m * λ / d| >= 1 doesn't propagate
+            let propagating = orders.iter().filter(|order| (order.order as f32 * wavelength * grating.groove_density).abs() < 1.0).count();
+            assert_eq!(orders.len(), propagating);
+        }
+    }
+
+    #[test]
+    fn blazed_grating_favors_the_blazed_order() {
+        let (light, mut grating) = grating_setup(0.02);
+
+        // optical path difference of one wavelength across each groove
+        grating.blaze_angle = (light.wavelength * grating.groove_density / 0.5).atan();
+
+        assert!((order_efficiency(&grating, 1, light.wavelength, 1.0) - 1.0).abs() < 1e-4);
+        assert!(order_efficiency(&grating, 0, light.wavelength, 1.0) < 1e-4);
+        assert!(order_efficiency(&grating, -1, light.wavelength, 1.0) < 1e-4);
+
+        // the reflection grating reflects the orders back towards the light
+        grating.grating_type = GratingType::Reflection;
+        for order in diffraction_orders(&grating, &light, 1.0) {
+            assert!(order.direction.z < 0.0);
+        }
+    }
+
+    fn transmitted_intensity(elements: &[WorldObject], polarization: Vector2<Complex<f32>>) -> f32 {
+        let output = elements.iter().fold(polarization, |field, element| element.jones_matrix * field);
+
+        output.norm_squared()
+    }
+
+    #[test]
+    fn absorbers_follow_their_transmittance() {
+        let mut filter = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, optical_density: 2.0, ..WorldObject::new() };
+        filter.set_jones_matrix(PolarizerType::NeutralDensityFilter, 0.0, 0.0, 0.0);
+
+        let mut slab = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, absorption_coefficient: 0.3, slab_thickness: 2.0, ..WorldObject::new() };
+        slab.set_jones_matrix(PolarizerType::AbsorbingSlab, 0.0, 0.0, 0.0);
+
+        let diagonal = Vector2::new(Complex::new(1.0, 0.0), Complex::new(0.0, 1.0)).map(|x| x / 2f32.sqrt());
+
+        assert!((transmitted_intensity(&[filter], diagonal) - 0.01).abs() < 1e-6);
+        assert!((transmitted_intensity(&[slab], diagonal) - (-0.6f32).exp()).abs() < 1e-6);
+        assert!((transmitted_intensity(&[filter, slab], diagonal) - 0.01 * (-0.6f32).exp()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn crossed_non_ideal_polarizers_leak() {
+        let mut first = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, principal_transmittance: 0.8, extinction_ratio: 1000.0, ..WorldObject::new() };
+        let mut second = first;
+
+        first.set_jones_matrix(PolarizerType::PartialLinearPolarizerTheta, 0.0, 0.0, 0.0);
+        second.set_jones_matrix(PolarizerType::PartialLinearPolarizerTheta, PI / 2.0, 0.0, 0.0);
+
+        let horizontal = Vector2::new(Complex::new(1.0, 0.0), Complex::new(0.0, 0.0));
+        let k1 = 0.8;
+        let k2 = 0.8 / 1000.0;
+
+        assert!((transmitted_intensity(&[first], horizontal) - k1).abs() < 1e-6);
+        assert!((transmitted_intensity(&[first, second], horizontal) - k1 * k2).abs() < 1e-6);
+
+        // an ideal pair doesn't let anything through
+        first.set_jones_matrix(PolarizerType::LinearHorizontal, 0.0, 0.0, 0.0);
+        second.set_jones_matrix(PolarizerType::LinearVertical, 0.0, 0.0, 0.0);
+        assert!(transmitted_intensity(&[first, second], horizontal) < 1e-12);
+    }
+
+    // intensity of `light` after going through `elements`, in order
+    fn intensity_through(elements: &[WorldObject], light: &WorldObject) -> f32 {
+        let direction = light_direction(light);
+        let output = elements.iter().fold(light.polarization, |field, element| jones_matrix_for_light(element, light, direction) * field);
+
+        output.norm_squared()
+    }
+
+    #[test]
+    fn faraday_isolator_only_works_one_way() {
+        // same setup as the optical isolator demo
+        let mut forward = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [13.5, 13.5, 6.0], polarization_type: LightPolarizationType::LinearHorizontal, ..WorldObject::new() };
+        let mut backward = WorldObject { rotation: [0.0, 0.0], center: [13.5, 13.5, 19.0], polarization_type: LightPolarizationType::LinearAntiDiagonal, ..forward };
+        forward.set_light_polarization();
+        backward.set_light_polarization();
+
+        let element = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], ..WorldObject::new() };
+        let mut input_polarizer = WorldObject { center: [13.5, 13.5, 9.0], ..element };
+        let mut rotator = WorldObject { center: [13.5, 13.5, 12.0], verdet_constant: 40.0, magnetic_field: 1.0, faraday_length: PI / 4.0 / 40.0, ..element };
+        let mut output_polarizer = WorldObject { center: [13.5, 13.5, 15.0], ..element };
+
+        input_polarizer.set_jones_matrix(PolarizerType::LinearHorizontal, 0.0, 0.0, 0.0);
+        rotator.set_jones_matrix(PolarizerType::FaradayRotator, 0.0, 0.0, 0.0);
+        output_polarizer.set_jones_matrix(PolarizerType::LinearTheta, PI / 4.0, 0.0, 0.0);
+
+        assert!((intensity_through(&[input_polarizer, rotator, output_polarizer], &forward) - 1.0).abs() < 1e-4);
+        assert!(intensity_through(&[output_polarizer, rotator, input_polarizer], &backward) < 1e-4);
+
+        // an optical rotator rotates the same way w.r.t the light in both directions
+        rotator.specific_rotation = 45.0;
+        rotator.concentration = 1.0;
+        rotator.rotator_path_length = 1.0;
+        rotator.set_jones_matrix(PolarizerType::OpticalRotator, 0.0, 0.0, 0.0);
+
+        assert!((intensity_through(&[input_polarizer, rotator, output_polarizer], &forward) - 1.0).abs() < 1e-4);
+        assert!((intensity_through(&[output_polarizer, rotator, input_polarizer], &backward) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn multi_order_retarders_are_more_chromatic() {
+        let mut light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [13.5, 13.5, 6.0], polarization_type: LightPolarizationType::LinearHorizontal, material_wavelength: 0.55, ..WorldObject::new() };
+        light.set_light_polarization();
+
+        // half-wave plates with the fast axis at 45 degrees between crossed polarizers
+        let element = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], ..WorldObject::new() };
+        let mut zero_order = WorldObject { center: [13.5, 13.5, 9.0], retarder_waves: 0.5, retarder_order: 0, ..element };
+        let mut multi_order = WorldObject { retarder_order: 20, ..zero_order };
+        let mut analyzer = WorldObject { center: [13.5, 13.5, 12.0], ..element };
+
+        for plate in [&mut zero_order, &mut multi_order] {
+            plate.retarder_thickness = plate.retarder_design_thickness();
+            plate.set_jones_matrix(PolarizerType::ChromaticRetarder, PI / 4.0, 0.0, 0.0);
+        }
+        analyzer.set_jones_matrix(PolarizerType::LinearVertical, 0.0, 0.0, 0.0);
+
+        // both are perfect at the design wavelength
+        assert!((intensity_through(&[zero_order, analyzer], &light) - 1.0).abs() < 1e-3);
+        assert!((intensity_through(&[multi_order, analyzer], &light) - 1.0).abs() < 1e-3);
+
+        // a bit further into the red only the zero-order plate is still close to a half-wave plate
+        light.material_wavelength = 0.62;
+        assert!(intensity_through(&[zero_order, analyzer], &light) > 0.95);
+        assert!(intensity_through(&[multi_order, analyzer], &light) < 0.5);
+    }
+
+    #[test]
+    fn material_library_matches_catalog_indices() {
+        let mut materials = MaterialLibrary::new();
+
+        // catalog values at the helium d line (587.6 nm)
+        for (name, expected) in [("BK7", 1.5168), ("Fused silica", 1.4585), ("Calcite (ordinary)", 1.6584), ("Calcite (extraordinary)", 1.4864), ("Quartz (ordinary)", 1.5443), ("Quartz (extraordinary)", 1.5534), ("Water", 1.3330), ("Air", 1.0003)] {
+            let n = materials.refractive_index(&MaterialName::new(name), 0.5876);
+            assert!((n - expected).abs() < 5e-4, "{} has n = {}", name, n);
+        }
+
+        // normal dispersion, blue light sees a higher index
+        let bk7 = MaterialName::new("BK7");
+        assert!(materials.refractive_index(&bk7, 0.45) > materials.refractive_index(&bk7, 0.65));
+
+        // users can add their own and replace existing ones
+        assert_eq!(materials.add_materials("Oil; cauchy; 1.5, 0.004, 0; 0.4, 0.8\nBK7; constant; 1.6; 0.1, 10"), Ok(2));
+        assert!((materials.refractive_index(&MaterialName::new("Oil"), 0.5) - 1.516).abs() < 1e-4);
+        assert_eq!(materials.refractive_index(&bk7, 0.5), 1.6);
+
+        assert!(materials.add_materials("Broken; sellmeier; 1, 2; 0.4, 0.8").is_err());
+        assert_eq!(materials.refractive_index(&MaterialName::new("Unobtainium"), 0.5), 1.0);
+    }
+
+    #[test]
+    fn prisms_deviate_blue_light_the_most() {
+        let materials = MaterialLibrary::new();
+        let sf11 = MaterialName::new("SF11");
+
+        // turned so that green light goes through at minimum deviation
+        let mut prism = WorldObject { object_type: ObjectType::Prism, center: [10.0, 10.0, 8.0], radius: 1.0, apex_angle: PI / 3.0, material: sf11, ..WorldObject::new() };
+        let green_index = materials.refractive_index(&sf11, 0.55);
+        prism.rotation = [minimum_deviation(&prism, green_index, 1.0).unwrap() / 2.0 - PI / 2.0, PI / 2.0];
+
+        let light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 3.0], ..WorldObject::new() };
+
+        // a ray through the middle goes in and out of the prism around its center
+        let (t_near, t_far, _, _) = prism_intersection(&prism, Vector3::from(light.center), light_direction(&light)).unwrap();
+        assert!(t_near < 5.0 && t_far > 5.0);
+
+        let deviations: Vec<f32> = [0.45, 0.55, 0.65].into_iter().map(|wavelength| {
+            let prism_index = materials.refractive_index(&sf11, wavelength);
+            let beam = prism_beam(&prism, &WorldObject { material_wavelength: wavelength, ..light }, prism_index, 1.0, &[]).unwrap();
+
+            // never below the minimum deviation, and near minimum deviation the faces are
+            // close to brewster's angle so p light gets through a lot better than s light
+            assert!(beam.deviation >= minimum_deviation(&prism, prism_index, 1.0).unwrap() - 1e-3);
+            assert!(beam.transmittance[1] > 0.95 && beam.transmittance[0] < beam.transmittance[1]);
+
+            beam.deviation
+        }).collect();
+
+        assert!((deviations[1] - minimum_deviation(&prism, green_index, 1.0).unwrap()).abs() < 1e-3);
+        assert!(deviations[0] > deviations[1] && deviations[1] > deviations[2]);
+        // dense flint spreads the visible spectrum over a few degrees
+        assert!(deviations[0] - deviations[2] > 3f32.to_radians());
+    }
+
+    #[test]
+    fn calcite_splits_light_into_orthogonal_rays() {
+        let materials = MaterialLibrary::new();
+        let displacer = WorldObject { object_type: ObjectType::BeamDisplacer, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 8.0], radius: 1.0, material: MaterialName::new("Calcite (ordinary)"), displacer_length: 4.0, ..WorldObject::new() };
+        let mut light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 3.0], material_wavelength: 0.55, polarization_type: LightPolarizationType::LinearDiagonal, ..WorldObject::new() };
+        light.set_light_polarization();
+
+        let indices = [materials.refractive_index(&displacer.material, 0.55), materials.refractive_index(&displacer.extraordinary_material, 0.55)];
+        let beams = displaced_beams(&displacer, &light, indices, 1.0);
+        assert_eq!(beams.len(), 2);
+
+        let (ordinary, extraordinary) = (beams[0], beams[1]);
+        assert_eq!((ordinary.ray, extraordinary.ray), (CrystalRay::Ordinary, CrystalRay::Extraordinary));
+
+        // at normal incidence the o ray goes straight and the e ray walks off by the
+        // textbook angle, both leave parallel to the light
+        assert!(ordinary.walk_off < 1e-4);
+        assert!((extraordinary.walk_off - walk_off_angle(indices[0], indices[1], PI / 4.0).abs()).abs() < 1e-3);
+        assert!((extraordinary.walk_off.to_degrees() - 6.2).abs() < 0.2);
+        assert!((ordinary.direction - light_direction(&light)).norm() < 1e-4);
+        assert!((extraordinary.direction - light_direction(&light)).norm() < 1e-4);
+
+        let separation = (extraordinary.exit - ordinary.exit).norm();
+        assert!((separation - displacer.displacer_length * extraordinary.walk_off.tan()).abs() < 1e-3);
+
+        // the rays take orthogonal polarizations and share the light between them
+        let ordinary_part = ordinary.jones_matrix * light.polarization;
+        let extraordinary_part = extraordinary.jones_matrix * light.polarization;
+        assert!(ordinary_part.dotc(&extraordinary_part).norm() < 1e-4);
+        assert!((ordinary_part.norm_squared() + extraordinary_part.norm_squared() - light.polarization.norm_squared()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn coatings_follow_the_transfer_matrix_method() {
+        let materials = MaterialLibrary::new();
+        let glass = materials.refractive_index(&MaterialName::new("BK7"), 0.55);
+
+        // with no layers it's the fresnel equations of the bare surface
+        let bare = stack_response(&[], 1.0, glass, 1.0, 0.55);
+        assert!((bare.reflectance[0] - ((glass - 1.0) / (glass + 1.0)).powi(2)).abs() < 1e-5);
+
+        let cos_incidence = 50f32.to_radians().cos();
+        let cos_transmission = (1.0 - (1.0 - cos_incidence.powi(2)) / glass.powi(2)).sqrt();
+        let rs = (cos_incidence - glass * cos_transmission) / (cos_incidence + glass * cos_transmission);
+        let rp = (glass * cos_incidence - cos_transmission) / (glass * cos_incidence + cos_transmission);
+        let oblique = stack_response(&[], 1.0, glass, cos_incidence, 0.55);
+        assert!((oblique.reflectance[0] - rs.powi(2)).abs() < 1e-5);
+        assert!((oblique.reflectance[1] - rp.powi(2)).abs() < 1e-5);
+
+        // a quarter wave of magnesium fluoride takes the ~4% of glass to about 1%
+        let anti_reflection = stack_response(&anti_reflection_coating(&materials, 0.55).indices(&materials, 0.55), 1.0, glass, 1.0, 0.55);
+        assert!(bare.reflectance[0] > 0.04 && anti_reflection.reflectance[0] < 0.015);
+
+        // a quarter-wave stack reflects almost everything at its design wavelength only
+        let mirror = dielectric_mirror(&materials, 0.55);
+        let at_design = stack_response(&mirror.indices(&materials, 0.55), 1.0, glass, 1.0, 0.55);
+        let far_away = stack_response(&mirror.indices(&materials, 0.75), 1.0, glass, 1.0, 0.75);
+        assert!(at_design.reflectance[0] > 0.9 && far_away.reflectance[0] < at_design.reflectance[0]);
+
+        // nothing gets absorbed
+        for response in [bare, oblique, anti_reflection, at_design, far_away] {
+            for polarization in 0..2 {
+                assert!((response.reflectance[polarization] + response.transmittance[polarization] - 1.0).abs() < 1e-4);
+                assert!((response.transmission[polarization].norm_sqr() - response.transmittance[polarization]).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn etalons_transmit_airy_peaks() {
+        let etalon = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, polarizer_type: PolarizerType::FabryPerotEtalon, etalon_reflectance: 0.9, etalon_gap: 10.0, ..WorldObject::new() };
+
+        // the sum of round trips matches the airy function away from and on the peaks
+        for wavelength in [0.5, 0.55, 0.5503, 0.6] {
+            for cos_incidence in [1.0, 0.9] {
+                let phase = round_trip_phase(&etalon, wavelength, cos_incidence);
+                let transmittance = etalon_transmission(&etalon, wavelength, cos_incidence).norm_sqr();
+
+                assert!((transmittance - airy_transmittance(etalon.etalon_reflectance, phase)).abs() < 1e-3);
+            }
+        }
+
+        // everything gets through on a peak and almost nothing halfway between two
+        let (order, resonance) = closest_resonance(&etalon, 0.55, 1.0);
+        let free_spectral_range = free_spectral_range(&etalon, resonance, 1.0);
+        assert_eq!(order, 36);
+        assert!(etalon_transmission(&etalon, resonance, 1.0).norm_sqr() > 0.99);
+        assert!(etalon_transmission(&etalon, resonance + free_spectral_range / 2.0, 1.0).norm_sqr() < 0.01);
+        // the next peak is the one of the order below, about a free spectral range away
+        let next_resonance = 2.0 * etalon.etalon_gap / (order - 1) as f32;
+        assert!(etalon_transmission(&etalon, next_resonance, 1.0).norm_sqr() > 0.99);
+        assert!(((next_resonance - resonance) / free_spectral_range - 1.0).abs() < 0.05);
+
+        // the peaks are about a finesse times narrower than the distance between them
+        let half_width = free_spectral_range / finesse(etalon.etalon_reflectance) / 2.0;
+        assert!((etalon_transmission(&etalon, resonance + half_width, 1.0).norm_sqr() - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn spatially_varying_elements_change_across_the_disk() {
+        // a half-wave q-plate with q = 1/2 turns horizontal light into radial polarization
+        let q_plate = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, polarizer_type: PolarizerType::QPlate, topological_charge: 0.5, spatial_retardance: PI, polarizer_angle: 0.0, ..WorldObject::new() };
+        let horizontal = Vector2::new(Complex::new(1.0, 0.0), Complex::new(0.0, 0.0));
+
+        for azimuth in [0.3f32, 1.2, 2.5, -2.0] {
+            let output = local_jones_matrix(&q_plate, azimuth.cos() * 0.5, azimuth.sin() * 0.5, None) * horizontal;
+            let radial = Vector2::new(Complex::new(azimuth.cos(), 0.0), Complex::new(azimuth.sin(), 0.0));
+
+            assert!((output.dotc(&radial).norm() - 1.0).abs() < 1e-4);
+        }
+
+        // amplitude maps read from a png, the left half of this one is black
+        let mut png = vec![];
+        let image = ::image::GrayImage::from_fn(64, 64, |x, _| ::image::Luma([if x < 32 { 0 } else { 255 }]));
+        image.write_to(&mut std::io::Cursor::new(&mut png), ::image::ImageFormat::Png).unwrap();
+
+        let mut map = ElementMap::new();
+        map.set_channel_from_image(MapChannel::Amplitude, "half.png", &png).unwrap();
+        assert_eq!(map.pixels.len(), MAP_RESOLUTION * MAP_RESOLUTION * 4);
+        assert!(map.uses(MapChannel::Amplitude) && !map.uses(MapChannel::Retardance));
+
+        let mut mask = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 10.0], radius: 1.0, spatial_retardance: 0.0, ..WorldObject::new() };
+        mask.set_jones_matrix(PolarizerType::ImageElement, 0.0, 0.0, 0.0);
+
+        let [horizontal_axis, _, _] = mask.local_axes();
+        let left = element_at(&mask, Vector3::from(mask.center) - horizontal_axis * 0.5, Some(&map));
+        let right = element_at(&mask, Vector3::from(mask.center) + horizontal_axis * 0.5, Some(&map));
+
+        assert!((left.jones_matrix * horizontal).norm() < 1e-4);
+        assert!(((right.jones_matrix * horizontal).norm() - 1.0).abs() < 1e-4);
+
+        // the map spans the whole disk main.frag draws
+        let (edge, _) = disk_coordinates(&mask, Vector3::from(mask.center) + horizontal_axis * mask.drawn_radius());
+        assert!((edge - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn photoelastic_samples_follow_the_stress_optic_law() {
+        // at the center of a disk under diametral compression σ1 - σ2 = 8P / (π D t)
+        // with the principal stresses along the axes
+        let mut disk = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, stress_field: StressField::DiskCompression, applied_load: 500.0, stress_optic_coefficient: 50.0, sample_size: 50.0, sample_thickness: 6.0, ..WorldObject::new() };
+        disk.set_jones_matrix(PolarizerType::PhotoelasticSample, 0.0, 0.0, 0.0);
+
+        let (stress_difference, angle) = principal_stresses(&disk, 0.0, 0.0);
+        let expected = 8.0 * 500.0 / (PI * 0.05 * 0.006);
+        assert!((stress_difference - expected).abs() / expected < 1e-3);
+        assert!(angle.abs() < 1e-4);
+
+        // the retardance of every wavelength is 2π C t (σ1 - σ2) / λ
+        let local = local_element(&disk, 0.0, 0.0, None);
+        assert_eq!(local.polarizer_type, PolarizerType::ChromaticRetarder);
+
+        for wavelength in [0.45, 0.55, 0.65] {
+            let fringe_order = 50e-12 * expected * 6000.0 / wavelength;
+            let retardance = 2.0 * PI * fringe_order;
+            let crossed = local.chromatic_retarder_jones_matrix(wavelength)[(1, 0)].norm_sqr();
+
+            assert!((local.retardance_at(wavelength) - retardance).abs() < 1e-2);
+            // the axes line up with the polarizers, the center is an isoclinic
+            assert!(crossed < 1e-6);
+        }
+
+        // the stress is symmetric about both axes and grows towards the loads
+        let (near_load, _) = principal_stresses(&disk, 0.0, 0.9);
+        assert!(near_load > stress_difference);
+        assert!((principal_stresses(&disk, 0.3, 0.4).0 - principal_stresses(&disk, -0.3, -0.4).0).abs() / stress_difference < 1e-3);
+
+        // a beam in bending has no stress (and no retardance) along its neutral axis and
+        // the stress grows linearly towards its edges
+        let beam = WorldObject { stress_field: StressField::BeamBending, applied_load: 5.0, sample_size: 20.0, ..disk };
+        assert!(local_jones_matrix(&beam, 0.5, 0.0, None)[(1, 0)].norm() < 1e-6);
+
+        let edge = principal_stresses(&beam, 0.0, 1.0).0;
+        assert!((edge - 6.0 * 5.0 / (0.006 * 0.02f32.powi(2))).abs() / edge < 1e-3);
+        assert!((principal_stresses(&beam, 0.0, 0.5).0 - edge / 2.0).abs() / edge < 1e-3);
+    }
+
+    #[test]
+    fn electro_optic_elements_follow_their_drive() {
+        // a liquid crystal cell keeps all of its retardance under the threshold and
+        // loses it as the voltage goes up, whatever its sign
+        let cell = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, polarizer_type: PolarizerType::LiquidCrystalCell, lc_max_retardance: PI, lc_threshold_voltage: 1.0, lc_voltage_scale: 1.0, ..WorldObject::new() };
+
+        assert!((liquid_crystal_retardance(&cell, 0.5) - PI).abs() < 1e-6);
+        assert!(liquid_crystal_retardance(&cell, 2.0) < liquid_crystal_retardance(&cell, 1.5));
+        assert!(liquid_crystal_retardance(&cell, 10.0) < 0.01);
+        assert!((liquid_crystal_retardance(&cell, -3.0) - liquid_crystal_retardance(&cell, 3.0)).abs() < 1e-6);
+
+        // a pockels cell at 45 degrees between crossed polarizers is a shutter, closed
+        // without voltage and open at the half-wave voltage
+        let pockels = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, polarizer_type: PolarizerType::PockelsCell, polarizer_angle: PI / 4.0, half_wave_voltage: 250.0, drive_waveform: DriveWaveform::Square, drive_offset: 125.0, drive_amplitude: 125.0, drive_frequency: 1.0, ..WorldObject::new() };
+        assert!((pockels_retardance(&pockels, 250.0) - PI).abs() < 1e-6);
+
+        // high for the first half of every period and low for the second one
+        assert!((drive_voltage(&pockels, 0.25) - 250.0).abs() < 1e-3);
+        assert!(drive_voltage(&pockels, 0.75).abs() < 1e-3);
+
+        let open = electro_optic_jones_matrix(&pockels, 0.25)[(1, 0)].norm_sqr();
+        let closed = electro_optic_jones_matrix(&pockels, 1.75)[(1, 0)].norm_sqr();
+        assert!((open - 1.0).abs() < 1e-4);
+        assert!(closed < 1e-6);
+
+        // a sine drive modulates the amplitude, sin²(Γ/2) between crossed polarizers
+        let modulator = WorldObject { drive_waveform: DriveWaveform::Sine, ..pockels };
+        let expected = (pockels_retardance(&modulator, drive_voltage(&modulator, 0.1)) / 2.0).sin().powi(2);
+        assert!((electro_optic_jones_matrix(&modulator, 0.1)[(1, 0)].norm_sqr() - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn detectors_record_intensity_and_stokes_parameters() {
+        // horizontal light going along +z towards a detector facing it, without
+        // insert_object since it logs to the browser console
+        let mut world = World::new();
+        let mut light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, polarization_type: LightPolarizationType::LinearHorizontal, wavelength: 0.5, ..WorldObject::new() };
+        light.set_light_polarization();
+        let detector = WorldObject { object_type: ObjectType::Detector, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 12.0], radius: 0.5, detector_pixels: [9, 9], ..WorldObject::new() };
+
+        for (index, object) in [(1, light), (2, detector)] {
+            world.objects[index] = object;
+            world.objects_associations.insert(index, vec![]);
+        }
+        world.light_sources.push(1);
+
+        let mut readout = DetectorReadout::new(&detector);
+        readout.record(&world, 2, 1.0);
+
+        let intensity = readout.values(DetectorQuantity::Intensity);
+        let (min, max, mean) = readout.statistics(DetectorQuantity::Intensity);
+        assert!(max > 0.0 && min < max && mean > min && mean < max);
+        // the center of the beam is in the middle pixel
+        assert_eq!(intensity[4 * 9 + 4], max);
+        assert!((pixel_position(&detector, 4, 4) - Vector3::from(detector.center)).norm() < 1e-5);
+        // the sensor is the square inscribed in the disk main.frag draws
+        let corner = pixel_position(&detector, 0, 0) - Vector3::from(detector.center);
+        assert!(corner.norm() < detector.drawn_radius() && corner.norm() > detector.radius);
+
+        // all of it is horizontal
+        let s1 = readout.values(DetectorQuantity::S1);
+        assert!(intensity.iter().zip(&s1).all(|(s0, s1)| (s0 - s1).abs() <= 1e-4 * s0));
+        assert!(readout.values(DetectorQuantity::DegreeOfPolarization).iter().all(|dop| (dop - 1.0).abs() < 1e-3));
+
+        // a quarter-wave plate at 45 degrees in between makes it circular
+        let plate = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 8.0], radius: 0.5, polarizer_type: PolarizerType::GeneralWavePlateLinearRetarderTheta, jones_matrix: retarder_jones_matrix(PI / 2.0, PI / 4.0), ..WorldObject::new() };
+        world.objects[3] = plate;
+        world.objects_associations.insert(3, vec![]);
+
+        let stokes = stokes_at(&world, Vector3::from(detector.center), 1.0, Some(2));
+        assert!((stokes[3].abs() - stokes[0]).abs() < 1e-3 * stokes[0]);
+
+        // and a wall in between blocks it, out to the edge main.frag draws it with
+        world.objects[4] = WorldObject { object_type: ObjectType::RoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 11.5], radius: 0.5, ..WorldObject::new() };
+        world.objects_associations.insert(4, vec![]);
+        assert_eq!(stokes_at(&world, Vector3::from(detector.center), 1.0, Some(2))[0], 0.0);
+        assert_eq!(stokes_at(&world, pixel_position(&detector, 0, 0), 1.0, Some(2))[0], 0.0);
+    }
+
+    #[test]
+    fn probes_measure_the_polarization_ellipse() {
+        let mut world = World::new();
+        let light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, wavelength: 0.5, ..WorldObject::new() };
+        let probe = WorldObject { object_type: ObjectType::Probe, center: [10.0, 10.0, 9.0], radius: 0.1, ..WorldObject::new() };
+
+        world.objects[2] = probe;
+        world.objects_associations.insert(1, vec![]);
+        world.objects_associations.insert(2, vec![]);
+        world.light_sources.push(1);
+
+        let measure = |world: &mut World, polarization_type: LightPolarizationType| {
+            world.objects[1] = WorldObject { polarization_type, ..light };
+            world.objects[1].set_light_polarization();
+            stokes_at(world, Vector3::from(probe.center), 1.0, Some(2))
+        };
+
+        let diagonal = measure(&mut world, LightPolarizationType::LinearDiagonal);
+        assert!((ellipse_azimuth(diagonal).abs() - PI / 4.0).abs() < 1e-4);
+        assert!(ellipticity_angle(diagonal).abs() < 1e-4);
+        assert_eq!(handedness(diagonal), Handedness::None);
+
+        let right = measure(&mut world, LightPolarizationType::CircularRightHand);
+        assert!((ellipticity_angle(right) - PI / 4.0).abs() < 1e-3);
+        assert_eq!(handedness(right), Handedness::Right);
+        assert_eq!(handedness(measure(&mut world, LightPolarizationType::CircularLeftHand)), Handedness::Left);
+
+        // a quarter-wave plate at 22.5 degrees turns horizontal light into an ellipse with
+        // its major axis along the fast axis and an axis ratio of tan(22.5)
+        world.objects[3] = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 7.0], radius: 0.5, polarizer_type: PolarizerType::GeneralWavePlateLinearRetarderTheta, jones_matrix: retarder_jones_matrix(PI / 2.0, PI / 8.0), ..WorldObject::new() };
+        world.objects_associations.insert(3, vec![]);
+
+        let elliptical = measure(&mut world, LightPolarizationType::LinearHorizontal);
+        assert!((ellipse_azimuth(elliptical).abs() - PI / 8.0).abs() < 1e-3);
+        assert!((ellipticity_angle(elliptical).abs() - PI / 8.0).abs() < 1e-3);
+
+        // a probe right on the light (both are created where the viewer is) reads nothing
+        // instead of NaN
+        assert_eq!(stokes_at(&world, Vector3::from(light.center), 1.0, Some(2)), [0.0; 4]);
+    }
+
+    #[test]
+    fn retarders_rotate_the_poincare_sphere() {
+        for point in [Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, -1.0, 0.0), Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.3, -0.5, 0.6).normalize()] {
+            assert!((sphere_point(jones_vector_at(point)).unwrap() - point).norm() < 1e-5);
+        }
+
+        let mut world = World::new();
+        world.objects[1] = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, wavelength: 0.5, ..WorldObject::new() };
+        world.objects[1].set_light_polarization();
+        world.objects_associations.insert(1, vec![]);
+        world.light_sources.push(1);
+
+        // a quarter-wave plate at 22.5 degrees turns the sphere a quarter of a turn around
+        // the point of the equator at 45 degrees, then a polarizer absorbs
+        let plate = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 7.0], radius: 0.5, polarizer_type: PolarizerType::GeneralWavePlateLinearRetarderTheta, jones_matrix: retarder_jones_matrix(PI / 2.0, PI / 8.0), ..WorldObject::new() };
+        let mut polarizer = WorldObject { center: [10.0, 10.0, 9.0], ..plate };
+        polarizer.set_jones_matrix(PolarizerType::LinearVertical, 0.0, 0.0, 0.0);
+
+        let (axis, angle) = rotation_on_sphere(plate.jones_matrix).unwrap();
+        assert!((axis - Vector3::new(1.0, 1.0, 0.0).normalize()).norm() < 1e-4);
+        assert!((angle - PI / 2.0).abs() < 1e-4);
+        assert!(rotation_on_sphere(polarizer.jones_matrix).is_none());
+
+        world.objects[3] = polarizer;
+        world.objects[2] = plate;
+        world.objects_associations.insert(2, vec![]);
+        world.objects_associations.insert(3, vec![]);
+
+        let steps = beam_trajectory(&world, 1, 16);
+        assert_eq!(steps.iter().map(|step| step.element_index).collect::<Vec<_>>(), vec![2, 3]);
+
+        // every point of the way of the plate stays at the same distance from its axis
+        for point in &steps[0].points {
+            assert!((point.dot(&axis) - Vector3::x().dot(&axis)).abs() < 1e-4);
+        }
+
+        let elliptical = sphere_point(plate.jones_matrix * world.objects[1].polarization).unwrap();
+        assert!((steps[0].points.last().unwrap() - elliptical).norm() < 1e-4);
+        assert!((steps[1].points.last().unwrap() - Vector3::new(-1.0, 0.0, 0.0)).norm() < 1e-4);
+    }
+
+    #[test]
+    fn the_field_traces_the_polarization_ellipse() {
+        // an ellipse at 30 degrees with an axis ratio of tan(15), right handed
+        let jones_vector = jones_vector_at(Vector3::new((PI / 3.0).cos() * (PI / 6.0).cos(), (PI / 3.0).sin() * (PI / 6.0).cos(), (PI / 6.0).sin()));
+        let stokes = stokes_parameters(jones_vector);
+        assert_eq!(handedness(stokes), Handedness::Right);
+
+        let cycle: Vec<Vector2<f32>> = (0..3600).map(|i| instantaneous_field(jones_vector, 2.0 * PI * i as f32 / 3600.0)).collect();
+        let longest = cycle.iter().max_by(|a, b| a.norm().total_cmp(&b.norm())).unwrap();
+        let shortest = cycle.iter().min_by(|a, b| a.norm().total_cmp(&b.norm())).unwrap();
+
+        assert!((longest.norm() - ellipticity_angle(stokes).cos()).abs() < 1e-3);
+        assert!((shortest.norm() - ellipticity_angle(stokes).sin().abs()).abs() < 1e-3);
+        assert!((longest.y.atan2(longest.x).rem_euclid(PI) - ellipse_azimuth(stokes)).abs() < 1e-2);
+        assert!((ellipse_azimuth(stokes) - PI / 6.0).abs() < 1e-4);
+
+        // right handed light turns clockwise in the (Ex, Ey) plane as t goes on
+        let turning = cycle[0].x * cycle[1].y - cycle[0].y * cycle[1].x;
+        assert!(turning < 0.0);
+    }
+
+    #[test]
+    fn sweeping_a_polarizer_follows_malus_law() {
+        let mut world = World::new();
+        world.objects[1] = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, wavelength: 0.5, ..WorldObject::new() };
+        world.objects[1].set_light_polarization();
+        world.objects[2] = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 7.0], radius: 0.5, ..WorldObject::new() };
+        world.objects[2].set_jones_matrix(PolarizerType::LinearTheta, 0.0, 0.0, 0.0);
+        world.objects[3] = WorldObject { object_type: ObjectType::Probe, center: [10.0, 10.0, 9.0], radius: 0.1, ..WorldObject::new() };
+
+        for index in 1..=3 {
+            world.objects_associations.insert(index, vec![]);
+        }
+        world.light_sources.push(1);
+
+        let sweep = Sweep { object_index: 2, parameter: SweepParameter::PolarizerAngle, start: 0.0, end: PI, steps: 13, target_index: 3, quantity: DetectorQuantity::Intensity };
+        let results = sweep.run(&world, 1.0);
+
+        assert_eq!(results.len(), 13);
+        for [angle, intensity] in &results {
+            assert!((intensity - results[0][1] * angle.cos().powi(2)).abs() < 1e-3 * results[0][1]);
+        }
+
+        // the lab itself is left as it was
+        assert_eq!(world.objects[2].polarizer_angle, 0.0);
+
+        let csv = sweep.csv(&results);
+        assert_eq!(csv.lines().count(), 14);
+        assert!(csv.lines().nth(1).unwrap().starts_with("0,"));
+    }
+
+    #[test]
+    fn the_optimizer_finds_the_quarter_wave_plate_angle() {
+        // rosenbrock's valley first, its minimum is at (1, 1)
+        let iterations = nelder_mead(|x| (1.0 - x[0]).powi(2) + 100.0 * (x[1] - x[0].powi(2)).powi(2), &[-1.0, 1.0], &[0.5, 0.5], 2000);
+        let best = iterations.last().unwrap();
+        assert!((best.values[0] - 1.0).abs() < 1e-2 && (best.values[1] - 1.0).abs() < 1e-2);
+        assert!(iterations.windows(2).all(|pair| pair[1].objective <= pair[0].objective));
+
+        let mut world = World::new();
+        world.objects[1] = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, wavelength: 0.5, ..WorldObject::new() };
+        world.objects[1].set_light_polarization();
+        world.objects[2] = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 7.0], radius: 0.5, ..WorldObject::new() };
+        world.objects[2].set_jones_matrix(PolarizerType::GeneralWavePlateLinearRetarderTheta, 0.1, PI / 2.0, 0.0);
+        world.objects[3] = WorldObject { object_type: ObjectType::Probe, center: [10.0, 10.0, 9.0], radius: 0.1, ..WorldObject::new() };
+
+        for index in 1..=3 {
+            world.objects_associations.insert(index, vec![]);
+        }
+        world.light_sources.push(1);
+
+        let optimization = Optimization {
+            variables: vec![OptimizationVariable { object_index: 2, parameter: SweepParameter::PolarizerAngle, min: 0.0, max: PI / 2.0 }],
+            probe_index: 3,
+            objective: Objective::Circular,
+            target_polarization: LightPolarizationType::CircularRightHand,
+            max_iterations: 200
+        };
+
+        let result = optimization.run(&world, 1.0).last().unwrap().clone();
+        assert!((result.values[0] - PI / 4.0).abs() < 1e-2);
+        assert!(result.objective < 1e-3);
+
+        // nothing changes until the result is applied
+        assert_eq!(world.objects[2].polarizer_angle, 0.1);
+        optimization.apply(&mut world, &result.values, &mut 1.0);
+        assert_eq!(world.objects[2].polarizer_angle, result.values[0]);
+        assert!(optimization.objective_value(&world, 1.0) < 1e-3);
+
+        // the cube size is a variable like any other, a smaller lab puts the probe closer
+        // to the waist of the beam
+        let scaling = Optimization { variables: vec![OptimizationVariable { object_index: 0, parameter: SweepParameter::CubeScalingFactor, min: 0.5, max: 4.0 }], objective: Objective::MaximizeIntensity, ..optimization };
+        let result = scaling.run(&world, 1.0).last().unwrap().clone();
+        assert!((result.values[0] - 0.5).abs() < 1e-2);
+
+        let mut cube_scaling_factor = 1.0;
+        scaling.apply(&mut world, &result.values, &mut cube_scaling_factor);
+        assert_eq!(cube_scaling_factor, result.values[0]);
+    }
+
+    #[test]
+    fn fringe_analysis_matches_crossing_beams() {
+        // fringes 0.37 mm apart sampled every 10 μm
+        let synthetic: Vec<f32> = (0..200).map(|i| 1.0 + 0.5 * (2.0 * PI * i as f32 * 1e-5 / 3.7e-4).cos()).collect();
+        let analysis = analyze_fringes(&synthetic, 1e-5);
+        assert!((analysis.visibility - 0.5).abs() < 1e-2);
+        assert!((analysis.period.unwrap() / 3.7e-4 - 1.0).abs() < 0.01);
+
+        // two beams crossing at 20 mrad, fringes λ / α = 5 cm apart along x
+        let mut world = World::new();
+        let light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, wavelength: 0.001, ..WorldObject::new() };
+        world.objects[1] = light;
+        world.objects[2] = WorldObject { rotation: [PI - 0.02, 0.0], ..light };
+        world.objects[3] = WorldObject { object_type: ObjectType::Detector, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 12.0], radius: 0.4, ..WorldObject::new() };
+
+        for index in 1..=3 {
+            world.objects[index].set_light_polarization();
+            world.objects_associations.insert(index, vec![]);
+        }
+        world.light_sources = vec![1, 2];
+
+        let detector = world.objects[3];
+        let [horizontal, _, _] = detector.local_axes();
+        let expected = expected_fringes(&world, Vector3::from(detector.center), horizontal, 1.0, Some(3)).unwrap();
+        assert!((expected.period / (0.001 / (2.0 * (0.01f32).sin())) - 1.0).abs() < 0.01);
+        assert!((expected.visibility - 1.0).abs() < 1e-3);
+
+        let mut readout = DetectorReadout::new(&detector);
+        readout.record(&world, 3, 1.0);
+
+        let (intensities, spacing) = detector_profile(&readout, sensor_side(&detector), FringeAxis::Horizontal);
+        let analysis = analyze_fringes(&intensities, spacing);
+        assert!((analysis.period.unwrap() / expected.period - 1.0).abs() < 0.03);
+        assert!(analysis.visibility > 0.8);
+    }
+
+    #[test]
+    fn line_profiles_sample_the_light_on_a_wall() {
+        // a point picked on the screen lands where the ray through it hits the wall
+        let camera = Camera { look_direction: egui::Vec2::new(0.3, -0.2), position: Vector3::new(10.0, 10.0, 5.0) };
+        let wall = WorldObject { object_type: ObjectType::RoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 12.0], radius: 0.5, ..WorldObject::new() };
+
+        let target = Vector3::new(10.1, 9.9, 12.0);
+        let screen_position = camera.screen_position(target).unwrap();
+        let direction = camera.ray_direction(screen_position);
+        let picked = camera.position + direction * surface_distance(&wall, camera.position, direction).unwrap();
+        assert!((picked - target).norm() < 1e-4);
+
+        // the same crossing beams of the fringe analysis, sampled across the wall
+        let mut world = World::new();
+        let light = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, wavelength: 0.001, ..WorldObject::new() };
+        world.objects[1] = light;
+        world.objects[2] = WorldObject { rotation: [PI - 0.02, 0.0], ..light };
+        world.objects[3] = wall;
+
+        for index in 1..=3 {
+            world.objects[index].set_light_polarization();
+            world.objects_associations.insert(index, vec![]);
+        }
+        world.light_sources = vec![1, 2];
+
+        let start = Vector3::new(9.7, 10.0, 12.0);
+        let end = Vector3::new(10.3, 10.0, 12.0);
+        let samples = sample_profile(&world, 3, start, end, 301, 2.0);
+
+        assert_eq!(samples.len(), 301);
+        assert!((samples.last().unwrap().distance - 1.2).abs() < 1e-4);
+        assert!(samples.iter().all(|sample| sample.phase.is_some() && sample.stokes[3].abs() < 1e-3 * sample.stokes[0].max(1e-12)));
+
+        let intensities: Vec<f32> = samples.iter().map(|sample| sample.stokes[0]).collect();
+        let expected = expected_fringes(&world, (start + end) / 2.0, Vector3::x(), 2.0, Some(3)).unwrap();
+        let analysis = analyze_fringes(&intensities, samples[1].distance);
+        assert!((analysis.period.unwrap() / expected.period - 1.0).abs() < 0.03);
+
+        let csv = profile_csv(&samples, true, true);
+        assert!(csv.starts_with("distance (m),intensity,phase Ex (rad),phase Ey (rad),S1,S2,S3\n"));
+        assert_eq!(csv.lines().count(), 302);
+        assert_eq!(csv.lines().nth(1).unwrap().split(',').count(), 7);
+    }
+
+    #[test]
+    fn cross_sections_show_the_field_between_objects() {
+        // a slice lying along a horizontal beam going towards +z
+        let mut world = World::new();
+        world.objects[1] = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, polarization_type: LightPolarizationType::LinearHorizontal, wavelength: 0.5, ..WorldObject::new() };
+        world.objects[1].set_light_polarization();
+        world.objects_associations.insert(1, vec![]);
+        world.light_sources = vec![1];
+
+        let section = CrossSection { mode: CrossSectionMode::Polarization, center: [10.0, 10.0, 8.0], ..CrossSection::new() };
+        let [horizontal, normal, vertical] = section.local_axes();
+        assert!((normal - Vector3::y()).norm() < 1e-6 && horizontal.dot(&vertical).abs() < 1e-6);
+        assert!((section.point_at(0.0, 1.0) - Vector3::new(10.0, 10.0, 9.0)).norm() < 1e-5);
+
+        let (stokes, _) = section_fields(&world, section.point_at(0.0, 0.0), 1.0);
+        assert!(stokes[0] > 0.0 && (stokes[1] / stokes[0] - 1.0).abs() < 1e-4);
+
+        // horizontal light is red, as bright as its intensity lets it be
+        let brightness = (stokes[0] * section.exposure).min(1.0);
+        let color = section_color(CrossSectionMode::Polarization, stokes, 0.0, section.exposure);
+        assert!((color[0] - brightness).abs() < 1e-4 && (color[1] - brightness / 2.0).abs() < 1e-4 && (color[2] - brightness / 2.0).abs() < 1e-4);
+
+        // the phase moves by 2π over a wavelength along the beam
+        let (_, phase) = section_fields(&world, section.point_at(0.0, 0.0), 1.0);
+        let (_, quarter_wave) = section_fields(&world, section.point_at(0.0, 0.125), 1.0);
+        let difference = (quarter_wave - phase).rem_euclid(2.0 * PI);
+        assert!((difference.min(2.0 * PI - difference) - PI / 2.0).abs() < 0.05);
+
+        assert_eq!(section_color(CrossSectionMode::Intensity, [0.0; 4], 0.0, 1.0), [0.0; 3]);
+        assert_eq!(section_color(CrossSectionMode::Intensity, [10.0, 0.0, 0.0, 0.0], 0.0, 1.0), [1.0; 3]);
+        assert!(hue_color(0.0, 1.0).iter().zip([1.0, 0.0, 0.0]).all(|(a, b)| (a - b).abs() < 1e-5));
+        assert!(hue_color(1.0 / 3.0, 0.5).iter().zip([0.0, 0.5, 0.0]).all(|(a, b)| (a - b).abs() < 1e-5));
+    }
+
+    #[test]
+    fn beam_paths_follow_the_light_through_its_elements() {
+        // horizontal light through a quarter-wave plate at 45° and a horizontal polarizer,
+        // stopped by a wall
+        let mut world = World::new();
+        world.objects[1] = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, polarization_type: LightPolarizationType::LinearHorizontal, wavelength: 0.5, ..WorldObject::new() };
+        world.objects[1].set_light_polarization();
+        world.objects[2] = WorldObject { object_type: ObjectType::OpticalObjectRoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 6.0], polarizer_type: PolarizerType::GeneralWavePlateLinearRetarderTheta, jones_matrix: retarder_jones_matrix(PI / 2.0, PI / 4.0), ..WorldObject::new() };
+        world.objects[3] = WorldObject { center: [10.0, 10.0, 8.0], ..world.objects[2] };
+        world.objects[3].set_jones_matrix(PolarizerType::LinearHorizontal, 0.0, 0.0, 0.0);
+        world.objects[4] = WorldObject { object_type: ObjectType::RoundWall, rotation: [0.0, PI / 2.0], center: [10.0, 10.0, 12.0], ..WorldObject::new() };
+
+        for index in 1..=4 {
+            world.objects_associations.insert(index, vec![]);
+        }
+        world.light_sources = vec![1];
+
+        let segments = beam_path(&world, 1);
+        assert_eq!(segments.iter().map(|segment| segment.end_index).collect::<Vec<_>>(), vec![Some(2), Some(3), Some(4)]);
+        assert!((segments[1].start - Vector3::new(10.0, 10.0, 6.0)).norm() < 1e-4 && (segments[2].end - Vector3::new(10.0, 10.0, 12.0)).norm() < 1e-4);
+        assert!((segments[1].start_distance - 2.0).abs() < 1e-4);
+
+        // circular between the plate and the polarizer, half of it gets through
+        let between = stokes_parameters(segments[1].polarization);
+        assert!((between[3].abs() / between[0] - 1.0).abs() < 1e-3);
+        let after = stokes_parameters(segments[2].polarization);
+        assert!((after[0] / between[0] - 0.5).abs() < 1e-3 && (after[1] / after[0] - 1.0).abs() < 1e-3);
+
+        // the glyph of circular light is a circle around the axis
+        let glyph = polarization_glyph(&world.objects[1], segments[1].polarization, Vector3::new(10.0, 10.0, 7.0), 0.3, 32);
+        assert!(glyph.iter().all(|point| ((point - Vector3::new(10.0, 10.0, 7.0)).norm() - 0.3).abs() < 1e-4 && (point.z - 7.0).abs() < 1e-5));
+
+        // crossing the polarizer with a vertical one ends the path there
+        world.objects[4] = WorldObject { center: [10.0, 10.0, 10.0], ..world.objects[3] };
+        world.objects[4].set_jones_matrix(PolarizerType::LinearVertical, 0.0, 0.0, 0.0);
+        assert_eq!(beam_path(&world, 1).last().unwrap().end_index, Some(4));
+
+        // the envelope grows by √2 over a rayleigh range
+        let rayleigh_range = PI * BEAM_WAIST * BEAM_WAIST / 0.5;
+        assert!((beam_radius(&world.objects[1], 0.0, 1.0, 2.0) - BEAM_WAIST / 2.0).abs() < 1e-5);
+        assert!((beam_radius(&world.objects[1], rayleigh_range / 2.0, 1.0, 2.0) / (BEAM_WAIST / 2.0) - 2.0f32.sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn false_colors_follow_the_render_mode() {
+        // the fits end where matplotlib's colormaps end
+        let close = |a: [f32; 3], b: [f32; 3]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 0.02);
+        assert!(close(colormap_color(Colormap::Viridis, 0.0), [0.267, 0.005, 0.329]) && close(colormap_color(Colormap::Viridis, 1.0), [0.993, 0.906, 0.144]));
+        assert!(close(colormap_color(Colormap::Inferno, 0.0), [0.001, 0.0, 0.014]) && close(colormap_color(Colormap::Inferno, 1.0), [0.988, 0.998, 0.645]));
+        assert!(close(colormap_color(Colormap::Jet, 0.5), [0.5, 1.0, 0.5]) && colormap_color(Colormap::Grayscale, 2.0) == [1.0; 3]);
+        assert!(diverging_color(1.0) == [1.0, 0.0, 0.0] && diverging_color(0.0) == [1.0; 3] && diverging_color(-1.0) == [0.0, 0.0, 1.0]);
+
+        let mut settings = RenderSettings { exposure: 1.0, ..RenderSettings::new() };
+        let horizontal = [(0.5, Vector2::new(Complex::new(0.0, 1.0), Complex::new(0.0, 0.0)))];
+        let circular = [(0.5, Vector2::new(Complex::new(1.0, 0.0), Complex::new(0.0, -1.0)).unscale(2.0f32.sqrt()))];
+
+        settings.mode = RenderMode::S1;
+        assert_eq!(render_color(&settings, &horizontal), [1.0, 0.0, 0.0]);
+        settings.mode = RenderMode::S3;
+        assert_eq!(render_color(&settings, &horizontal), [1.0; 3]);
+
+        // a phase of π/2 is three quarters of the way around the hues from -π
+        settings.mode = RenderMode::PhaseEx;
+        assert!(close(render_color(&settings, &horizontal), [0.5, 0.0, 1.0]));
+        settings.mode = RenderMode::PhaseEy;
+        assert_eq!(render_color(&settings, &horizontal), [0.0; 3]);
+
+        // horizontal light is red, circular light is white whatever its azimuth
+        settings.mode = RenderMode::AzimuthEllipticity;
+        assert!(close(render_color(&settings, &horizontal), [1.0, 0.0, 0.0]));
+        assert!(close(render_color(&settings, &circular), [1.0; 3]));
+
+        settings.mode = RenderMode::DegreeOfPolarization;
+        settings.colormap = Colormap::Grayscale;
+        let partially_polarized = [
+            (0.5, Vector2::new(Complex::new(0.75f32.sqrt(), 0.0), Complex::new(0.0, 0.0))),
+            (0.6, Vector2::new(Complex::new(0.0, 0.0), Complex::new(0.25f32.sqrt(), 0.0)))
+        ];
+        assert!(close(render_color(&settings, &partially_polarized), [0.5; 3]));
+
+        // the shader uses the same numbers
+        let shader = include_str!("gpu-code/main.frag");
+        assert!(shader.contains(&format!("const uint RENDER_AZIMUTH_ELLIPTICITY = uint({});", RenderMode::AzimuthEllipticity as u32)));
+        assert!(shader.contains(&format!("const uint COLORMAP_JET = uint({});", Colormap::Jet as u32)));
+    }
+
+    #[test]
+    fn time_resolved_views_show_the_field_oscillating() {
+        let mut settings = RenderSettings { mode: RenderMode::FieldEx, exposure: 1.0, ..RenderSettings::new() };
+        let horizontal = [(0.5, Vector2::new(Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)))];
+
+        // e^(-iωt) goes around once every unit of field time
+        assert_eq!(render_color(&settings, &horizontal), [1.0, 0.0, 0.0]);
+        settings.advance(0.5);
+        assert!(render_color(&settings, &horizontal).iter().zip([0.0, 0.0, 1.0]).all(|(a, b)| (a - b).abs() < 1e-5));
+        settings.field_paused = true;
+        settings.advance(0.25);
+        assert_eq!(settings.field_time, 0.5);
+
+        // wavefronts move forward a quarter of a wavelength every quarter of a cycle
+        let mut world = World::new();
+        world.objects[1] = WorldObject { object_type: ObjectType::LightSource, rotation: [PI, 0.0], center: [10.0, 10.0, 4.0], radius: 0.1, polarization_type: LightPolarizationType::LinearHorizontal, wavelength: 0.5, ..WorldObject::new() };
+        world.objects[1].set_light_polarization();
+        world.objects_associations.insert(1, vec![]);
+        world.light_sources = vec![1];
+
+        for z in [6.0, 6.1, 6.3] {
+            let here = fields_by_wavelength(&world, Vector3::new(10.0, 10.0, z), 1.0, None);
+            let ahead = fields_by_wavelength(&world, Vector3::new(10.0, 10.0, z + 0.125), 1.0, None);
+            let scale = here[0].1.norm();
+
+            assert!((summed_instantaneous_field(&here, 0.0) - summed_instantaneous_field(&ahead, 0.25)).norm() < 1e-2 * scale);
+        }
+
+        // lights 10% apart in wavelength cancel out after 5.5 cycles of the first one
+        let beating = [(1.0, horizontal[0].1), (1.1, horizontal[0].1)];
+        assert!((summed_instantaneous_field(&beating, 0.0).x - 2.0).abs() < 1e-5);
+        assert!(summed_instantaneous_field(&beating, 5.5).x.abs() < 1e-3);
+
+        let shader = include_str!("gpu-code/main.frag");
+        assert!(shader.contains(&format!("const uint RENDER_FIELD_EY = uint({});", RenderMode::FieldEy as u32)));
+    }
 }
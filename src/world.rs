@@ -4,7 +4,7 @@ use nalgebra::{Complex, ComplexField, Matrix2, Vector2, Vector3};
 use web_sys::console;
 use serde::{Deserialize, Serialize};
 
-use crate::{camera::{rotate3d_x, rotate3d_y}, gpu_hash::GPUHashTable, util::i32_to_u32_vec};
+use crate::{camera::{rotate3d_x, rotate3d_y}, coatings::{Coating, MAX_COATING_LAYERS, MAX_COATING_MATERIALS}, gpu_hash::GPUHashTable, materials::{MaterialLibrary, MaterialName, GPU_MATERIAL_SIZE}, displacer::displacer_bounding_radius, electro_optics::{drive_voltage, electro_optic_jones_matrix, electro_optic_retardance, is_electro_optic}, etalon::etalon_transmission, prism::prism_bounding_radius, spatial::{local_jones_matrix, ElementMap}, util::i32_to_u32_vec};
 
 // WorldObject.type possible values
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
@@ -16,6 +16,12 @@ pub enum ObjectType {
     OpticalObjectCube = 4,          // An object represented using a jones matrix
     OpticalObjectSquareWall = 5,    // An object represented using a jones matrix
     OpticalObjectRoundWall = 6,     // An object represented using a jones matrix
+    Aperture = 7,                   // Opaque round screen with openings that diffract light
+    Grating = 8,                    // Round grating that splits light into diffraction orders
+    Prism = 9,                      // Triangular prism that refracts light depending on its wavelength
+    BeamDisplacer = 10,             // Block of a birefringent crystal that splits light into two rays
+    Detector = 11,                  // Round screen with a grid of pixels that records the light
+    Probe = 12,                     // Small sphere that measures the polarization of the light
 }
 
 // Needed for the drop down list
@@ -29,6 +35,12 @@ impl Display for ObjectType {
             Self::OpticalObjectCube => write!(f, "Optical object (cube)"),
             Self::OpticalObjectSquareWall => write!(f, "Optical object (square)"),
             Self::OpticalObjectRoundWall  => write!(f, "Optical object (round)"),
+            Self::Aperture => write!(f, "Aperture screen (round)"),
+            Self::Grating => write!(f, "Diffraction grating (round)"),
+            Self::Prism => write!(f, "Prism (triangular)"),
+            Self::BeamDisplacer => write!(f, "Beam displacer (birefringent crystal)"),
+            Self::Detector => write!(f, "Detector screen (round)"),
+            Self::Probe => write!(f, "Polarimeter probe (sphere)"),
         }
     }
 }
@@ -43,7 +55,10 @@ pub enum LightPolarizationType {
 
     CircularRightHand = 4,
     CircularLeftHand = 5,
-    NotPolarized = 6
+    NotPolarized = 6,
+
+    // whatever was picked on the poincaré sphere, the jones vector is kept as it is
+    Custom = 7
 }
 
 #[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
@@ -66,7 +81,58 @@ pub enum PolarizerType {
 
     GeneralWavePlateLinearRetarderTheta = 11,
 
-    ArbitraryBirefringentMaterialTheta = 12
+    ArbitraryBirefringentMaterialTheta = 12,
+
+    NeutralDensityFilter = 13,
+    AbsorbingSlab = 14,
+    PartialLinearPolarizerTheta = 15,
+
+    OpticalRotator = 16,
+    FaradayRotator = 17,
+
+    ChromaticRetarder = 18,
+
+    FabryPerotEtalon = 19,
+
+    QPlate = 20,
+    VortexRetarder = 21,
+    ImageElement = 22,
+
+    PhotoelasticSample = 23,
+
+    LiquidCrystalCell = 24,
+    PockelsCell = 25
+}
+
+// WorldObject.drive_waveform possible values
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum DriveWaveform {
+    Constant = 0,
+    Sine = 1,
+    Square = 2
+}
+
+// WorldObject.stress_field possible values
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum StressField {
+    DiskCompression = 0,
+    BeamBending = 1
+}
+
+// WorldObject.aperture_type possible values
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum ApertureType {
+    SingleSlit = 0,
+    MultipleSlits = 1,
+    CircularPinhole = 2,
+    Rectangular = 3
+}
+
+// WorldObject.grating_type possible values
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum GratingType {
+    Transmission = 0,
+    Reflection = 1
 }
 
 #[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
@@ -87,6 +153,49 @@ impl Display for Alignment {
     }
 }
 
+// Needed for the drop down list
+impl Display for ApertureType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SingleSlit => write!(f, "Single slit"),
+            Self::MultipleSlits => write!(f, "Multiple slits"),
+            Self::CircularPinhole => write!(f, "Circular pinhole"),
+            Self::Rectangular => write!(f, "Rectangular aperture"),
+        }
+    }
+}
+
+// Needed for the drop down list
+impl Display for DriveWaveform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Constant => write!(f, "Constant"),
+            Self::Sine => write!(f, "Sine"),
+            Self::Square => write!(f, "Square"),
+        }
+    }
+}
+
+// Needed for the drop down list
+impl Display for StressField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DiskCompression => write!(f, "Disk under diametral compression"),
+            Self::BeamBending => write!(f, "Beam in pure bending"),
+        }
+    }
+}
+
+// Needed for the drop down list
+impl Display for GratingType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transmission => write!(f, "Transmission grating"),
+            Self::Reflection => write!(f, "Reflection grating"),
+        }
+    }
+}
+
 // Needed for the drop down list
 impl Display for LightPolarizationType {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -100,7 +209,9 @@ impl Display for LightPolarizationType {
             Self::CircularRightHand => write!(f, "Right circular"),
             Self::CircularLeftHand => write!(f, "Left circular"),
 
-            Self::NotPolarized => write!(f, "Not polarized")
+            Self::NotPolarized => write!(f, "Not polarized"),
+
+            Self::Custom => write!(f, "Custom (from the Poincaré sphere)")
         }
     }
 }
@@ -128,6 +239,26 @@ impl Display for PolarizerType {
             Self::GeneralWavePlateLinearRetarderTheta => write!(f, "General Waveplate (Linear Phase Retarder)"),
 
             Self::ArbitraryBirefringentMaterialTheta => write!(f, "Arbitrary birefringent material (Elliptical phase retarder)"),
+
+            Self::NeutralDensityFilter => write!(f, "Neutral density filter"),
+            Self::AbsorbingSlab => write!(f, "Absorbing slab (Beer-Lambert)"),
+            Self::PartialLinearPolarizerTheta => write!(f, "Non-ideal linear polarizer rotated θ degrees"),
+
+            Self::OpticalRotator => write!(f, "Optical rotator (optically active medium)"),
+            Self::FaradayRotator => write!(f, "Faraday rotator"),
+
+            Self::ChromaticRetarder => write!(f, "Chromatic retarder (birefringent plate)"),
+
+            Self::FabryPerotEtalon => write!(f, "Fabry-Pérot etalon (multi-pass interference)"),
+
+            Self::QPlate => write!(f, "Q-plate"),
+            Self::VortexRetarder => write!(f, "Vortex retarder"),
+            Self::ImageElement => write!(f, "Element driven by images (retardance, fast axis and amplitude maps)"),
+
+            Self::PhotoelasticSample => write!(f, "Photoelastic sample (stress-induced birefringence)"),
+
+            Self::LiquidCrystalCell => write!(f, "Liquid crystal cell"),
+            Self::PockelsCell => write!(f, "Pockels cell"),
         }
     }
 }
@@ -145,7 +276,13 @@ pub struct WorldObject {
     pub polarization: Vector2<Complex<f32>>,
     pub jones_matrix: Matrix2<Complex<f32>>,
     pub polarization_type: LightPolarizationType,
+    // lights, in meters like the distances scaled by cube_scaling_factor, it only sets
+    // how the light propagates
     pub wavelength: f32,
+    // lights, in micrometers, the wavelength the refractive index of materials and the
+    // dispersion of coatings, chromatic retarders and etalons are looked up at, the lab
+    // is far too small for `wavelength` to be a real one
+    pub material_wavelength: f32,
 
     // these next 3 should probably be an option for correctness
     pub aligned_to_object: usize,
@@ -154,9 +291,123 @@ pub struct WorldObject {
     // i want to make a vector but it doesn't implement copy
     // sometimes i regret ever trying to use rust at all
     // for now i'll just limit the amount of possibly aligned objects to 1
-    pub object_aligned_to_self: usize
+    pub object_aligned_to_self: usize,
+
+    // name of the material the object is made of, see materials.rs
+    pub material: MaterialName,
+    // thin-film coating on its surfaces, only optical objects (round) and
+    // prisms use it for now, see coatings.rs
+    pub coating: Coating,
+
+    // Will only be relevant if it's an aperture screen, sizes are in
+    // cubes just like the radius, the slits are long along the screen's
+    // vertical axis and are laid out along its horizontal axis
+    pub aperture_type: ApertureType,
+    // slit width, rectangle width or pinhole diameter
+    pub aperture_width: f32,
+    // slit length or rectangle height
+    pub aperture_height: f32,
+    pub slit_count: u32,
+    // distance between the centers of two neighbouring slits
+    pub slit_pitch: f32,
+
+    // Will only be relevant if it's an optical object, these are the values
+    // last used to compute its jones matrix so that it can be recomputed
+    pub polarizer_type: PolarizerType,
+    pub polarizer_angle: f32,
+    pub relative_phase_retardation: f32,
+    pub circularity: f32,
+    // neutral density filters, transmittance is 10^-OD
+    pub optical_density: f32,
+    // absorbing slabs, transmittance is e^(-αd), α in 1/meters and d in meters
+    pub absorption_coefficient: f32,
+    pub slab_thickness: f32,
+    // non-ideal polarizers, intensity transmittance along the transmission axis (k1)
+    // and the ratio between it and the one along the blocking axis (k1/k2)
+    pub principal_transmittance: f32,
+    pub extinction_ratio: f32,
+    // optical rotators, rotation is [α] * c * L, [α] in degrees / (dm * g/mL),
+    // c in g/mL (1 for crystals like quartz) and L in dm
+    pub specific_rotation: f32,
+    pub concentration: f32,
+    pub rotator_path_length: f32,
+    // faraday rotators, rotation is V * B * L, V in rad / (T * m), B in teslas
+    // along the normal of the object (negative goes against it) and L in meters
+    pub verdet_constant: f32,
+    pub magnetic_field: f32,
+    pub faraday_length: f32,
+    // chromatic retarders, the retardance is 2π * Δn(λ) * d / λ with
+    // Δn(λ) = Δn + B / λ², these read wavelengths in micrometers so the
+    // visible range goes from 0.38 to 0.78, thickness is in micrometers too
+    pub birefringence: f32,
+    pub birefringence_dispersion: f32,
+    pub retarder_thickness: f32,
+    // what the plate was designed for, a retardance of (order + waves) waves
+    // at the design wavelength, order 0 is a zero-order plate
+    pub retarder_design_wavelength: f32,
+    pub retarder_waves: f32,
+    pub retarder_order: u32,
+    // fabry-pérot etalons, intensity reflectance of each mirror, gap between them in
+    // micrometers and refractive index of what fills it relative to the medium
+    pub etalon_reflectance: f32,
+    pub etalon_gap: f32,
+    pub etalon_index: f32,
+    // spatially varying elements (see spatial.rs), the fast axis of q-plates turns q
+    // (the topological charge) times as fast as the azimuth on the disk, vortex
+    // retarders use it as their order m, the retardance is the one of q-plates and
+    // the uniform one of image elements, polarizer_angle is the fast axis at azimuth 0
+    pub topological_charge: f32,
+    pub spatial_retardance: f32,
+    // photoelastic samples, the load is the force squeezing the disk (N) or the bending
+    // moment of the beam (N m), the size is the diameter of the disk or the height of
+    // the beam in mm and it fills the whole object, the thickness is in mm and the
+    // stress-optic coefficient in brewsters (10^-12 / Pa)
+    pub stress_field: StressField,
+    pub applied_load: f32,
+    pub stress_optic_coefficient: f32,
+    pub sample_size: f32,
+    pub sample_thickness: f32,
+    // liquid crystal cells, retardance with the molecules lying flat (radians), the
+    // threshold voltage and how fast they tilt after it (V)
+    pub lc_max_retardance: f32,
+    pub lc_threshold_voltage: f32,
+    pub lc_voltage_scale: f32,
+    // pockels cells, voltage that gives half a wave of retardance
+    pub half_wave_voltage: f32,
+    // voltage applied to electro-optic elements, offset + amplitude w(2π f t) where w is
+    // the waveform and t the time of the app, in V and Hz
+    pub drive_waveform: DriveWaveform,
+    pub drive_offset: f32,
+    pub drive_amplitude: f32,
+    pub drive_frequency: f32,
+
+    // Will only be relevant if it's a detector, columns and rows of its grid
+    pub detector_pixels: [u32; 2],
+
+    // Will only be relevant if it's a diffraction grating, the grooves go
+    // along the vertical axis of the grating
+    pub grating_type: GratingType,
+    // grooves per meter, same units as the wavelength
+    pub groove_density: f32,
+    // in radians
+    pub blaze_angle: f32,
+
+    // Will only be relevant if it's a prism, in radians
+    pub apex_angle: f32,
+
+    // Will only be relevant if it's a beam displacer, the optic axis is given by
+    // its angle from the normal and its angle around the normal measured from
+    // the horizontal axis, `material` gives the ordinary index and this one the
+    // extraordinary index, the length is in cubes
+    pub optic_axis: [f32; 2],
+    pub extraordinary_material: MaterialName,
+    pub displacer_length: f32
 }
 
+// Amount of u32s each object takes in the list sent to the gpu,
+// must match OBJECT_SIZE in main.frag
+pub const GPU_OBJECT_SIZE: usize = 56 + 2 * MAX_COATING_LAYERS + 2 * GPU_MATERIAL_SIZE;
+
 #[derive(Debug, Clone)]
 pub struct World {
     pub hash_map: GPUHashTable,
@@ -167,6 +418,11 @@ pub struct World {
     pub light_sources: Vec<u32>,
     pub objects_stack: Vec<usize>,
     pub objects_associations: HashMap<usize, Vec<Vector3<u32>>>,
+    pub materials: MaterialLibrary,
+    // material the lab is filled with, light propagates with its n(λ)
+    pub medium: MaterialName,
+    // maps of the image elements, by the index of the object they belong to
+    pub element_maps: HashMap<usize, ElementMap>,
 }
 
 impl World {
@@ -177,8 +433,73 @@ impl World {
             aligned_objects: HashSet::new(),
             light_sources: vec![],
             objects_stack: (1..10).collect(),
-            objects_associations: HashMap::new()
+            objects_associations: HashMap::new(),
+            materials: MaterialLibrary::new(),
+            medium: MaterialName::new("Air"),
+            element_maps: HashMap::new()
+        }
+    }
+
+    // λ in micrometers
+    pub fn medium_refractive_index(&self, wavelength: f32) -> f32 {
+        self.materials.refractive_index(&self.medium, wavelength)
+    }
+
+    pub fn get_gpu_compatible_medium(&self) -> [u32; GPU_MATERIAL_SIZE] {
+        self.materials.gpu_definition(&self.medium)
+    }
+
+    // every different material used by the coatings of the objects, the layers
+    // sent to the gpu point into this list
+    pub fn coating_materials(&self) -> Vec<MaterialName> {
+        let mut coating_materials: Vec<MaterialName> = vec![];
+
+        for layer in self.objects.iter().flat_map(|object| object.coating.layers()) {
+            if !coating_materials.contains(&layer.material) && coating_materials.len() < MAX_COATING_MATERIALS {
+                coating_materials.push(layer.material);
+            }
+        }
+
+        coating_materials
+    }
+
+    pub fn get_gpu_compatible_coating_materials(&self) -> Vec<u32> {
+        let mut coating_materials = self.coating_materials();
+        coating_materials.resize(MAX_COATING_MATERIALS, MaterialName::new("Vacuum"));
+
+        coating_materials.iter().flat_map(|material| self.materials.gpu_definition(material)).collect()
+    }
+
+    // objects with maps in the order their maps are sent to the gpu, the layer of
+    // the texture array of the maps of an object is its position here
+    pub fn element_map_layers(&self) -> Vec<usize> {
+        let mut layers: Vec<usize> = self.element_maps.keys().copied().collect();
+        layers.sort();
+        layers
+    }
+
+    // rgba pixels of every layer one after the other, there's always at least
+    // one so that the texture can be made
+    pub fn get_gpu_compatible_element_maps(&self) -> (Vec<u8>, usize) {
+        let layers = self.element_map_layers();
+        if layers.is_empty() {
+            return (ElementMap::new().pixels, 1);
+        }
+
+        (layers.iter().flat_map(|object_index| self.element_maps[object_index].pixels.iter().copied()).collect(), layers.len())
+    }
+
+    // sets the jones matrix of every electro-optic element to the one of the voltage at
+    // `time`, returns whether any of them changes with time (so it should keep repainting)
+    pub fn drive_electro_optic_elements(&mut self, time: f64) -> bool {
+        let mut animated = false;
+
+        for object in self.objects.iter_mut().filter(|object| is_electro_optic(object)) {
+            object.jones_matrix = electro_optic_jones_matrix(object, time);
+            animated |= object.drive_waveform != DriveWaveform::Constant && object.drive_amplitude != 0.0;
         }
+
+        animated
     }
 
     pub fn remove_object(&mut self, object_index: usize) {
@@ -210,6 +531,7 @@ impl World {
         self.objects[object_index] = WorldObject::new();
         self.objects_stack.push(object_index);
         self.objects_associations.remove(&object_index);
+        self.element_maps.remove(&object_index);
         console::log_1(&format!("{:?}", self.objects_associations).into());
     }
 
@@ -257,11 +579,17 @@ impl World {
             }
 
             ObjectType::RoundWall              |
+            ObjectType::Detector               |
+            ObjectType::Probe                  |
             ObjectType::OpticalObjectRoundWall |
+            ObjectType::Aperture               |
+            ObjectType::Grating                |
+            ObjectType::Prism                  |
+            ObjectType::BeamDisplacer          |
             ObjectType::SquareWall             |
             ObjectType::OpticalObjectSquareWall => {
                 let center = [position[0] as u32, position[1] as u32, position[2] as u32];
-                let truncated_radius = object_definition.radius as u32 + 1;
+                let truncated_radius = object_definition.footprint_radius();
 
                 for x in (center[0] - truncated_radius)..=(center[0] + truncated_radius) {
                     for y in (center[1] - truncated_radius)..=(center[1] + truncated_radius) {
@@ -295,7 +623,7 @@ impl World {
         self.objects_associations.remove(&object_index);
 
         let center = [object_definition.center[0] as u32, object_definition.center[1] as u32, object_definition.center[2] as u32];
-        let truncated_radius = object_definition.radius as u32 + 1;
+        let truncated_radius = object_definition.footprint_radius();
         let mut object_positions = vec![];
 
         // this can only be used on non cube objects
@@ -312,8 +640,28 @@ impl World {
     }
 
     pub fn get_gpu_compatible_world_objects_list(&self) -> Vec<u32> {
-        self.objects.iter().flat_map(|object| {
-            [
+        let element_map_layers = self.element_map_layers();
+
+        self.objects.iter().enumerate().flat_map(|(object_index, object)| {
+            let material = self.materials.gpu_definition(&object.material);
+            let extraordinary_material = self.materials.gpu_definition(&object.extraordinary_material);
+
+            // index into coating_materials() and thickness of every layer, layers made of
+            // materials that didn't fit in the list get an invalid index and act like vacuum
+            let coating_materials = self.coating_materials();
+            let mut coating = [0; 1 + 2 * MAX_COATING_LAYERS];
+            coating[0] = object.coating.layers().len() as u32;
+
+            for (i, layer) in object.coating.layers().iter().enumerate() {
+                coating[1 + 2 * i] = coating_materials.iter().position(|material| *material == layer.material).map_or(u32::MAX, |index| index as u32);
+                coating[2 + 2 * i] = layer.thickness.to_bits();
+            }
+
+            // layer of the texture array with its maps and which of them it uses
+            let element_map_layer = element_map_layers.iter().position(|index| *index == object_index).map_or(u32::MAX, |layer| layer as u32);
+            let element_map_channels = self.element_maps.get(&object_index).map_or(0, |map| map.channels_mask());
+
+            let definition = [
                 object.object_type as u32,
 
                 object.rotation[0].to_bits(),
@@ -338,20 +686,63 @@ impl World {
                 object.polarization[1].real().to_bits(),
                 object.polarization[1].imaginary().to_bits(),
 
-                object.jones_matrix[0].real().to_bits(),
-                object.jones_matrix[0].imaginary().to_bits(),
+                // nalgebra stores matrices by columns, main.frag expects them by rows
+                object.jones_matrix[(0, 0)].real().to_bits(),
+                object.jones_matrix[(0, 0)].imaginary().to_bits(),
 
-                object.jones_matrix[1].real().to_bits(),
-                object.jones_matrix[1].imaginary().to_bits(),
+                object.jones_matrix[(0, 1)].real().to_bits(),
+                object.jones_matrix[(0, 1)].imaginary().to_bits(),
 
-                object.jones_matrix[2].real().to_bits(),
-                object.jones_matrix[2].imaginary().to_bits(),
+                object.jones_matrix[(1, 0)].real().to_bits(),
+                object.jones_matrix[(1, 0)].imaginary().to_bits(),
 
-                object.jones_matrix[3].real().to_bits(),
-                object.jones_matrix[3].imaginary().to_bits(),
+                object.jones_matrix[(1, 1)].real().to_bits(),
+                object.jones_matrix[(1, 1)].imaginary().to_bits(),
 
                 object.wavelength.to_bits(),
-            ]
+
+                object.aperture_type as u32,
+                object.aperture_width.to_bits(),
+                object.aperture_height.to_bits(),
+                object.slit_count,
+                object.slit_pitch.to_bits(),
+
+                object.grating_type as u32,
+                object.groove_density.to_bits(),
+                object.blaze_angle.to_bits(),
+
+                object.polarizer_type as u32,
+                object.polarizer_angle.to_bits(),
+
+                object.birefringence.to_bits(),
+                object.birefringence_dispersion.to_bits(),
+                object.retarder_thickness.to_bits(),
+
+                object.apex_angle.to_bits(),
+
+                object.optic_axis[0].to_bits(),
+                object.optic_axis[1].to_bits(),
+                object.displacer_length.to_bits(),
+
+                object.etalon_reflectance.to_bits(),
+                object.etalon_gap.to_bits(),
+                object.etalon_index.to_bits(),
+
+                object.topological_charge.to_bits(),
+                object.spatial_retardance.to_bits(),
+                element_map_layer,
+                element_map_channels,
+
+                object.stress_field as u32,
+                object.applied_load.to_bits(),
+                object.stress_optic_coefficient.to_bits(),
+                object.sample_size.to_bits(),
+                object.sample_thickness.to_bits(),
+
+                object.material_wavelength.to_bits(),
+            ];
+
+            definition.into_iter().chain(coating).chain(material).chain(extraordinary_material)
         }).collect()
     }
 }
@@ -375,14 +766,117 @@ impl WorldObject {
             polarization_type: LightPolarizationType::LinearHorizontal,
 
             wavelength: 0.1,
+            material_wavelength: 0.55,
 
             aligned_to_object: 0,
             alignment: Alignment::FRONT,
             aligned_distance: 0.0,
-            object_aligned_to_self: 0
+            object_aligned_to_self: 0,
+
+            material: MaterialName::new("BK7"),
+            coating: Coating::new(),
+
+            aperture_type: ApertureType::MultipleSlits,
+            aperture_width: 0.1,
+            aperture_height: 0.8,
+            slit_count: 2,
+            slit_pitch: 0.3,
+
+            polarizer_type: PolarizerType::LinearHorizontal,
+            polarizer_angle: 0.0,
+            relative_phase_retardation: 0.0,
+            circularity: 0.0,
+            optical_density: 1.0,
+            absorption_coefficient: 0.5,
+            slab_thickness: 1.0,
+            principal_transmittance: 0.9,
+            extinction_ratio: 1000.0,
+            specific_rotation: 66.5,
+            concentration: 0.5,
+            rotator_path_length: 1.0,
+            verdet_constant: 40.0,
+            magnetic_field: 1.0,
+            faraday_length: 0.02,
+            // crystalline quartz, a zero-order quarter-wave plate at 0.55 micrometers
+            birefringence: 0.0087,
+            birefringence_dispersion: 0.00014,
+            retarder_thickness: 15.0,
+            retarder_design_wavelength: 0.55,
+            retarder_waves: 0.25,
+            retarder_order: 0,
+            // air spaced, a free spectral range of about 15 nm in the visible
+            etalon_reflectance: 0.9,
+            etalon_gap: 10.0,
+            etalon_index: 1.0,
+            // half-wave q-plate with q = 1/2, makes radial polarization out of horizontal
+            topological_charge: 0.5,
+            spatial_retardance: PI,
+            // a polycarbonate disk a couple of fringes deep at its center
+            stress_field: StressField::DiskCompression,
+            applied_load: 500.0,
+            stress_optic_coefficient: 50.0,
+            sample_size: 50.0,
+            sample_thickness: 6.0,
+            // a half-wave cell that stops retarding at a few volts and a transverse
+            // lithium niobate modulator
+            lc_max_retardance: PI,
+            lc_threshold_voltage: 1.0,
+            lc_voltage_scale: 1.0,
+            half_wave_voltage: 250.0,
+            drive_waveform: DriveWaveform::Constant,
+            drive_offset: 0.0,
+            drive_amplitude: 0.0,
+            drive_frequency: 0.5,
+
+            detector_pixels: [64, 64],
+
+            grating_type: GratingType::Transmission,
+            groove_density: 20.0,
+            blaze_angle: 0.0,
+
+            apex_angle: PI / 3.0,
+
+            // cut for the biggest walk-off in calcite
+            optic_axis: [PI / 4.0, 0.0],
+            extraordinary_material: MaterialName::new("Calcite (extraordinary)"),
+            displacer_length: 2.0
         }
     }
 
+    // cubes around the center of the object that it could be touching
+    pub fn footprint_radius(&self) -> u32 {
+        match self.object_type {
+            ObjectType::Prism => prism_bounding_radius(self).ceil() as u32 + 1,
+            ObjectType::BeamDisplacer => displacer_bounding_radius(self).ceil() as u32 + 1,
+            _ => self.radius as u32 + 1
+        }
+    }
+
+    // main.frag draws round objects (and lets light hit them) out to twice their
+    // radius, must match drawn_radius in main.frag
+    pub fn drawn_radius(&self) -> f32 {
+        self.radius * 2.0
+    }
+
+    // horizontal axis, normal and vertical axis of a round/square object, rotated
+    // the same way main.frag rotates them
+    pub fn local_axes(&self) -> [Vector3<f32>; 3] {
+        [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0)
+        ].map(|axis| rotate3d_y(rotate3d_x(axis, self.rotation[1]), self.rotation[0]))
+    }
+
+    // unit vector along the optic axis of a beam displacer
+    pub fn optic_axis_direction(&self) -> Vector3<f32> {
+        let [horizontal, normal, vertical] = self.local_axes();
+        let (sin, cos) = self.optic_axis[0].sin_cos();
+        let (azimuth_sin, azimuth_cos) = self.optic_axis[1].sin_cos();
+
+        normal * cos + (horizontal * azimuth_cos + vertical * azimuth_sin) * sin
+    }
+
     pub fn update_object_aligned_position(&mut self, aligned_to_object: &WorldObject) {
         let mut ray_dir: Vector3<f32>;
 
@@ -411,6 +905,26 @@ impl WorldObject {
         self.center[2] = self.center[2].clamp(1.0, 24.0);
     }
 
+    // Δn(λ) of a chromatic retarder, λ in micrometers
+    pub fn birefringence_at(&self, wavelength: f32) -> f32 {
+        self.birefringence + self.birefringence_dispersion / wavelength.powi(2)
+    }
+
+    // retardance of a chromatic retarder in radians
+    pub fn retardance_at(&self, wavelength: f32) -> f32 {
+        2.0 * PI * self.birefringence_at(wavelength) * self.retarder_thickness / wavelength
+    }
+
+    // thickness that gives the design retardance at the design wavelength
+    pub fn retarder_design_thickness(&self) -> f32 {
+        (self.retarder_order as f32 + self.retarder_waves) * self.retarder_design_wavelength / self.birefringence_at(self.retarder_design_wavelength)
+    }
+
+    // R(θ) diag(e^(-iΓ/2), e^(iΓ/2)) R(-θ) with the fast axis at θ, mirrors main.frag
+    pub fn chromatic_retarder_jones_matrix(&self, wavelength: f32) -> Matrix2<Complex<f32>> {
+        retarder_jones_matrix(self.retardance_at(wavelength), self.polarizer_angle)
+    }
+
     pub fn set_light_polarization(&mut self) {
         let type_of_object = self.polarization_type;
         match type_of_object {
@@ -441,10 +955,17 @@ impl WorldObject {
             LightPolarizationType::CircularLeftHand => {
                 self.polarization = Vector2::new(Complex::new(1.0, 0.0), Complex::new(0.0, 1.0)).map(|x| x * (1.0 / (2.0).sqrt()));
             },
+
+            LightPolarizationType::Custom => {}
         }
     }
 
     pub fn set_jones_matrix(&mut self, type_of_object: PolarizerType, angle: f32, relative_phase_retardation: f32, circularity: f32) {
+        self.polarizer_type = type_of_object;
+        self.polarizer_angle = angle;
+        self.relative_phase_retardation = relative_phase_retardation;
+        self.circularity = circularity;
+
         match type_of_object {
             PolarizerType::LinearHorizontal => {
                 self.jones_matrix = Matrix2::new(
@@ -462,8 +983,8 @@ impl WorldObject {
 
             PolarizerType::Linear45Degrees => {
                 self.jones_matrix = Matrix2::new(
-                    Complex::new(1.0, 0.0), Complex::new(0.0, 0.0),
-                    Complex::new(0.0, 0.0), Complex::new(0.0, 0.0)
+                    Complex::new(1.0, 0.0), Complex::new(1.0, 0.0),
+                    Complex::new(1.0, 0.0), Complex::new(1.0, 0.0)
                 ).map(|x| x * 0.5)
             }
 
@@ -549,6 +1070,104 @@ impl WorldObject {
                      angle.sin().powi(2)          + (e_to_the_in * angle.cos().powi(2)),
                 ).map(|x| x * Complex::new(0.0, -PI / 2.0).exp())
             }
+
+            // these only take away light, the jones matrix has the square root
+            // of the intensity transmittance
+            PolarizerType::NeutralDensityFilter => {
+                let amplitude_transmittance = 10f32.powf(-self.optical_density / 2.0);
+
+                self.jones_matrix = Matrix2::identity().map(|x: Complex<f32>| x * amplitude_transmittance)
+            }
+
+            PolarizerType::AbsorbingSlab => {
+                let amplitude_transmittance = (-self.absorption_coefficient * self.slab_thickness / 2.0).exp();
+
+                self.jones_matrix = Matrix2::identity().map(|x: Complex<f32>| x * amplitude_transmittance)
+            }
+
+            // the rotation of optically active media follows the direction of propagation,
+            // this is the matrix for light going along the normal of the object, main.frag
+            // transposes it when the light goes the other way just like any other element
+            PolarizerType::OpticalRotator => {
+                let rotation = (self.specific_rotation * self.concentration * self.rotator_path_length).to_radians();
+
+                self.jones_matrix = rotation_jones_matrix(rotation)
+            }
+
+            // the rotation of faraday rotators follows the magnetic field instead, so main.frag
+            // uses the same matrix for both directions and that's what makes it non-reciprocal
+            PolarizerType::FaradayRotator => {
+                self.jones_matrix = rotation_jones_matrix(self.verdet_constant * self.magnetic_field * self.faraday_length)
+            }
+
+            // the retardance depends on the wavelength of each light, main.frag computes
+            // the matrix for every light, this one is just the one at the design wavelength
+            PolarizerType::ChromaticRetarder => {
+                self.jones_matrix = self.chromatic_retarder_jones_matrix(self.retarder_design_wavelength)
+            }
+
+            // same for etalons, the transmission depends on the wavelength and on the angle
+            // of each light, this one is for green light (0.55 micrometers) at normal incidence
+            PolarizerType::FabryPerotEtalon => {
+                let transmission = etalon_transmission(self, 0.55, 1.0);
+
+                self.jones_matrix = Matrix2::identity().map(|x: Complex<f32>| x * transmission)
+            }
+
+            // these change across the disk, main.frag computes the matrix at every point
+            // (see spatial.rs), this one is the one at azimuth 0 without any maps
+            PolarizerType::QPlate | PolarizerType::ImageElement => {
+                self.jones_matrix = retarder_jones_matrix(self.spatial_retardance, angle)
+            }
+
+            PolarizerType::VortexRetarder => {
+                self.jones_matrix = retarder_jones_matrix(PI, angle)
+            }
+
+            // the one at the center of the sample for the design wavelength of chromatic retarders
+            PolarizerType::PhotoelasticSample => {
+                self.jones_matrix = local_jones_matrix(self, 0.0, 0.0, None)
+            }
+
+            // the retardance follows the voltage of the drive, World::drive_electro_optic_elements
+            // updates it every frame, this one is the one at time 0
+            PolarizerType::LiquidCrystalCell | PolarizerType::PockelsCell => {
+                self.jones_matrix = retarder_jones_matrix(electro_optic_retardance(self, drive_voltage(self, 0.0)), angle)
+            }
+
+            // R(θ) diag(√k1, √k2) R(-θ), an ideal polarizer is k1 = 1 and k2 = 0
+            PolarizerType::PartialLinearPolarizerTheta => {
+                let p1 = self.principal_transmittance.sqrt();
+                let p2 = (self.principal_transmittance / self.extinction_ratio).sqrt();
+
+                self.jones_matrix = Matrix2::new(
+                    Complex::new(p1 * angle.cos().powi(2) + p2 * angle.sin().powi(2), 0.0), Complex::new((p1 - p2) * angle.cos() * angle.sin(), 0.0),
+                    Complex::new((p1 - p2) * angle.cos() * angle.sin(), 0.0),               Complex::new(p1 * angle.sin().powi(2) + p2 * angle.cos().powi(2), 0.0)
+                )
+            }
         }
     }
 }
+
+// linear retarder with its fast axis at `fast_axis` radians from the horizontal axis,
+// R(θ) diag(e^(-iΓ/2), e^(iΓ/2)) R(-θ)
+pub fn retarder_jones_matrix(retardance: f32, fast_axis: f32) -> Matrix2<Complex<f32>> {
+    let (sin, cos) = fast_axis.sin_cos();
+
+    let fast = Complex::new(0.0, -retardance / 2.0).exp();
+    let slow = Complex::new(0.0, retardance / 2.0).exp();
+    let off_diagonal = (fast - slow) * sin * cos;
+
+    Matrix2::new(
+        fast * cos.powi(2) + slow * sin.powi(2), off_diagonal,
+        off_diagonal,                            fast * sin.powi(2) + slow * cos.powi(2)
+    )
+}
+
+// rotates linear polarization by `angle` radians
+pub fn rotation_jones_matrix(angle: f32) -> Matrix2<Complex<f32>> {
+    Matrix2::new(
+        Complex::new(angle.cos(), 0.0), Complex::new(-angle.sin(), 0.0),
+        Complex::new(angle.sin(), 0.0), Complex::new( angle.cos(), 0.0)
+    )
+}